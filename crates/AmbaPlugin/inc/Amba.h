@@ -7,6 +7,7 @@
 #include <compare>
 #include <optional>
 #include <functional>
+#include <vector>
 
 #include "Numbers.h"
 #include "Zydis.h"
@@ -43,9 +44,18 @@ using StateMergeFunction = void (
 	s2e::S2EExecutionState *
 );
 using StateKillFunction = void (s2e::S2EExecutionState *);
+using SymbolicVariableFunction = void (
+	s2e::S2EExecutionState *,
+	const std::string &,
+	const std::vector<klee::ref<klee::Expr>> &,
+	const klee::ArrayPtr &
+);
 using TimerFunction = void ();
+using ProcessForkFunction = void (bool, bool, unsigned);
 using ModuleFunction = void (s2e::S2EExecutionState *, const s2e::ModuleDescriptor &);
 using ProcessFunction = void (s2e::S2EExecutionState *, const u64, const u64, const u64);
+using ProcessSignalFunction = void (s2e::S2EExecutionState *, u64, u64);
+using ProcessTrapFunction = void (s2e::S2EExecutionState *, u64, u64, int);
 
 struct AddressLengthPair {
 	target_phys_addr_t adr;
@@ -66,6 +76,8 @@ std::array<uint8_t, MAX_INSTRUCTION_LENGTH> readConstantMemory(S2EExecutionState
 
 zydis::Instruction readInstruction(s2e::S2EExecutionState *state, u64 pc);
 
+std::vector<u8> readConcretizedMemory(s2e::S2EExecutionState *state, u64 address, u64 len);
+
 bool isStackAddress(const CPUX86State &state, target_phys_addr_t adr);
 
 u64 readRegister(const CPUX86State &state, const ZydisRegister reg);
@@ -1,12 +1,19 @@
 #pragma once
 
 #include <s2e/S2EExecutionState.h>
+#include <s2e/Plugins/Core/BaseInstructions.h>
 #include <klee/Searcher.h>
 
 #include <memory>
 #include <atomic>
+#include <functional>
+#include <fstream>
+#include <string>
+#include <unordered_map>
 #include <thread>
 #include <mutex>
+#include <utility>
+#include <vector>
 
 #include "Amba.h"
 #include "HeapLeak.h"
@@ -18,7 +25,54 @@
 namespace s2e {
 namespace plugins {
 
-class AmbaPlugin : public Plugin {
+// Commands sent by bootstrap through `s2e_invoke_plugin`, see
+// `crates/bootstrap/src/s2e.rs`
+enum class GuestCommandKind : u64 {
+	ProgramOutput = 1,
+	ProgramExit = 2,
+	TraceProcess = 3,
+};
+
+struct GuestProgramOutput {
+	GuestCommandKind kind;
+	u64 stdout_ptr;
+	u64 stdout_len;
+	u64 stderr_ptr;
+	u64 stderr_len;
+};
+
+struct GuestProgramExit {
+	GuestCommandKind kind;
+	// Nonzero if `status` is the signal that terminated the program rather
+	// than its exit code
+	u64 signalled;
+	i64 status;
+};
+
+struct GuestTraceProcess {
+	GuestCommandKind kind;
+	u64 pid;
+};
+
+// Cloned into both states whenever a state forks
+class AmbaPluginState : public PluginState {
+  public:
+	// The process bootstrap runs the analyzed program in, which differs
+	// between states since bootstrap spawns it in each state of its own.
+	// Setup and teardown commands may run the program too, but are not
+	// traced.
+	u64 traced_pid = 0;
+
+	AmbaPluginState *clone() const override {
+		return new AmbaPluginState(*this);
+	}
+
+	static PluginState *factory(Plugin *, S2EExecutionState *) {
+		return new AmbaPluginState();
+	}
+};
+
+class AmbaPlugin : public Plugin, public IPluginInvoker {
 	S2E_PLUGIN
   public:
 	explicit AmbaPlugin(S2E *s2e);
@@ -29,24 +83,57 @@ class AmbaPlugin : public Plugin {
 	amba::TranslationFunction translateInstructionStart;
 	amba::TranslationFunction translateBlockStart;
 	amba::TranslationCompleteFunction translateBlockComplete;
-	amba::ModuleFunction onModuleLoad;
-	amba::ModuleFunction onModuleUnload;
 	amba::ProcessFunction onProcessUnload;
 	amba::TimerFunction onTimer;
 	amba::TimerFunction onEngineShutdown;
+	amba::ProcessForkFunction onProcessFork;
 	amba::StateKillFunction onStateKill;
 	amba::StateMergeFunction onStateSwitch;
+	amba::SymbolicVariableFunction onSymbolicVariableCreation;
+	amba::ProcessSignalFunction onSegFault;
+	amba::ProcessTrapFunction onTrap;
+
+	void handleOpcodeInvocation(
+		S2EExecutionState *state,
+		u64 guestDataPtr,
+		u64 guestDataSize
+	) override;
 
   protected:
-	Ipc *const m_ipc;
+	bool isTraced(S2EExecutionState *state, const ModuleDescriptor *module) const;
+	void startIpcReceiver();
+	void sendStateTermination(
+		S2EExecutionState *state,
+		StateTerminationKind kind,
+		i32 value
+	);
+	std::string terminateMessage(S2EExecutionState *state);
+	void sendWithStateIdAmba(
+		S2EExecutionState *state,
+		std::function<void(u32, i32)> send
+	);
+
+	// Replaced in every process that S2E forks, see `onProcessFork`
+	Ipc *m_ipc;
 	ModuleMap *m_modules = nullptr;
 	std::string m_module_path = "";
-	u64 m_module_pid = 0;
+	// The inclusive byte ranges allowed by each constrained symbolic range,
+	// see `recipe::symbolic_constrained_guest_path`
+	std::vector<std::vector<std::pair<u8, u8>>> m_byte_constraints;
 	std::atomic<bool> m_alive = true;
 	std::atomic<klee::Searcher *> m_next_searcher = nullptr;
 
 	std::mutex m_dead_states_lock;
 	std::unordered_set<i32> m_dead_states;
+	// States whose termination has been sent, by guid
+	std::unordered_set<i32> m_terminated_states;
+	// S2E's log, read as far as `terminateMessage` has needed
+	std::ifstream m_s2e_log;
+	// The messages S2E's executor logged when killing states, by state id
+	std::unordered_map<i32, std::string> m_terminate_messages;
+	// Messages about states without an amba id yet, sent once they have one,
+	// see `sendWithStateIdAmba`
+	std::vector<std::pair<control_flow::StateIdS2E, std::function<void(u32, i32)>>> m_pending_state_messages;
 	std::jthread m_ipc_receiver_thread;
 	heap_leak::HeapLeak m_heap_leak;
 	assembly_graph::AssemblyGraph m_assembly_graph;
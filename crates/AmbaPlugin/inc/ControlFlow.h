@@ -3,6 +3,7 @@
 #include <memory>
 #include <s2e/S2EExecutionState.h>
 
+#include <optional>
 #include <unordered_map>
 #include <vector>
 
@@ -58,6 +59,7 @@ class ControlFlow {
 
   protected:
 	StateIdAmba getStateIdAmba(StateIdS2E);
+	std::optional<StateIdAmba> findStateIdAmba(StateIdS2E) const;
 	void incrementStateIdAmba(StateIdS2E);
 
 	const std::string m_name;
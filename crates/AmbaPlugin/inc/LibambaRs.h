@@ -31,9 +31,34 @@ struct NodeMetadataFFIPair {
 	NodeMetadataFFI snd;
 };
 
+// How a state ended, see `ipc::StateTermination`
+enum class StateTerminationKind : u32 {
+	Exited = 0,
+	Signalled = 1,
+	Killed = 2,
+};
+
+// See `ipc::KillReason`
+enum class KillReason : i32 {
+	Stopped = 0,
+	Unknown = 1,
+	ForkLimit = 2,
+	Timeout = 3,
+	SolverFailure = 4,
+};
+
 extern "C" {
 	Ipc *rust_new_ipc();
 	void rust_free_ipc(Ipc *ptr);
+	// `parent_pid` and `first_own_state_id` are only read if `forked`, see
+	// `ipc::ForkOrigin`
+	void rust_ipc_send_hello(
+		Ipc *ipc,
+		u32 pid,
+		bool forked,
+		u32 parent_pid,
+		u32 first_own_state_id
+	);
 	void rust_ipc_send_edges(
 		Ipc *ipc,
 		const NodeMetadataFFIPair *state_data,
@@ -41,5 +66,26 @@ extern "C" {
 		const NodeMetadataFFIPair *block_data,
 		u64 block_len
 	);
+	void rust_ipc_send_state_output(
+		Ipc *ipc,
+		u32 amba_state_id,
+		i32 s2e_state_id,
+		const u8 *stdout_data,
+		u64 stdout_len,
+		const u8 *stderr_data,
+		u64 stderr_len
+	);
+	// `value` is the exit code, the signal or the `KillReason` depending
+	// on `kind`
+	void rust_ipc_send_state_termination(
+		Ipc *ipc,
+		u32 amba_state_id,
+		i32 s2e_state_id,
+		StateTerminationKind kind,
+		i32 value
+	);
+	// The reason behind the message S2E's executor logged when killing a
+	// state, see `ipc::KillReason::from_terminate_message`
+	KillReason rust_kill_reason(const u8 *message, u64 message_len);
 	bool rust_ipc_receive_message(Ipc *ipc, std::vector<i32> *vec);
 }
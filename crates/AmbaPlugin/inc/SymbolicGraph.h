@@ -1,5 +1,6 @@
 #pragma once
 
+#include <optional>
 #include <string>
 
 #include "ControlFlow.h"
@@ -14,6 +15,10 @@ class SymbolicGraph : public control_flow::ControlFlow {
 
 	amba::SymbolicExecutionFunction onStateFork;
 	amba::StateMergeFunction onStateMerge;
+
+	// None until the state is part of the state graph, which the initial
+	// state is only once it first forks
+	std::optional<StateIdAmba> stateIdAmba(StateIdS2E) const;
 };
 
 }
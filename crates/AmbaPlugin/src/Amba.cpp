@@ -74,6 +74,22 @@ zydis::Instruction readInstruction(s2e::S2EExecutionState *state, u64 pc) {
 	return DECODER.decode(SPAN(mem));
 }
 
+// Read memory that may be symbolic, using the concrete values of the current
+// path for symbolic bytes
+std::vector<u8> readConcretizedMemory(s2e::S2EExecutionState *state, u64 address, u64 len) {
+	auto mem = state->mem();
+
+	std::vector<u8> ret;
+	ret.reserve(len);
+	for (u64 i = 0; i < len; i++) {
+		auto expr = state->concolics->evaluate(mem->read(address + i));
+		auto constant = klee::dyn_cast<klee::ConstantExpr>(expr);
+		AMBA_ASSERT(constant);
+		ret.push_back((u8) constant->getLimitedValue(0xFF));
+	}
+	return ret;
+}
+
 bool isStackAddress(const CPUX86State &state, target_phys_addr_t adr) {
 	const auto sp = state.regs[6];
 	// https://stackoverflow.com/questions/1825964/c-c-maximum-stack-size-of-program-on-mainstream-oses
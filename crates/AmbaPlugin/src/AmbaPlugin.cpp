@@ -3,9 +3,16 @@
 #include <s2e/Utils.h>
 #include <s2e/Plugins/OSMonitors/Support/ModuleMap.h>
 #include <s2e/Plugins/OSMonitors/OSMonitor.h>
+#include <s2e/Plugins/OSMonitors/Linux/LinuxMonitor.h>
 #include <klee/Searcher.h>
+#include <klee/Expr.h>
 
+#include <algorithm>
 #include <memory.h>
+#include <unistd.h>
+#include <csignal>
+#include <cstdlib>
+#include <string>
 #include <thread>
 
 // Our headers
@@ -28,6 +35,7 @@ AmbaPlugin::AmbaPlugin(S2E *s2e)
 	amba::debug_stream = [=](){ return &self->getDebugStream(); };
 	amba::info_stream = [=](){ return &self->getInfoStream(); };
 	amba::warning_stream = [=](){ return &self->getWarningsStream(); };
+	rust_ipc_send_hello(this->m_ipc, (u32) getpid(), false, 0, 0);
 }
 
 AmbaPlugin::~AmbaPlugin() {
@@ -62,6 +70,30 @@ void AmbaPlugin::initialize() {
 		<< this->m_module_path
 		<< '\n';
 
+	const auto byte_constraints_key = this->getConfigKey() + ".byte_constraints";
+	const int constraint_count = s2e->getConfig()->getListSize(byte_constraints_key, &ok);
+	for (int i = 1; ok && i <= constraint_count; i++) {
+		const auto allowed_key = byte_constraints_key + "[" + std::to_string(i) + "]";
+		const int allowed_count = s2e->getConfig()->getListSize(allowed_key, &ok);
+		std::vector<std::pair<u8, u8>> allowed;
+		for (int j = 1; ok && j <= allowed_count; j++) {
+			const auto range_key = allowed_key + "[" + std::to_string(j) + "]";
+			const auto lo = s2e->getConfig()->getInt(range_key + "[1]", 0, &ok);
+			const auto hi = s2e->getConfig()->getInt(range_key + "[2]", 0, &ok);
+			allowed.push_back({ (u8) lo, (u8) hi });
+		}
+		this->m_byte_constraints.push_back(allowed);
+	}
+	if (!ok) {
+		*amba::warning_stream()
+			<< "Malformed `byte_constraints` in the lua config, "
+			<< "symbolic ranges will not be constrained\n";
+		this->m_byte_constraints.clear();
+	}
+
+	// See `terminateMessage`
+	this->m_s2e_log.open(s2e->getOutputFilename("debug.txt"));
+
 	// Set up event callbacks
 	core.onTranslateInstructionStart
 		.connect(sigc::mem_fun(
@@ -113,32 +145,52 @@ void AmbaPlugin::initialize() {
 			*this,
 			&AmbaPlugin::onEngineShutdown
 		));
-	core.onStateSwitch
+	core.onProcessFork
 		.connect(sigc::mem_fun(
 			*this,
-			&AmbaPlugin::onStateSwitch
+			&AmbaPlugin::onProcessFork
 		));
-
-	monitor->onModuleLoad
+	core.onStateSwitch
 		.connect(sigc::mem_fun(
 			*this,
-			&AmbaPlugin::onModuleLoad
+			&AmbaPlugin::onStateSwitch
 		));
-	monitor->onModuleUnload
+	core.onSymbolicVariableCreation
 		.connect(sigc::mem_fun(
 			*this,
-			&AmbaPlugin::onModuleUnload
+			&AmbaPlugin::onSymbolicVariableCreation
 		));
+
 	monitor->onProcessUnload
 		.connect(sigc::mem_fun(
 			*this,
 			&AmbaPlugin::onProcessUnload
 		));
+	// LinuxMonitor kills states on segfaults and traps before bootstrap can
+	// report how the analyzed program ended
+	if (auto linux_monitor = dynamic_cast<LinuxMonitor *>(monitor)) {
+		linux_monitor->onSegFault
+			.connect(sigc::mem_fun(
+				*this,
+				&AmbaPlugin::onSegFault
+			));
+		linux_monitor->onTrap
+			.connect(sigc::mem_fun(
+				*this,
+				&AmbaPlugin::onTrap
+			));
+	}
+
+	this->startIpcReceiver();
+	*amba::debug_stream() << "Finished initializing AmbaPlugin\n";
+}
 
+void AmbaPlugin::startIpcReceiver() {
 	auto self = this;
+	auto ipc = this->m_ipc;
 	this->m_ipc_receiver_thread = std::jthread([=]() {
 		state_prioritisation::ipcReceiver(
-			self->m_ipc,
+			ipc,
 			&self->m_alive,
 			self->s2e(),
 			&self->m_dead_states_lock,
@@ -146,7 +198,6 @@ void AmbaPlugin::initialize() {
 			&self->m_next_searcher
 		);
 	});
-	*amba::debug_stream() << "Finished initializing AmbaPlugin\n";
 }
 
 void AmbaPlugin::onStateKill(S2EExecutionState *state) {
@@ -158,6 +209,13 @@ void AmbaPlugin::onStateKill(S2EExecutionState *state) {
 	this->m_dead_states.insert(state->getGuid());
 	this->m_dead_states_lock.unlock();
 
+	const auto message = this->terminateMessage(state);
+	this->sendStateTermination(
+		state,
+		StateTerminationKind::Killed,
+		(i32) rust_kill_reason((const u8 *) message.data(), (u64) message.size())
+	);
+
 	auto &s2e = *this->s2e();
 	auto &executor = *s2e.getExecutor();
 
@@ -169,6 +227,74 @@ void AmbaPlugin::onStateKill(S2EExecutionState *state) {
 	}
 }
 
+// Send how `state` ended, unless that has already been sent
+void AmbaPlugin::sendStateTermination(
+	S2EExecutionState *state,
+	StateTerminationKind kind,
+	i32 value
+) {
+	if (!this->m_terminated_states.insert(state->getGuid()).second) {
+		return;
+	}
+	this->sendWithStateIdAmba(state, [=, this](u32 amba_state_id, i32 s2e_state_id) {
+		rust_ipc_send_state_termination(
+			this->m_ipc,
+			amba_state_id,
+			s2e_state_id,
+			kind,
+			value
+		);
+	});
+}
+
+// Call `send` with the amba and S2E ids of `state`. A state only gets an amba
+// id once it is part of the state graph, so until then `send` waits for
+// `onTimer`, rather than giving the state an id the graph never uses.
+void AmbaPlugin::sendWithStateIdAmba(
+	S2EExecutionState *state,
+	std::function<void(u32, i32)> send
+) {
+	const auto s2e_state_id = control_flow::getStateIdS2E(state);
+	const auto amba_state_id = this->m_symbolic_graph.stateIdAmba(s2e_state_id);
+	if (!amba_state_id) {
+		this->m_pending_state_messages.push_back({ s2e_state_id, std::move(send) });
+		return;
+	}
+	send((u32) amba_state_id->val, (i32) s2e_state_id.val);
+}
+
+// S2E does not tell plugins why it kills a state. Its executor only logs the
+// message it terminates the state with, right before `onStateKill`, so look
+// for it in the lines logged since the last kill. Empty if there is none.
+std::string AmbaPlugin::terminateMessage(S2EExecutionState *state) {
+	auto &s2e = *this->s2e();
+	s2e.getWarningsStream().flush();
+	s2e.getInfoStream().flush();
+	s2e.getDebugStream().flush();
+
+	const std::string state_marker = "[State ";
+	const std::string terminate_marker = "Terminating state";
+	std::string line;
+	while (std::getline(this->m_s2e_log, line)) {
+		const auto state_at = line.find(state_marker);
+		const auto terminate_at = line.find(terminate_marker);
+		if (state_at == std::string::npos || terminate_at == std::string::npos) {
+			continue;
+		}
+		const auto id = (i32) std::strtol(
+			line.c_str() + state_at + state_marker.size(),
+			nullptr,
+			10
+		);
+		this->m_terminate_messages[id] = line.substr(terminate_at + terminate_marker.size());
+	}
+	// Continue from the end of the log once S2E writes more
+	this->m_s2e_log.clear();
+
+	const auto message = this->m_terminate_messages.extract(state->getID());
+	return message.empty() ? "" : message.mapped();
+}
+
 void AmbaPlugin::onStateSwitch(
 	S2EExecutionState *from,
 	S2EExecutionState *to
@@ -184,6 +310,161 @@ void AmbaPlugin::onStateSwitch(
 	}
 }
 
+void AmbaPlugin::handleOpcodeInvocation(
+	S2EExecutionState *state,
+	u64 guestDataPtr,
+	u64 guestDataSize
+) {
+	GuestCommandKind kind;
+	if (guestDataSize < sizeof(kind) || !state->mem()->read(guestDataPtr, &kind, sizeof(kind))) {
+		*amba::warning_stream() << "Could not read guest command\n";
+		return;
+	}
+
+	switch (kind) {
+	case GuestCommandKind::ProgramOutput: {
+		GuestProgramOutput output;
+		if (
+			guestDataSize != sizeof(output)
+			|| !state->mem()->read(guestDataPtr, &output, sizeof(output))
+		) {
+			*amba::warning_stream() << "Could not read guest program output\n";
+			return;
+		}
+		auto stdout_bytes = amba::readConcretizedMemory(state, output.stdout_ptr, output.stdout_len);
+		auto stderr_bytes = amba::readConcretizedMemory(state, output.stderr_ptr, output.stderr_len);
+		this->sendWithStateIdAmba(state, [
+			this,
+			stdout_bytes = std::move(stdout_bytes),
+			stderr_bytes = std::move(stderr_bytes)
+		](u32 amba_state_id, i32 s2e_state_id) {
+			rust_ipc_send_state_output(
+				this->m_ipc,
+				amba_state_id,
+				s2e_state_id,
+				stdout_bytes.data(),
+				(u64) stdout_bytes.size(),
+				stderr_bytes.data(),
+				(u64) stderr_bytes.size()
+			);
+		});
+		break;
+	}
+	case GuestCommandKind::ProgramExit: {
+		GuestProgramExit exit;
+		if (
+			guestDataSize != sizeof(exit)
+			|| !state->mem()->read(guestDataPtr, &exit, sizeof(exit))
+		) {
+			*amba::warning_stream() << "Could not read guest program exit\n";
+			return;
+		}
+		this->sendStateTermination(
+			state,
+			exit.signalled
+				? StateTerminationKind::Signalled
+				: StateTerminationKind::Exited,
+			(i32) exit.status
+		);
+		break;
+	}
+	case GuestCommandKind::TraceProcess: {
+		GuestTraceProcess trace;
+		if (
+			guestDataSize != sizeof(trace)
+			|| !state->mem()->read(guestDataPtr, &trace, sizeof(trace))
+		) {
+			*amba::warning_stream() << "Could not read guest process to trace\n";
+			return;
+		}
+		DECLARE_PLUGINSTATE(AmbaPluginState, state);
+		plgState->traced_pid = trace.pid;
+		break;
+	}
+	default:
+		*amba::warning_stream()
+			<< "Unknown guest command "
+			<< (u64) kind
+			<< '\n';
+	}
+}
+
+void AmbaPlugin::onSymbolicVariableCreation(
+	S2EExecutionState *state,
+	const std::string &name,
+	const std::vector<klee::ref<klee::Expr>> &bytes,
+	const klee::ArrayPtr &array
+) {
+	// Constrained symbolic ranges are files made symbolic by bootstrap, see
+	// `recipe::symbolic_constrained_guest_path`.
+	const std::string constrained_marker = "amba_constrained_";
+	const auto constrained = name.find(constrained_marker);
+	if (constrained != std::string::npos) {
+		const auto index = std::strtoull(
+			name.c_str() + constrained + constrained_marker.size(),
+			nullptr,
+			10
+		);
+		if (index >= this->m_byte_constraints.size()) {
+			*amba::warning_stream()
+				<< "No byte constraint for "
+				<< name
+				<< '\n';
+			return;
+		}
+		const auto &allowed = this->m_byte_constraints[index];
+		for (const auto &byte : bytes) {
+			klee::ref<klee::Expr> constraint = klee::ConstantExpr::create(0, klee::Expr::Bool);
+			for (const auto &[lo, hi] : allowed) {
+				constraint = klee::OrExpr::create(
+					constraint,
+					klee::AndExpr::create(
+						klee::UleExpr::create(
+							klee::ConstantExpr::create(lo, byte->getWidth()),
+							byte
+						),
+						klee::UleExpr::create(
+							byte,
+							klee::ConstantExpr::create(hi, byte->getWidth())
+						)
+					)
+				);
+			}
+			if (!state->addConstraint(constraint, true)) {
+				*amba::warning_stream()
+					<< "Failed constraining "
+					<< name
+					<< " to its allowed bytes\n";
+			}
+		}
+		return;
+	}
+
+	// Symbolic arguments and environment variables are files made symbolic
+	// by bootstrap, see `recipe::symbolic_argument_guest_path`. They are
+	// passed to the analyzed program as NUL-terminated strings, so every
+	// byte must be nonzero. The allowed bytes of constrained ranges within
+	// them already exclude zero.
+	if (
+		name.find("amba_argv_") == std::string::npos
+		&& name.find("amba_envp_") == std::string::npos
+	) {
+		return;
+	}
+	for (const auto &byte : bytes) {
+		const auto nonzero = klee::NeExpr::create(
+			byte,
+			klee::ConstantExpr::create(0, byte->getWidth())
+		);
+		if (!state->addConstraint(nonzero, true)) {
+			*amba::warning_stream()
+				<< "Failed constraining "
+				<< name
+				<< " to be nonzero\n";
+		}
+	}
+}
+
 void AmbaPlugin::translateInstructionStart(
 	ExecutionSignal *signal,
 	S2EExecutionState *state,
@@ -220,7 +501,7 @@ void AmbaPlugin::translateBlockStart(
 	u64 pc
 ) {
 	auto mod = this->m_modules->getModule(state);
-	if (mod.get() == nullptr || mod->Path != this->m_module_path) {
+	if (!this->isTraced(state, mod.get())) {
 		return;
 	}
 
@@ -235,31 +516,21 @@ void AmbaPlugin::translateBlockComplete(
 	u64 final_instruction_pc
 ) {
 	auto mod = this->m_modules->getModule(state);
-	if (mod.get() == nullptr || mod->Path != this->m_module_path) {
+	if (!this->isTraced(state, mod.get())) {
 		return;
 	}
 
 	this->m_assembly_graph.translateBlockComplete(state, tb, final_instruction_pc);
 }
 
-void AmbaPlugin::onModuleLoad(
-	S2EExecutionState *state,
-	const ModuleDescriptor &module
-) {
-	if (module.Path != this->m_module_path) {
-		return;
-	}
-
-	this->m_module_pid = module.Pid;
-}
-
-void AmbaPlugin::onModuleUnload(
+bool AmbaPlugin::isTraced(
 	S2EExecutionState *state,
-	const ModuleDescriptor &module
-) {
-	if (module.Path == this->m_module_path) {
-		this->m_module_pid = 0;
-	}
+	const ModuleDescriptor *module
+) const {
+	DECLARE_PLUGINSTATE_CONST(AmbaPluginState, state);
+	return module != nullptr
+		&& module->Path == this->m_module_path
+		&& module->Pid == plgState->traced_pid;
 }
 
 void AmbaPlugin::onProcessUnload(
@@ -268,11 +539,11 @@ void AmbaPlugin::onProcessUnload(
 	const u64 pid,
 	const u64 return_code
 ) {
-	if (pid != this->m_module_pid) {
+	DECLARE_PLUGINSTATE_CONST(AmbaPluginState, state);
+	if (pid != plgState->traced_pid) {
 		return;
 	}
 
-	this->m_module_pid = 0;
 	*amba::debug_stream()
 		<< "Module "
 		<< this->m_module_path
@@ -281,6 +552,29 @@ void AmbaPlugin::onProcessUnload(
 		<< '\n';
 }
 
+void AmbaPlugin::onSegFault(
+	S2EExecutionState *state,
+	u64 pid,
+	u64 pc
+) {
+	DECLARE_PLUGINSTATE_CONST(AmbaPluginState, state);
+	if (pid == plgState->traced_pid) {
+		this->sendStateTermination(state, StateTerminationKind::Signalled, SIGSEGV);
+	}
+}
+
+void AmbaPlugin::onTrap(
+	S2EExecutionState *state,
+	u64 pid,
+	u64 pc,
+	int trapnr
+) {
+	DECLARE_PLUGINSTATE_CONST(AmbaPluginState, state);
+	if (pid == plgState->traced_pid) {
+		this->sendStateTermination(state, StateTerminationKind::Signalled, SIGTRAP);
+	}
+}
+
 void AmbaPlugin::onTimer() {
 	auto &symbolic_edges = this->m_symbolic_graph.edges();
 	auto &assembly_edges = this->m_assembly_graph.edges();
@@ -294,10 +588,67 @@ void AmbaPlugin::onTimer() {
 	symbolic_edges.clear();
 	assembly_edges.clear();
 
+	// States get their amba id when they first fork
+	std::erase_if(this->m_pending_state_messages, [this](auto &pending) {
+		const auto &[s2e_state_id, send] = pending;
+		const auto amba_state_id = this->m_symbolic_graph.stateIdAmba(s2e_state_id);
+		if (amba_state_id) {
+			send((u32) amba_state_id->val, (i32) s2e_state_id.val);
+		}
+		return amba_state_id.has_value();
+	});
 }
 
 void AmbaPlugin::onEngineShutdown() {
 	this->onTimer();
+
+	// amba quits QEMU before every state has ended, such as when the budget
+	// of the run is exhausted
+	for (auto *state : this->s2e()->getExecutor()->getStates()) {
+		this->sendStateTermination(
+			static_cast<S2EExecutionState *>(state),
+			StateTerminationKind::Killed,
+			(i32) KillReason::Stopped
+		);
+	}
+}
+
+// S2E spreads states over several processes by forking. Every process
+// connects to amba on its own, and tells it which states it inherited so that
+// amba can merge their graphs.
+void AmbaPlugin::onProcessFork(
+	bool pre_fork,
+	bool is_child,
+	unsigned parent_proc_id
+) {
+	if (pre_fork) {
+		// Otherwise both processes would send the unsent edges
+		this->onTimer();
+		return;
+	}
+	if (!is_child) {
+		return;
+	}
+
+	// The connection and receiver thread are shared with the parent, whose
+	// thread does not exist in this process. Both are leaked, since closing
+	// the connection would close it for the parent too.
+	new std::jthread(std::move(this->m_ipc_receiver_thread));
+	this->m_ipc = rust_new_ipc();
+	// S2E logs to a new output directory in every process
+	this->m_s2e_log = std::ifstream(this->s2e()->getOutputFilename("debug.txt"));
+	const auto first_own_state_id = std::max(
+		this->m_symbolic_graph.states(),
+		this->m_assembly_graph.states()
+	) + 1;
+	rust_ipc_send_hello(
+		this->m_ipc,
+		(u32) getpid(),
+		true,
+		(u32) getppid(),
+		(u32) first_own_state_id
+	);
+	this->startIpcReceiver();
 }
 
 } // namespace plugins
@@ -89,6 +89,15 @@ StateIdAmba ControlFlow::getStateIdAmba(StateIdS2E id) {
 	return amba_id;
 }
 
+// Unlike `getStateIdAmba`, does not give the state an id if it has none
+std::optional<StateIdAmba> ControlFlow::findStateIdAmba(StateIdS2E id) const {
+	const auto found = this->m_states.find(id);
+	if (found == this->m_states.end() || found->second == 0) {
+		return std::nullopt;
+	}
+	return found->second;
+}
+
 void ControlFlow::incrementStateIdAmba(StateIdS2E id) {
 	this->state_count++;
 	auto& amba_id = this->m_states[id];
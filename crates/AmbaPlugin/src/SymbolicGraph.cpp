@@ -91,4 +91,8 @@ void SymbolicGraph::onStateMerge(
 	);
 }
 
+std::optional<StateIdAmba> SymbolicGraph::stateIdAmba(StateIdS2E id) const {
+	return this->findStateIdAmba(id);
+}
+
 }
@@ -0,0 +1,458 @@
+//! The batch subcommand, which runs many recipes headless, each as a session of
+//! its own
+
+use std::{
+	env, fs,
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
+	thread,
+	time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use recipe::BudgetDuration;
+use regex::Regex;
+
+use crate::{
+	cmd::Cmd,
+	report::{Outcome, Report, REPORT_JSON_FILE},
+	sessions, BaseConfig, BatchArgs, BudgetArgs, VmArgs,
+};
+
+/// How often to check whether running jobs have finished
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How a recipe of the batch went.
+struct JobResult {
+	recipe_path: PathBuf,
+	/// `None` if the session did not start
+	session_dir: Option<PathBuf>,
+	success: bool,
+	/// `None` if the session did not start
+	report: Option<Report>,
+	duration: Duration,
+}
+
+/// A recipe of the batch that is being run.
+struct Job {
+	index: usize,
+	recipe_path: PathBuf,
+	session_dir: PathBuf,
+	pid: u32,
+	started: Instant,
+}
+
+/// Each recipe is run by an `amba run --no-gui` of its own, so that a session
+/// that fails or panics does not take the others down with it.
+pub fn batch(
+	cmd: &mut Cmd,
+	base: &'static BaseConfig,
+	BatchArgs {
+		recipes,
+		jobs,
+		set,
+		budget,
+		vm,
+	}: BatchArgs,
+) -> Result<(), ()> {
+	let recipe_paths = recipe_paths(&recipes)?;
+	if recipe_paths.is_empty() {
+		tracing::error!(?recipes, "No recipes to run");
+		return Err(());
+	}
+	tracing::info!(
+		recipes = recipe_paths.len(),
+		jobs,
+		"Running batch"
+	);
+
+	let amba =
+		env::current_exe().map_err(|err| tracing::error!(?err, "Finding the amba executable"))?;
+	let run_args = run_args(&set, budget, &vm);
+	let mut results: Vec<Option<JobResult>> = recipe_paths.iter().map(|_| None).collect();
+	let mut pending = recipe_paths.into_iter().enumerate();
+	let mut running: Vec<Job> = Vec::new();
+	loop {
+		while running.len() < jobs.max(1) {
+			let Some((index, recipe_path)) = pending.next() else {
+				break;
+			};
+			let Ok(session_dir) = sessions::create_session_dir(&base.data_dir) else {
+				results[index] = Some(JobResult {
+					recipe_path,
+					session_dir: None,
+					success: false,
+					report: None,
+					duration: Duration::ZERO,
+				});
+				continue;
+			};
+			tracing::info!(?recipe_path, ?session_dir, "Starting session");
+			let pid = cmd.command_spawn(
+				Command::new(&amba)
+					.arg("run")
+					.arg(&recipe_path)
+					.arg("--no-gui")
+					.arg("--session-dir")
+					.arg(&session_dir)
+					.args(&run_args)
+					.stdin(Stdio::null()),
+			);
+			running.push(Job {
+				index,
+				recipe_path,
+				session_dir,
+				pid,
+				started: Instant::now(),
+			});
+		}
+		if running.is_empty() {
+			break;
+		}
+
+		thread::sleep(POLL_INTERVAL);
+		let mut index = 0;
+		while index < running.len() {
+			match cmd.command_try_wait(running[index].pid) {
+				Some(status) => {
+					let job = running.swap_remove(index);
+					tracing::info!(recipe_path = ?job.recipe_path, %status, "Session ended");
+					let slot = &mut results[job.index];
+					*slot = Some(finish_job(cmd, job, status.success()));
+				}
+				None => index += 1,
+			}
+		}
+	}
+
+	let results: Vec<JobResult> = results.into_iter().map(Option::unwrap).collect();
+	print_table(&results);
+	let failed = results.iter().filter(|result| !result.success).count();
+	if failed > 0 {
+		tracing::error!(failed, "Some sessions of the batch failed");
+		return Err(());
+	}
+	Ok(())
+}
+
+/// The options of `amba run` that every job of the batch shares. Durations are
+/// written as `recipe::parse_duration` reads them, so they arrive unchanged.
+fn run_args(set: &[(String, String)], budget: BudgetArgs, vm: &VmArgs) -> Vec<String> {
+	let mut args = Vec::new();
+	for (name, value) in set {
+		args.extend(["--set".to_owned(), format!("{name}={value}")]);
+	}
+	let BudgetArgs {
+		max_time,
+		max_states,
+		stop_when_no_new_coverage_for,
+	} = budget;
+	if let Some(max_time) = max_time {
+		args.extend([
+			"--max-time".to_owned(),
+			BudgetDuration(max_time).to_string(),
+		]);
+	}
+	if let Some(max_states) = max_states {
+		args.extend(["--max-states".to_owned(), max_states.to_string()]);
+	}
+	if let Some(timeout) = stop_when_no_new_coverage_for {
+		args.extend([
+			"--stop-when-no-new-coverage-for".to_owned(),
+			BudgetDuration(timeout).to_string(),
+		]);
+	}
+	let VmArgs {
+		vm_config,
+		memory,
+		accelerator,
+		snapshot,
+		processes,
+		qemu_arg,
+	} = vm;
+	if let Some(vm_config) = vm_config {
+		args.extend(["--vm-config".to_owned(), vm_config.display().to_string()]);
+	}
+	if let Some(memory) = memory {
+		args.extend(["--memory".to_owned(), memory.clone()]);
+	}
+	if let Some(accelerator) = accelerator {
+		let accelerator = accelerator.to_possible_value().unwrap();
+		args.extend([
+			"--accelerator".to_owned(),
+			accelerator.get_name().to_owned(),
+		]);
+	}
+	if let Some(snapshot) = snapshot {
+		args.extend(["--snapshot".to_owned(), snapshot.clone()]);
+	}
+	if let Some(processes) = processes {
+		args.extend(["--processes".to_owned(), processes.to_string()]);
+	}
+	for qemu_arg in qemu_arg {
+		args.push(format!("--qemu-arg={qemu_arg}"));
+	}
+	args
+}
+
+/// Collect what the `amba run` of `job` left in its session directory.
+fn finish_job(cmd: &mut Cmd, job: Job, success: bool) -> JobResult {
+	let duration = job.started.elapsed();
+	// `amba run` removes the session directory if amba has not been
+	// initialized, and leaves it empty if the recipe is invalid
+	let mut session_dir = Some(job.session_dir).filter(|dir| dir.exists());
+	if session_dir
+		.as_ref()
+		.is_some_and(|dir| cmd.read_dir(dir).next().is_none())
+	{
+		cmd.remove_dir(session_dir.take().unwrap());
+	}
+	let report = session_dir.as_ref().and_then(|dir| {
+		let report_path = dir.join(REPORT_JSON_FILE);
+		if !report_path.exists() {
+			return None;
+		}
+		serde_json::from_slice(&cmd.read(&report_path))
+			.map_err(|err| tracing::error!(?err, ?report_path, "Reading report"))
+			.ok()
+	});
+	JobResult {
+		recipe_path: job.recipe_path,
+		session_dir,
+		success,
+		report,
+		duration,
+	}
+}
+
+/// The recipes that `patterns` name, in order. Each pattern is a recipe file,
+/// a directory whose recipe files are all run, or a glob with `*` and `?` in
+/// its last component, for when the shell has not already expanded it.
+fn recipe_paths(patterns: &[PathBuf]) -> Result<Vec<PathBuf>, ()> {
+	let mut recipe_paths = Vec::new();
+	for pattern in patterns {
+		let file_name = pattern
+			.file_name()
+			.map(|name| name.to_string_lossy().into_owned())
+			.unwrap_or_default();
+		if pattern.is_dir() {
+			recipe_paths.extend(
+				dir_entries(pattern)?
+					.into_iter()
+					.filter(|path| is_recipe_file(path)),
+			);
+		} else if file_name.contains(['*', '?']) {
+			let regex = Regex::new(&format!(
+				"^{}$",
+				regex::escape(&file_name)
+					.replace(r"\*", ".*")
+					.replace(r"\?", ".")
+			))
+			.unwrap();
+			let dir = match pattern.parent() {
+				Some(dir) if dir != Path::new("") => dir,
+				_ => Path::new("."),
+			};
+			recipe_paths.extend(dir_entries(dir)?.into_iter().filter(|path| {
+				path.is_file()
+					&& path
+						.file_name()
+						.is_some_and(|name| regex.is_match(&name.to_string_lossy()))
+			}));
+		} else {
+			recipe_paths.push(pattern.clone());
+		}
+	}
+	Ok(recipe_paths)
+}
+
+/// The entries of `dir`, sorted by name.
+fn dir_entries(dir: &Path) -> Result<Vec<PathBuf>, ()> {
+	let mut paths = Vec::new();
+	let entries =
+		fs::read_dir(dir).map_err(|err| tracing::error!(?err, ?dir, "Reading recipe directory"))?;
+	for entry in entries {
+		let entry = entry.map_err(|err| tracing::error!(?err, ?dir, "Reading recipe directory"))?;
+		paths.push(entry.path());
+	}
+	paths.sort();
+	Ok(paths)
+}
+
+/// Whether `path` is a file with the extension of a recipe format.
+fn is_recipe_file(path: &Path) -> bool {
+	path.is_file()
+		&& matches!(
+			path.extension().and_then(|extension| extension.to_str()),
+			Some("json" | "toml" | "yaml" | "yml")
+		)
+}
+
+fn print_table(results: &[JobResult]) {
+	println!(
+		"{:<24} {:<8} {:>7} {:>7} {:>7} {:>10}  RECIPE",
+		"SESSION", "STATUS", "STATES", "BLOCKS", "CRASHES", "DURATION"
+	);
+	for result in results {
+		let report = result.report.as_ref();
+		println!(
+			"{:<24} {:<8} {:>7} {:>7} {:>7} {:>10}  {}",
+			result
+				.session_dir
+				.as_ref()
+				.and_then(|dir| dir.file_name())
+				.map_or("-".to_owned(), |id| id
+					.to_string_lossy()
+					.into_owned()),
+			match result.success {
+				true => "finished",
+				false => "failed",
+			},
+			report.map_or("-".to_owned(), |report| report.states.to_string()),
+			report.map_or("-".to_owned(), |report| report.blocks.to_string()),
+			report.map_or("-".to_owned(), |report| report
+				.outcomes
+				.get(&Outcome::Crash)
+				.unwrap_or(&0)
+				.to_string()),
+			sessions::format_duration(result.duration),
+			result.recipe_path.display(),
+		);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{fs, slice};
+
+	use clap::Parser;
+
+	use crate::{batch::*, Args};
+
+	#[test]
+	fn recipe_paths_expand_directories_and_globs() {
+		let dir = std::env::temp_dir().join(format!("amba-batch-{}", std::process::id()));
+		// Directories are never recipes, whatever their name
+		fs::create_dir_all(dir.join("nested.json")).unwrap();
+		for file in [
+			"b.toml",
+			"a.json",
+			"c.yaml",
+			"d.yml",
+			"notes.txt",
+			"ab.json",
+		] {
+			fs::write(dir.join(file), "").unwrap();
+		}
+		let in_dir =
+			|names: &[&str]| -> Vec<PathBuf> { names.iter().map(|name| dir.join(name)).collect() };
+
+		assert_eq!(
+			recipe_paths(slice::from_ref(&dir)),
+			Ok(in_dir(&[
+				"a.json", "ab.json", "b.toml", "c.yaml", "d.yml"
+			]))
+		);
+		assert_eq!(
+			recipe_paths(&[dir.join("*.json")]),
+			Ok(in_dir(&["a.json", "ab.json"]))
+		);
+		// Files that do not exist are left for `amba run` to report
+		assert_eq!(
+			recipe_paths(&[
+				dir.join("?.json"),
+				dir.join("missing.json"),
+				dir.join("*.t*"),
+			]),
+			Ok(in_dir(&[
+				"a.json",
+				"missing.json",
+				"b.toml",
+				"notes.txt"
+			]))
+		);
+		assert_eq!(
+			recipe_paths(&[dir.join("missing/*.json")]),
+			Err(())
+		);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn run_args_round_trip() {
+		let Args::Batch(BatchArgs {
+			set, budget, vm, ..
+		}) = Args::parse_from([
+			"amba",
+			"batch",
+			"recipes",
+			"--set",
+			"A=1",
+			"--set",
+			"B=x=y",
+			"--max-time",
+			"90m",
+			"--max-states",
+			"5",
+			"--stop-when-no-new-coverage-for",
+			"45",
+			"--vm-config",
+			"vm.json",
+			"--memory",
+			"1G",
+			"--accelerator",
+			"tcg",
+			"--snapshot",
+			"fresh",
+			"--processes",
+			"2",
+			"--qemu-arg",
+			"-d",
+			"--qemu-arg=-s",
+		])
+		else {
+			panic!()
+		};
+		let args = run_args(&set, budget, &vm);
+		assert_eq!(
+			args,
+			[
+				"--set",
+				"A=1",
+				"--set",
+				"B=x=y",
+				"--max-time",
+				"90m",
+				"--max-states",
+				"5",
+				"--stop-when-no-new-coverage-for",
+				"45s",
+				"--vm-config",
+				"vm.json",
+				"--memory",
+				"1G",
+				"--accelerator",
+				"tcg",
+				"--snapshot",
+				"fresh",
+				"--processes",
+				"2",
+				"--qemu-arg=-d",
+				"--qemu-arg=-s",
+			]
+		);
+
+		let Args::Run(run) = Args::parse_from(
+			["amba", "run", "recipe.json", "--no-gui"]
+				.into_iter()
+				.map(str::to_owned)
+				.chain(args),
+		) else {
+			panic!()
+		};
+		assert_eq!(run.set, set);
+		assert_eq!(format!("{:?}", run.budget), format!("{budget:?}"));
+		assert_eq!(format!("{:?}", run.vm), format!("{vm:?}"));
+	}
+}
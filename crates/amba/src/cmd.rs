@@ -2,8 +2,10 @@
 
 use std::{
 	collections::{hash_map, HashMap},
+	ffi::{OsStr, OsString},
 	fs::{self, ReadDir},
 	io, iter, mem,
+	os::unix::ffi::{OsStrExt, OsStringExt},
 	path::Path,
 	process::{self, Child, Command, ExitStatus},
 	sync::{
@@ -48,18 +50,43 @@ impl Cmd {
 		command: &mut Command,
 		with_pid: impl FnOnce(u32),
 	) -> ExitStatus {
+		let pid = self.command_spawn(command);
+		with_pid(pid);
+		loop {
+			if let Some(status) = self.command_try_wait(pid) {
+				return status;
+			}
+			thread::sleep(Duration::from_millis(50));
+		}
+	}
+
+	pub fn command_spawn_wait(&mut self, command: &mut Command) -> ExitStatus {
+		self.command_spawn_wait_with_pid(command, |_| {})
+	}
+
+	/// Spawn `command` without waiting for it, returning its PID. Like every
+	/// subprocess of `Cmd`, it is killed on SIGINT until
+	/// [`Cmd::command_try_wait`] has seen it exit.
+	pub fn command_spawn(&mut self, command: &mut Command) -> u32 {
 		tracing::debug!(
 			cwd = ?command.get_current_dir(),
 			env = ?command.get_envs().collect::<Vec<_>>(),
 			args = ?iter::once(command.get_program()).chain(command.get_args()).collect::<Vec<_>>()
 		);
 		let child = command.spawn().unwrap();
-		with_pid(child.id());
-		safe_wait(child).wait().unwrap()
+		let pid = child.id();
+		CHILDREN
+			.try_lock()
+			.unwrap()
+			.get_or_insert_with(HashMap::default)
+			.insert(pid, child);
+		pid
 	}
 
-	pub fn command_spawn_wait(&mut self, command: &mut Command) -> ExitStatus {
-		self.command_spawn_wait_with_pid(command, |_| {})
+	/// The exit status of the subprocess `pid` of [`Cmd::command_spawn`], if it
+	/// has exited.
+	pub fn command_try_wait(&mut self, pid: u32) -> Option<ExitStatus> {
+		try_wait(pid)
 	}
 
 	pub fn read_dir(&mut self, dir: impl AsRef<Path>) -> ReadDir {
@@ -121,6 +148,19 @@ impl Cmd {
 		fs::copy(file, target).unwrap();
 	}
 
+	#[cfg(target_family = "unix")]
+	pub fn set_permissions(&mut self, file: impl AsRef<Path>, mode: u32) {
+		use std::os::unix::fs::PermissionsExt;
+
+		let file = file.as_ref();
+		tracing::debug!(
+			?file,
+			mode = format_args!("{mode:o}"),
+			"set_permissions"
+		);
+		fs::set_permissions(file, fs::Permissions::from_mode(mode)).unwrap();
+	}
+
 	#[cfg(target_family = "unix")]
 	pub fn symlink(&mut self, original: impl AsRef<Path>, link: impl AsRef<Path>) {
 		let original = original.as_ref();
@@ -171,34 +211,17 @@ impl Cmd {
 	}
 }
 
-/// Blocking wait for child termination, implemented as a loop polling and then
-/// sleeping for 50ms. [`CHILDREN`] is only locked during the poll, allowing the
-/// SIGINT handler to lock and kill all children while still using the stdlib
-/// interface of requiring `&mut Child` for all operations. The alternative
-/// would be to use [`libc::kill`].
-fn safe_wait(child: Child) -> Child {
-	let id = child.id();
-	{
-		let mut slot = CHILDREN.try_lock().unwrap();
-		if slot.is_none() {
-			*slot = Some(HashMap::default());
-		}
-		slot.as_mut().unwrap().insert(id, child);
-	}
-	loop {
-		{
-			let mut guard = CHILDREN.try_lock().unwrap();
-			let mut entry = match guard.as_mut().unwrap().entry(id) {
-				hash_map::Entry::Occupied(occupied) => occupied,
-				hash_map::Entry::Vacant(_) => unreachable!(),
-			};
-			match entry.get_mut().try_wait().unwrap() {
-				Some(_) => return entry.remove(),
-				None => {}
-			}
-		}
-		thread::sleep(Duration::from_millis(50));
-	}
+/// Poll a child for termination. [`CHILDREN`] is only locked during the
+/// poll, allowing the SIGINT handler to lock and kill all children while still
+/// using the stdlib interface of requiring `&mut Child` for all operations. The
+/// alternative would be to use [`libc::kill`].
+fn try_wait(pid: u32) -> Option<ExitStatus> {
+	let mut guard = CHILDREN.try_lock().unwrap();
+	let hash_map::Entry::Occupied(mut entry) = guard.as_mut().unwrap().entry(pid) else {
+		unreachable!()
+	};
+	entry.get_mut().try_wait().unwrap()?;
+	Some(entry.remove().wait().unwrap())
 }
 
 /// Try to kill all [`CHILDREN`] before exiting.
@@ -233,3 +256,17 @@ pub fn ctrlc_handler() {
 	Box::leak(Box::new(guard));
 	process::exit(1);
 }
+
+/// `arg` in single quotes, for a POSIX shell. Bytes that are not UTF-8 are
+/// kept as they are.
+pub fn shell_quote(arg: &OsStr) -> OsString {
+	let mut quoted = vec![b'\''];
+	for &byte in arg.as_bytes() {
+		match byte {
+			b'\'' => quoted.extend_from_slice(b"'\\''"),
+			byte => quoted.push(byte),
+		}
+	}
+	quoted.push(b'\'');
+	OsString::from_vec(quoted)
+}
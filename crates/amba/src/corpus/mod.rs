@@ -0,0 +1,237 @@
+//! The corpus subcommand, which turns the explored states of a session into
+//! seeds for a fuzzer such as AFL++ or libFuzzer
+
+use std::{
+	collections::{btree_map::Entry, BTreeMap, BTreeSet},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use recipe::Recipe;
+
+use crate::{
+	replay::{self, RecordedInputs},
+	BaseConfig, CorpusArgs,
+};
+
+/// The directory within the session directory that the corpus is written to,
+/// unless another one is given.
+pub const CORPUS_DIR: &str = "corpus";
+
+pub fn corpus(
+	base: &BaseConfig,
+	CorpusArgs {
+		session,
+		out,
+		leaves_only,
+		input,
+	}: CorpusArgs,
+) -> Result<(), ()> {
+	let session_dir = base.session_dir(session);
+	let out_dir = out.unwrap_or_else(|| session_dir.join(CORPUS_DIR));
+	export(
+		&session_dir,
+		&out_dir,
+		leaves_only,
+		input.as_deref(),
+	)?;
+	Ok(())
+}
+
+/// Write the contents that the input file `input` has in every state of the
+/// session in `session_dir`, or only in the leaves of its state graph, to
+/// `out_dir`. Each distinct content is written once, to `state-<id>` named by
+/// the lowest state id it occurs in. Other files in `out_dir` are kept.
+/// `input` defaults to the symbolic file that is stdin of the analyzed
+/// program, or the only symbolic file of the recipe.
+///
+/// Returns the number of files written.
+pub fn export(
+	session_dir: &Path,
+	out_dir: &Path,
+	leaves_only: bool,
+	input: Option<&str>,
+) -> Result<usize, ()> {
+	let states = replay::recorded_states(session_dir)?;
+	let host_files_dir = session_dir.join("hostfiles");
+	let recipe = fs::read(host_files_dir.join("recipe.json"))
+		.map_err(|err| tracing::error!(?err, "Reading recipe of session"))?;
+	let recipe = &Recipe::deserialize_from(&recipe)
+		.map_err(|err| tracing::error!(?err, "Invalid recipe in session"))?;
+	let input = corpus_input(recipe, input)?;
+	let mut initial_contents: BTreeMap<PathBuf, Vec<u8>> = BTreeMap::new();
+	let parents: BTreeSet<u32> = states
+		.values()
+		.flat_map(|state| state.parents.iter().copied())
+		.collect();
+	let mut corpus: BTreeMap<Vec<u8>, u32> = BTreeMap::new();
+	for (
+		amba_state_id,
+		RecordedInputs {
+			concrete_inputs, ..
+		},
+	) in &states
+	{
+		if leaves_only && parents.contains(amba_state_id) {
+			continue;
+		}
+		let seed = recipe::seed_of(concrete_inputs);
+		let initial_contents_path =
+			|guest_path: &str| replay::initial_contents_path(session_dir, recipe, seed, guest_path);
+		for guest_path in recipe.symbolic_files() {
+			if let Entry::Vacant(entry) = initial_contents.entry(initial_contents_path(guest_path))
+			{
+				let contents = fs::read(entry.key())
+					.map_err(|err| tracing::error!(?err, path = ?entry.key(), "Reading seed"))?;
+				entry.insert(contents);
+			}
+		}
+		let mut inputs = recipe.concrete_inputs(
+			|guest_path| initial_contents[&initial_contents_path(guest_path)].clone(),
+			concrete_inputs,
+		);
+		let content = inputs.files.remove(&input).unwrap();
+		// States are visited in order of id, so the lowest id is kept
+		corpus.entry(content).or_insert(*amba_state_id);
+	}
+
+	tracing::info!(
+		?out_dir,
+		input,
+		states = states.len(),
+		files = corpus.len(),
+		"Writing corpus"
+	);
+	let write = |name: PathBuf, content: &[u8]| {
+		fs::write(&name, content).map_err(|err| tracing::error!(?err, ?name, "Writing corpus"))
+	};
+	fs::create_dir_all(out_dir)
+		.map_err(|err| tracing::error!(?err, ?out_dir, "Creating corpus directory"))?;
+	for (content, amba_state_id) in &corpus {
+		write(
+			out_dir.join(format!("state-{amba_state_id}")),
+			content,
+		)?;
+	}
+	Ok(corpus.len())
+}
+
+/// The key in `recipe.files` of the symbolic file that makes up the corpus.
+fn corpus_input(recipe: &Recipe, input: Option<&str>) -> Result<String, ()> {
+	let guest_path = match input {
+		Some(input) => recipe.symbolic_file(input).ok_or_else(|| {
+			tracing::error!(
+				input,
+				"Corpus input is not a symbolic file of the recipe"
+			);
+		})?,
+		None => recipe.default_symbolic_file().ok_or_else(|| {
+			tracing::error!(
+				"Stdin is not symbolic and the recipe has not exactly one symbolic file, choose one with --input"
+			);
+		})?,
+	};
+	Ok(guest_path.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+	use std::fs;
+
+	use ipc::NodeMetadata;
+
+	use crate::{
+		corpus::*,
+		open::{SessionEvent, SessionRecorder},
+	};
+
+	fn state(amba_state_id: u32, contents: &[u8]) -> NodeMetadata {
+		NodeMetadata::State {
+			amba_state_id,
+			s2e_state_id: 0,
+			concrete_inputs: vec![(
+				"v0___symfile____tmp_input.txt_0_4_symfile___0".to_owned(),
+				contents.to_vec(),
+			)],
+		}
+	}
+
+	#[test]
+	fn dedup_and_leaves_only() {
+		let session_dir = std::env::temp_dir().join(format!("amba-corpus-{}", std::process::id()));
+		fs::create_dir_all(session_dir.join("hostfiles")).unwrap();
+		fs::write(
+			session_dir.join("hostfiles/recipe.json"),
+			r#"{
+				"files": { "input.txt": { "seed": "abcd", "symbolic": [[0, 4]] } },
+				"executable_path": "/bin/cat",
+				"stdin_path": "/tmp/input.txt"
+			}"#,
+		)
+		.unwrap();
+		fs::write(session_dir.join("hostfiles/input.txt"), "abcd").unwrap();
+
+		// State 3 has the contents of state 1 and state 5 those of state 2,
+		// which is a leaf only until state 5 is forked from it
+		let mut recorder = SessionRecorder::new(&session_dir, &[]);
+		for state_edges in [
+			vec![
+				(state(1, b"abcd"), state(2, b"xxxx")),
+				(state(1, b"abcd"), state(3, b"abcd")),
+			],
+			vec![(state(2, b"xxxx"), state(4, b"yyyy"))],
+			vec![(state(2, b"xxxx"), state(5, b"xxxx"))],
+		] {
+			recorder.record(&SessionEvent::NewEdges {
+				state_edges,
+				block_edges: Vec::new(),
+			});
+		}
+		drop(recorder);
+
+		let written = |out_dir: &Path| {
+			let mut files: Vec<_> = fs::read_dir(out_dir)
+				.unwrap()
+				.map(|entry| {
+					let entry = entry.unwrap();
+					(
+						entry.file_name().into_string().unwrap(),
+						fs::read_to_string(entry.path()).unwrap(),
+					)
+				})
+				.collect();
+			files.sort();
+			files
+		};
+		let expected = |files: &[(&str, &str)]| {
+			files
+				.iter()
+				.map(|&(name, contents)| (name.to_owned(), contents.to_owned()))
+				.collect::<Vec<_>>()
+		};
+
+		let all = session_dir.join("all");
+		assert_eq!(export(&session_dir, &all, false, None), Ok(3));
+		assert_eq!(
+			written(&all),
+			expected(&[
+				("state-1", "abcd"),
+				("state-2", "xxxx"),
+				("state-4", "yyyy")
+			])
+		);
+
+		let leaves = session_dir.join("leaves");
+		assert_eq!(export(&session_dir, &leaves, true, None), Ok(3));
+		assert_eq!(
+			written(&leaves),
+			expected(&[
+				("state-3", "abcd"),
+				("state-4", "yyyy"),
+				("state-5", "xxxx")
+			])
+		);
+
+		fs::remove_dir_all(session_dir).unwrap();
+	}
+}
@@ -1,5 +1,6 @@
 use std::{
 	mem,
+	path::PathBuf,
 	sync::{mpsc, Arc},
 	thread,
 };
@@ -12,19 +13,24 @@ use graphui::{ColouringMode, GraphWidget};
 use model::{GraphToView, Model};
 
 use crate::{
-	cmd::Cmd,
+	corpus,
 	run::control::{Controller, ControllerMsg},
-	SessionConfig,
 };
 
-pub fn run_gui(cmd: &'static mut Cmd, config: SessionConfig) -> Result<(), ()> {
+/// Show `model` in the gui, while a [`Controller`] started by
+/// `run_controller` on a thread of its own fills it.
+pub fn run_gui(
+	model: Arc<Model>,
+	session_dir: PathBuf,
+	run_controller: impl FnOnce(Controller, Arc<Model>) -> Result<(), ()> + Send + 'static,
+) -> Result<(), ()> {
 	eframe::run_native(
 		"amba",
 		eframe::NativeOptions {
 			default_theme: eframe::Theme::Light,
 			..Default::default()
 		},
-		Box::new(move |cc| Box::new(Gui::new(cc, cmd, config))),
+		Box::new(move |cc| Box::new(Gui::new(cc, model, session_dir, run_controller))),
 	)
 	.map_err(|error| tracing::error!(?error, "GUI"))
 }
@@ -35,12 +41,17 @@ struct Gui {
 	graph_widget: GraphWidget,
 	view: GraphToView,
 	colouring_mode: ColouringMode,
+	corpus_export: CorpusExport,
 }
 
 impl Gui {
-	fn new(cc: &CreationContext<'_>, cmd: &'static mut Cmd, config: SessionConfig) -> Self {
+	fn new(
+		cc: &CreationContext<'_>,
+		model: Arc<Model>,
+		session_dir: PathBuf,
+		run_controller: impl FnOnce(Controller, Arc<Model>) -> Result<(), ()> + Send + 'static,
+	) -> Self {
 		let (controller_tx, controller_rx) = mpsc::channel();
-		let model = Arc::new(Model::new());
 
 		thread::Builder::new()
 			.name("controller".to_owned())
@@ -49,14 +60,16 @@ impl Gui {
 				let gui_context = Some(cc.egui_ctx.clone());
 				let model = Arc::clone(&model);
 				move || {
-					(Controller {
-						tx,
-						rx: controller_rx,
-						gui_context,
-						qemu_pid: None,
-						embedder_tx: None,
-					})
-					.run(cmd, &config, model)
+					run_controller(
+						Controller {
+							tx,
+							rx: controller_rx,
+							gui_context,
+							qemu_pid: None,
+							embedder_tx: None,
+						},
+						model,
+					)
 				}
 			})
 			.unwrap();
@@ -67,7 +80,61 @@ impl Gui {
 			graph_widget: GraphWidget::default(),
 			view: GraphToView::RawBlock,
 			colouring_mode: ColouringMode::AllGrey,
+			corpus_export: CorpusExport {
+				session_dir,
+				leaves_only: false,
+				status: String::new(),
+				running: None,
+			},
+		}
+	}
+}
+
+/// Exports the inputs explored so far as a fuzzing corpus, like `amba corpus`
+/// does. The export replays every state, so it runs on a thread of its own.
+struct CorpusExport {
+	session_dir: PathBuf,
+	leaves_only: bool,
+	status: String,
+	running: Option<thread::JoinHandle<Result<usize, ()>>>,
+}
+
+impl CorpusExport {
+	fn show(&mut self, ui: &mut egui::Ui) {
+		let out_dir = self.session_dir.join(corpus::CORPUS_DIR);
+		if let Some(running) = self.running.take_if(|running| running.is_finished()) {
+			self.status = match running.join().unwrap() {
+				Ok(files) => format!("Wrote {files} inputs to {}", out_dir.display()),
+				Err(()) => "Export failed, see the log".to_owned(),
+			};
+		}
+
+		ui.separator();
+		ui.checkbox(&mut self.leaves_only, "Leaves only");
+		let export = ui.add_enabled(
+			self.running.is_none(),
+			egui::Button::new("Export corpus"),
+		);
+		if export.clicked() {
+			let session_dir = self.session_dir.clone();
+			let leaves_only = self.leaves_only;
+			let gui_context = ui.ctx().clone();
+			self.running = Some(
+				thread::Builder::new()
+					.name("corpus-export".to_owned())
+					.spawn(move || {
+						let res = corpus::export(&session_dir, &out_dir, leaves_only, None);
+						gui_context.request_repaint();
+						res
+					})
+					.unwrap(),
+			);
+			self.status = "Exporting corpus".to_owned();
 		}
+		if self.running.is_some() {
+			ui.spinner();
+		}
+		ui.label(&self.status);
 	}
 }
 
@@ -196,9 +263,26 @@ impl App for Gui {
 						});
 					}
 					GraphToView::State => {
-						self.colouring_mode = ColouringMode::AllGrey;
+						// Required due to both dropdowns having the same label
+						ui.push_id(ui.id(), |ui| {
+							egui::ComboBox::from_label("")
+								.selected_text(format!("{}", self.colouring_mode))
+								.show_ui(ui, |ui| {
+									ui.selectable_value(
+										&mut self.colouring_mode,
+										ColouringMode::AllGrey,
+										"All grey",
+									);
+									ui.selectable_value(
+										&mut self.colouring_mode,
+										ColouringMode::ByOutcome,
+										"By outcome (leaves only)",
+									);
+								})
+						});
 					}
 				}
+				self.corpus_export.show(ui);
 			})
 		});
 		if let Some(active) = self.graph_widget.active_node_id() {
@@ -1,23 +1,34 @@
 use std::{
-	env,
-	path::PathBuf,
+	env, fs,
+	io::{self, IsTerminal},
+	path::{Path, PathBuf},
 	process::ExitCode,
 	sync::{mpsc, Arc},
-	time::Instant,
+	time::{Duration, Instant},
 };
 
 use chrono::offset::Local;
 use model::Model;
 use rand::{distributions::Alphanumeric, Rng};
-use recipe::{FileSource, Recipe, RecipeError};
+use recipe::{BudgetDuration, FileSource, Recipe, RecipeError, RecipeFormat, Seeds};
 use tracing_subscriber::{filter::targets::Targets, fmt, layer::Layer};
 
-use crate::cmd::Cmd;
+use crate::{
+	cmd::Cmd,
+	run::vm::{Accelerator, VmConfig},
+};
 
+mod batch;
 mod cmd;
+mod corpus;
 mod gui;
 mod init;
+mod open;
+mod recipes;
+mod replay;
+mod report;
 mod run;
+mod sessions;
 
 /// The executable component of amba that runs QEMU+S2E+libamba as a subprocess
 ///
@@ -29,6 +40,12 @@ mod run;
 enum Args {
 	Init(InitArgs),
 	Run(RunArgs),
+	Recipe(RecipeArgs),
+	Replay(ReplayArgs),
+	Corpus(CorpusArgs),
+	Open(OpenArgs),
+	Sessions(SessionsArgs),
+	Batch(BatchArgs),
 }
 
 /// Initialize `$AMBA_DATA_DIR`
@@ -46,7 +63,8 @@ pub struct InitArgs {
 /// Run QEMU+S2E+libamba
 #[derive(clap::Args, Debug)]
 pub struct RunArgs {
-	/// Path to a recipe file specifying the run
+	/// Path to a recipe file specifying the run, in JSON, TOML or YAML as chosen
+	/// by its extension
 	recipe_path: PathBuf,
 	/// Start QEMU in a paused state, to attach a debugger or profiler
 	#[arg(long)]
@@ -54,6 +72,198 @@ pub struct RunArgs {
 	/// Do not open the graphical user interface
 	#[arg(long)]
 	no_gui: bool,
+	/// Fill the `${VAR}` placeholders of the recipe, as `VAR=value`
+	#[arg(long, value_name = "VAR=VALUE", value_parser = parse_variable)]
+	set: Vec<(String, String)>,
+	/// A directory of seed inputs, such as a fuzzing corpus, to start
+	/// exploration from. Overrides the seeds directory of the recipe
+	#[arg(long, value_name = "DIR")]
+	seeds: Option<PathBuf>,
+	#[command(flatten)]
+	budget: BudgetArgs,
+	#[command(flatten)]
+	vm: VmArgs,
+	/// An existing, empty directory to record the session in, in place of a
+	/// new one in `$AMBA_DATA_DIR`. Used by `amba batch` to know where each of
+	/// its runs went
+	#[arg(long, value_name = "DIR", hide = true)]
+	session_dir: Option<PathBuf>,
+}
+
+/// Overrides of the budget of the recipe
+#[derive(clap::Args, Debug, Clone, Copy)]
+pub struct BudgetArgs {
+	/// Stop exploring after this long, such as `30m`. Overrides the budget of
+	/// the recipe, as do the other budget options
+	#[arg(long, value_name = "DURATION", value_parser = recipe::parse_duration)]
+	max_time: Option<Duration>,
+	/// Stop exploring once this many states have been explored
+	#[arg(long, value_name = "N")]
+	max_states: Option<usize>,
+	/// Stop exploring once no new basic block has been covered for this long
+	#[arg(long, value_name = "DURATION", value_parser = recipe::parse_duration)]
+	stop_when_no_new_coverage_for: Option<Duration>,
+}
+
+/// Resources and launch options of the virtual machine
+#[derive(clap::Args, Debug, Clone)]
+pub struct VmArgs {
+	/// A JSON file of VM options, in place of `$AMBA_DATA_DIR/vm.json`. The
+	/// other VM options override those of the file
+	#[arg(long, value_name = "FILE")]
+	vm_config: Option<PathBuf>,
+	/// Guest memory, as for `-m` of QEMU, such as `1G`. Defaults to 256M
+	#[arg(long, value_name = "SIZE")]
+	memory: Option<String>,
+	/// How QEMU executes the guest. Defaults to kvm
+	#[arg(long, value_enum)]
+	accelerator: Option<Accelerator>,
+	/// The snapshot of the guest image to boot from. Defaults to `ready`
+	#[arg(long, value_name = "NAME")]
+	snapshot: Option<String>,
+	/// The most S2E processes to spread states over. Defaults to 1
+	#[arg(long, value_name = "N")]
+	processes: Option<u16>,
+	/// An extra argument to QEMU, which may be given repeatedly
+	#[arg(long, value_name = "ARG", allow_hyphen_values = true)]
+	qemu_arg: Vec<String>,
+}
+
+fn parse_variable(arg: &str) -> Result<(String, String), String> {
+	let (name, value) = arg
+		.split_once('=')
+		.ok_or_else(|| format!("expected VAR=VALUE, found `{arg}`"))?;
+	Ok((name.to_owned(), value.to_owned()))
+}
+
+/// Run many recipes without the graphical user interface, each as a session of
+/// its own, and summarize how each went
+#[derive(clap::Args, Debug)]
+pub struct BatchArgs {
+	/// Recipe files, directories of recipe files, or globs such as
+	/// `recipes/*.json`
+	#[arg(required = true)]
+	recipes: Vec<PathBuf>,
+	/// How many sessions to run at once
+	#[arg(short, long, default_value_t = 1)]
+	jobs: usize,
+	/// Fill the `${VAR}` placeholders of every recipe, as `VAR=value`
+	#[arg(long, value_name = "VAR=VALUE", value_parser = parse_variable)]
+	set: Vec<(String, String)>,
+	#[command(flatten)]
+	budget: BudgetArgs,
+	#[command(flatten)]
+	vm: VmArgs,
+}
+
+/// Tools for writing recipes
+#[derive(clap::Args, Debug)]
+pub struct RecipeArgs {
+	#[command(subcommand)]
+	command: RecipeCommand,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum RecipeCommand {
+	/// Print a JSON Schema for recipes, for editors to validate and
+	/// autocomplete recipes with
+	Schema,
+	/// Write a starter recipe for running a binary with symbolic stdin
+	New {
+		/// The binary to run, on the host
+		binary: PathBuf,
+		/// Where to write the recipe, with the extension choosing its format.
+		/// Defaults to `<binary>.recipe.json` in the current directory
+		#[arg(short, long)]
+		output: Option<PathBuf>,
+	},
+	/// Check that a recipe is valid, without running it
+	Check {
+		recipe_path: PathBuf,
+		/// Fill the `${VAR}` placeholders of the recipe, as `VAR=value`
+		#[arg(long, value_name = "VAR=VALUE", value_parser = parse_variable)]
+		set: Vec<(String, String)>,
+	},
+	/// Rewrite a recipe in place in canonical form. Recipes with comments,
+	/// which are not kept, are only rewritten with `--force`
+	Fmt {
+		recipe_path: PathBuf,
+		/// Rewrite the recipe even if that loses its comments
+		#[arg(short, long)]
+		force: bool,
+	},
+}
+
+/// Re-run the analyzed program natively on the host, with the concrete inputs
+/// of a state of a finished session, and report its exit status and output
+#[derive(clap::Args, Debug)]
+pub struct ReplayArgs {
+	/// The session directory, or its name within `$AMBA_DATA_DIR`
+	session: PathBuf,
+	/// The amba state id of the state to replay
+	state_id: u32,
+	/// Start the program under gdb instead, with its inputs in place
+	#[arg(long)]
+	gdb: bool,
+}
+
+/// Write the concrete inputs of the states of a session as a seed corpus for
+/// AFL++ or libFuzzer, one file per distinct input named by state id
+#[derive(clap::Args, Debug)]
+pub struct CorpusArgs {
+	/// The session directory, or its name within `$AMBA_DATA_DIR`
+	session: PathBuf,
+	/// The directory to write the corpus to. Defaults to `corpus` in the session
+	/// directory
+	#[arg(short, long)]
+	out: Option<PathBuf>,
+	/// Only export the leaves of the state graph
+	#[arg(long)]
+	leaves_only: bool,
+	/// The guest path of the symbolic file to export. Defaults to the one that
+	/// is stdin, or the only symbolic file of the recipe
+	#[arg(long)]
+	input: Option<String>,
+}
+
+/// Show a recorded session in the graphical user interface, without running
+/// QEMU
+#[derive(clap::Args, Debug)]
+pub struct OpenArgs {
+	/// The session directory, or its name within `$AMBA_DATA_DIR`
+	session: PathBuf,
+}
+
+/// List, inspect and remove the sessions in `$AMBA_DATA_DIR`
+#[derive(clap::Args, Debug)]
+pub struct SessionsArgs {
+	#[command(subcommand)]
+	command: SessionsCommand,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum SessionsCommand {
+	/// List sessions, oldest first
+	List,
+	/// Show the details of a session
+	Show {
+		/// The session directory, or its name within `$AMBA_DATA_DIR`
+		session: PathBuf,
+	},
+	/// Remove old sessions. With both `--older-than` and `--keep`, only
+	/// sessions matching both are removed. Running sessions are never removed
+	#[command(group(clap::ArgGroup::new("which").required(true).multiple(true)))]
+	Prune {
+		/// Remove sessions started longer ago than this, such as `7d` or `12h`
+		#[arg(long, value_name = "DURATION", value_parser = recipe::parse_duration, group = "which")]
+		older_than: Option<Duration>,
+		/// Keep this many of the newest sessions
+		#[arg(long, value_name = "N", group = "which")]
+		keep: Option<usize>,
+		/// Only print the sessions that would be removed
+		#[arg(long)]
+		dry_run: bool,
+	},
 }
 
 /// The nix store path of the script that builds guest images.
@@ -75,6 +285,8 @@ fn main() -> ExitCode {
 			.with_subscriber(
 				tracing_subscriber::FmtSubscriber::builder()
 					.with_max_level(tracing::Level::TRACE)
+					// Logs go to stderr, keeping stdout for subcommand output
+					.with_writer(io::stderr)
 					.with_timer(UptimeHourMinuteSeconds::default())
 					.with_thread_names(true)
 					.finish(),
@@ -120,21 +332,39 @@ fn main() -> ExitCode {
 	let cmd = Cmd::get();
 	let res = match args {
 		Args::Init(args) => init::init(cmd, base, args),
+		Args::Recipe(args) => recipes::recipe(cmd, args),
+		Args::Replay(args) => replay::replay(cmd, base, args),
+		Args::Corpus(args) => corpus::corpus(base, args),
+		Args::Open(args) => open::open(base, args),
+		Args::Sessions(args) => sessions::sessions_command(cmd, base, args),
+		Args::Batch(args) => batch::batch(cmd, base, args),
 		Args::Run(args) => {
 			if args.no_gui {
 				let (tx, rx) = mpsc::channel();
-				SessionConfig::new(cmd, base, &args).and_then(|config| {
-					(run::control::Controller {
+				SessionConfig::new(base, &args).and_then(|config| {
+					let model = Arc::new(Model::new(config.recipe.input_layouts()));
+					let res = (run::control::Controller {
 						tx,
 						rx,
 						gui_context: None,
 						qemu_pid: None,
 						embedder_tx: None,
 					})
-					.run(cmd, &config, Arc::new(Model::new()))
+					.run(cmd, &config, Arc::clone(&model));
+					// Also summarize failed runs, as far as they got
+					if config.session_dir.exists() {
+						report::write_report(cmd, &config, &model);
+					}
+					res
 				})
 			} else {
-				SessionConfig::new(cmd, base, &args).and_then(|config| gui::run_gui(cmd, config))
+				SessionConfig::new(base, &args).and_then(|config| {
+					gui::run_gui(
+						Arc::new(Model::new(config.recipe.input_layouts())),
+						config.session_dir.clone(),
+						move |controller, model| controller.run(cmd, &config, model),
+					)
+				})
 			}
 		}
 	};
@@ -149,61 +379,137 @@ pub struct BaseConfig {
 	data_dir: PathBuf,
 }
 
+impl BaseConfig {
+	/// The directory of `session`, which is either a path to it or its name
+	/// within the data directory.
+	pub fn session_dir(&self, session: PathBuf) -> PathBuf {
+		match session.is_dir() {
+			true => session,
+			false => self.data_dir.join(session),
+		}
+	}
+}
+
 pub struct SessionConfig {
 	base: &'static BaseConfig,
 	session_dir: PathBuf,
 	temp_dir: PathBuf,
 	recipe_path: PathBuf,
 	recipe: Recipe,
+	/// The seed files of the recipe, in the order in which they are numbered
+	seeds: Vec<PathBuf>,
+	vm: VmConfig,
 	sigstop_before_qemu_exec: bool,
 }
 
+/// Print compiler-style diagnostics for `err` to stderr.
+fn log_recipe_error(recipe_path: &Path, err: RecipeError) {
+	let source = fs::read(recipe_path)
+		.map(|source| String::from_utf8_lossy(&source).into_owned())
+		.unwrap_or_default();
+	let colour = io::stderr().is_terminal();
+	for diagnostic in err.diagnostics(&source, RecipeFormat::from_path(recipe_path)) {
+		eprintln!(
+			"{}",
+			diagnostic.render(
+				&recipe_path.display().to_string(),
+				&source,
+				colour
+			)
+		);
+	}
+	if let RecipeError::InExtended(base_path, err) = err {
+		log_recipe_error(&base_path, *err);
+	} else {
+		tracing::error!(?recipe_path, "Invalid recipe");
+	}
+}
+
 impl SessionConfig {
-	pub fn new(cmd: &mut Cmd, base: &'static BaseConfig, run_args: &RunArgs) -> Result<Self, ()> {
+	pub fn new(base: &'static BaseConfig, run_args: &RunArgs) -> Result<Self, ()> {
 		let timestamp = Local::now().format("%Y-%m-%dT%H:%M:%S");
 		let mut rng = rand::thread_rng();
 		let random: String = (0..6).map(|_| rng.sample(Alphanumeric) as char).collect();
 
 		let recipe_path = run_args.recipe_path.clone();
-		let recipe = match Recipe::deserialize_from(&cmd.read(&recipe_path)) {
+		let variables = run_args.set.iter().cloned().collect();
+		let recipe = match Recipe::load(&recipe_path, &variables).and_then(|mut recipe| {
+			if let Some(seeds_dir) = &run_args.seeds {
+				recipe.seeds = Some(Seeds {
+					directory: env::current_dir()
+						.unwrap()
+						.join(seeds_dir)
+						.to_str()
+						.expect("seeds directory must be UTF-8")
+						.to_owned(),
+					file: recipe.seeds.and_then(|seeds| seeds.file),
+				});
+			}
+			let BudgetArgs {
+				max_time,
+				max_states,
+				stop_when_no_new_coverage_for,
+			} = run_args.budget;
+			if let Some(max_time) = max_time {
+				recipe.budget.max_time = Some(BudgetDuration(max_time));
+			}
+			if let Some(max_states) = max_states {
+				recipe.budget.max_states = Some(max_states);
+			}
+			if let Some(timeout) = stop_when_no_new_coverage_for {
+				recipe.budget.stop_when_no_new_coverage_for = Some(BudgetDuration(timeout));
+			}
+			recipe
+				.validate(recipe_path.parent().unwrap())
+				.map(|()| recipe)
+		}) {
 			Ok(recipe) => recipe,
 			Err(err) => {
-				match err {
-					RecipeError::NotSemanticRecipe(err) => {
-						tracing::error!(
-							?recipe_path,
-							?err,
-							"Not a semantically valid Recipe"
-						);
-					}
-					RecipeError::NotSyntacticRecipe(err) => {
-						tracing::error!(
-							?recipe_path,
-							?err,
-							"Not a syntactically valid Recipe"
-						);
-					}
-					RecipeError::NotJson(err) => {
-						tracing::error!(?recipe_path, ?err, "Not a valid JSON");
-					}
-					RecipeError::NotUtf8(err) => {
-						tracing::error!(?recipe_path, ?err, "Not valid UTF8");
-					}
-				}
+				log_recipe_error(&recipe_path, err);
 				return Err(());
 			}
 		};
+		let seeds = match &recipe.seeds {
+			Some(seeds) => Self::seed_files(&recipe_path.parent().unwrap().join(&seeds.directory))?,
+			None => Vec::new(),
+		};
+		let vm = VmConfig::load(base, &run_args.vm)?;
 
 		Ok(Self {
 			base,
-			session_dir: base.data_dir.join(timestamp.to_string()),
+			session_dir: match &run_args.session_dir {
+				Some(session_dir) => session_dir.clone(),
+				None => sessions::create_session_dir(&base.data_dir)?,
+			},
 			temp_dir: env::temp_dir().join(format!("amba-{timestamp}-{random}")),
 			recipe_path,
 			recipe,
+			seeds,
+			vm,
 			sigstop_before_qemu_exec: run_args.debugger,
 		})
 	}
 
+	/// The files directly within `seeds_dir`, sorted by name.
+	fn seed_files(seeds_dir: &Path) -> Result<Vec<PathBuf>, ()> {
+		let mut seeds = Vec::new();
+		let entries = fs::read_dir(seeds_dir)
+			.map_err(|err| tracing::error!(?err, ?seeds_dir, "Reading seeds directory"))?;
+		for entry in entries {
+			let path = entry
+				.map_err(|err| tracing::error!(?err, ?seeds_dir, "Reading seeds directory"))?
+				.path();
+			if path.is_file() {
+				seeds.push(path);
+			}
+		}
+		seeds.sort();
+		if seeds.is_empty() {
+			tracing::warn!(?seeds_dir, "Seeds directory is empty");
+		}
+		Ok(seeds)
+	}
+
 	pub fn executable_host_path(&self) -> Option<PathBuf> {
 		// NOTE: `fs::canonicalize` and similar are inappropriate here since we are
 		// operating on a *guest* path.
@@ -222,17 +528,21 @@ impl SessionConfig {
 		}
 		let guest_path: &str = remove_executable_dotslash(&self.recipe.executable_path);
 
-		match self.recipe.files.get(guest_path) {
-			None => panic!(
+		if !self.recipe.files.contains_key(guest_path) {
+			panic!(
 				"invalid recipe: guest path '{guest_path}' matches no guest file: {:?}",
 				self.recipe.files
-			),
-			Some(FileSource::Host(host_path)) => {
+			);
+		}
+		// Symlinks out of `files` are treated like absolute guest paths.
+		match self.recipe.guest_file(guest_path)?.1 {
+			FileSource::Host(host_path) | FileSource::HostWithMode { host_path, .. } => {
 				Some(self.recipe_path.parent().unwrap().join(host_path))
 			}
-			Some(
-				symbolic @ (FileSource::SymbolicContent { .. } | FileSource::SymbolicHost { .. }),
-			) => panic!("invalid recipe: executable file cannot be symbolic: {symbolic:?}"),
+			FileSource::Symlink { .. } => unreachable!("`Recipe::guest_file` follows symlinks"),
+			symbolic @ (FileSource::SymbolicContent { .. } | FileSource::SymbolicHost { .. }) => {
+				panic!("invalid recipe: executable file cannot be symbolic: {symbolic:?}")
+			}
 		}
 	}
 }
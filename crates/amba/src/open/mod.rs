@@ -0,0 +1,226 @@
+//! The open subcommand, and the recording of sessions that it shows
+
+use std::{
+	fs::{self, File},
+	io::{BufWriter, Write},
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use ipc::{NodeMetadata, StateTermination};
+use model::Model;
+use recipe::{FileSource, Recipe};
+use serde::{Deserialize, Serialize};
+
+use crate::{gui, BaseConfig, OpenArgs};
+
+/// The file in the session directory where `amba run` records everything that
+/// the model is built from, as a [`Header`] line followed by one
+/// [`SessionEvent`] per line.
+pub const SESSION_FILE: &str = "session.jsonl";
+
+/// The version of the format of [`SESSION_FILE`]. Bump it whenever
+/// [`SessionEvent`] changes in a way that older sessions cannot be read with.
+const FORMAT_VERSION: u32 = 4;
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+	version: u32,
+	/// The seed files of the recipe, in the order in which they are numbered
+	#[serde(default)]
+	seeds: Vec<PathBuf>,
+}
+
+/// What amba received from S2E, with state ids that are unique within the
+/// session. Concrete inputs keep the names S2E gave them, as
+/// `Recipe::concrete_inputs` expects, until
+/// [`SessionEvent::name_concrete_inputs`].
+#[derive(Serialize, Deserialize)]
+pub enum SessionEvent {
+	NewEdges {
+		state_edges: Vec<(NodeMetadata, NodeMetadata)>,
+		block_edges: Vec<(NodeMetadata, NodeMetadata)>,
+	},
+	StateOutput {
+		amba_state_id: u32,
+		stdout: Vec<u8>,
+		stderr: Vec<u8>,
+	},
+	StateTermination {
+		amba_state_id: u32,
+		termination: StateTermination,
+	},
+}
+
+impl SessionEvent {
+	/// Replace the S2E symbolic variable names of inputs created by bootstrap
+	/// with names referring to the recipe, such as `argv[1]` or
+	/// `len(input.txt)`, for the model. The seed that a state originated from
+	/// is named by its file.
+	pub fn name_concrete_inputs(&mut self, recipe: &Recipe, seeds: &[PathBuf]) {
+		let Self::NewEdges { state_edges, .. } = self else {
+			return;
+		};
+		for node in state_edges.iter_mut().flat_map(|(from, to)| [from, to]) {
+			if let NodeMetadata::State {
+				concrete_inputs, ..
+			} = node
+			{
+				for (name, value) in concrete_inputs {
+					if let Some(index) = recipe::seed_marker_index(name) {
+						*name = "seed".to_owned();
+						*value = match seeds.get(index) {
+							Some(seed) => seed.display().to_string().into_bytes(),
+							None => index.to_string().into_bytes(),
+						};
+					} else if let Some((recipe_name, recipe_value)) =
+						recipe.describe_concrete_input(name, value)
+					{
+						*name = recipe_name;
+						*value = recipe_value;
+					}
+				}
+			}
+		}
+	}
+}
+
+/// Appends every [`SessionEvent`] to [`SESSION_FILE`] as it is received, so
+/// that the session can be opened even if amba did not exit cleanly.
+pub struct SessionRecorder {
+	file: BufWriter<File>,
+}
+
+impl SessionRecorder {
+	pub fn new(session_dir: &Path, seeds: &[PathBuf]) -> Self {
+		let path = session_dir.join(SESSION_FILE);
+		tracing::debug!(?path, "recording session");
+		let mut recorder = Self {
+			file: BufWriter::new(File::create(path).unwrap()),
+		};
+		recorder.write_line(&Header {
+			version: FORMAT_VERSION,
+			seeds: seeds.to_vec(),
+		});
+		recorder
+	}
+
+	pub fn record(&mut self, event: &SessionEvent) {
+		self.write_line(event);
+	}
+
+	fn write_line(&mut self, line: &impl Serialize) {
+		serde_json::to_writer(&mut self.file, line).unwrap();
+		self.file.write_all(b"\n").unwrap();
+		self.file.flush().unwrap();
+	}
+}
+
+/// A session as read back from [`SESSION_FILE`].
+pub struct RecordedSession {
+	/// The seed files of the recipe, in the order in which they are numbered
+	pub seeds: Vec<PathBuf>,
+	/// In the order in which they were received
+	pub events: Vec<SessionEvent>,
+}
+
+/// The recording of the session in `session_dir`, which may still be running.
+pub fn load(session_dir: &Path) -> Result<RecordedSession, ()> {
+	let path = &session_dir.join(SESSION_FILE);
+	let recorded = fs::read_to_string(path).map_err(|err| {
+		tracing::error!(
+			?err,
+			?path,
+			"Not a session directory, or one recorded before sessions were saved"
+		);
+	})?;
+	let mut lines = recorded.lines();
+	let header = lines
+		.next()
+		.and_then(|line| serde_json::from_str::<Header>(line).ok());
+	let seeds = match header {
+		Some(Header {
+			version: FORMAT_VERSION,
+			seeds,
+		}) => seeds,
+		Some(Header { version, .. }) => {
+			tracing::error!(
+				version,
+				supported = FORMAT_VERSION,
+				"Session was recorded in a format this version of amba cannot read"
+			);
+			return Err(());
+		}
+		None => {
+			tracing::error!(?path, "Session file has no valid header");
+			return Err(());
+		}
+	};
+
+	let mut events = Vec::new();
+	let mut lines = lines.enumerate().peekable();
+	while let Some((index, line)) = lines.next() {
+		match serde_json::from_str(line) {
+			Ok(event) => events.push(event),
+			// Amba may have been killed while writing the last line
+			Err(err) if lines.peek().is_none() => {
+				tracing::warn!(?err, "Ignoring incomplete last event of session");
+			}
+			Err(err) => {
+				tracing::error!(
+					?err,
+					line = index + 2,
+					"Invalid event in session file"
+				);
+				return Err(());
+			}
+		}
+	}
+	Ok(RecordedSession { seeds, events })
+}
+
+/// Show a recorded session in the gui, without running QEMU.
+pub fn open(base: &BaseConfig, OpenArgs { session }: OpenArgs) -> Result<(), ()> {
+	let session_dir = base.session_dir(session);
+	let host_files_dir = session_dir.join("hostfiles");
+	let recipe = fs::read(host_files_dir.join("recipe.json"))
+		.map_err(|err| tracing::error!(?err, "Reading recipe of session"))?;
+	let recipe = Recipe::deserialize_from(&recipe)
+		.map_err(|err| tracing::error!(?err, "Invalid recipe in session"))?;
+	let RecordedSession { seeds, mut events } = load(&session_dir)?;
+	for event in &mut events {
+		event.name_concrete_inputs(&recipe, &seeds);
+	}
+	tracing::info!(
+		?session_dir,
+		events = events.len(),
+		"Opening session"
+	);
+
+	let executable = executable_host_path(&host_files_dir, &recipe);
+	let model = Arc::new(Model::new(recipe.input_layouts()));
+	gui::run_gui(model, session_dir, move |controller, model| {
+		// Source files are also looked up next to the recipe, which is not
+		// part of the session
+		controller.open(
+			events,
+			executable.as_deref(),
+			&host_files_dir,
+			model,
+		)
+	})
+}
+
+/// The copy of the analyzed executable within `hostfiles`, if it is not an
+/// absolute guest path.
+fn executable_host_path(host_files_dir: &Path, recipe: &Recipe) -> Option<PathBuf> {
+	if recipe.executable_path.starts_with('/') {
+		return None;
+	}
+	match recipe.guest_file(&recipe.executable_path)? {
+		(guest_path, FileSource::Host(_) | FileSource::HostWithMode { .. }) => {
+			Some(host_files_dir.join(recipe::host_file_name(&guest_path)))
+		}
+		_ => None,
+	}
+}
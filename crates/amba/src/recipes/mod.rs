@@ -0,0 +1,116 @@
+//! The recipe subcommand
+
+use std::{
+	collections::BTreeMap,
+	env,
+	io::{self, Write},
+	path::Path,
+};
+
+use recipe::{Budget, Environment, FileSource, Recipe, RecipeError, RecipeFormat, SymbolicRange};
+
+use crate::{cmd::Cmd, log_recipe_error, RecipeArgs, RecipeCommand};
+
+/// Tools for writing recipes.
+pub fn recipe(cmd: &mut Cmd, RecipeArgs { command }: RecipeArgs) -> Result<(), ()> {
+	match command {
+		RecipeCommand::Schema => io::stdout()
+			.write_all(Recipe::json_schema().as_bytes())
+			.map_err(|err| tracing::error!(?err, "writing schema to stdout")),
+		RecipeCommand::New { binary, output } => {
+			let Some(name) = binary.file_name().and_then(|name| name.to_str()) else {
+				tracing::error!(?binary, "Binary has no file name");
+				return Err(());
+			};
+			let output = output.unwrap_or_else(|| format!("{name}.recipe.json").into());
+			if output.exists() {
+				tracing::error!(?output, "Refusing to overwrite existing recipe");
+				return Err(());
+			}
+			let host_path = host_path_relative_to(&binary, output.parent().unwrap());
+			let recipe = Recipe {
+				files: BTreeMap::from([
+					(name.to_owned(), FileSource::Host(host_path)),
+					(
+						"input.txt".to_owned(),
+						FileSource::SymbolicContent {
+							seed: "a".repeat(8),
+							symbolic: vec![SymbolicRange::Begin(0, ())],
+							max_length: None,
+							layout: None,
+							mode: None,
+						},
+					),
+				]),
+				executable_path: format!("./{name}"),
+				stdin_path: "/tmp/input.txt".to_owned(),
+				arg0: None,
+				arguments: Vec::new(),
+				environment: Environment::default(),
+				setup: Vec::new(),
+				teardown: Vec::new(),
+				seeds: None,
+				budget: Budget::default(),
+			};
+			cmd.write(
+				&output,
+				recipe.serialize_to_format(RecipeFormat::from_path(&output)),
+			);
+			tracing::info!(?output, "Wrote recipe");
+			Ok(())
+		}
+		RecipeCommand::Check { recipe_path, set } => {
+			let variables = set.into_iter().collect();
+			match Recipe::load(&recipe_path, &variables)
+				.and_then(|recipe| recipe.validate(recipe_path.parent().unwrap()))
+			{
+				Ok(()) => {
+					tracing::info!(?recipe_path, "Recipe is valid");
+					Ok(())
+				}
+				Err(err) => {
+					log_recipe_error(&recipe_path, err);
+					Err(())
+				}
+			}
+		}
+		RecipeCommand::Fmt { recipe_path, force } => {
+			let format = RecipeFormat::from_path(&recipe_path);
+			let bytes = cmd.read(&recipe_path);
+			if !force && format.has_comments(&String::from_utf8_lossy(&bytes)) {
+				tracing::error!(
+					?recipe_path,
+					"Refusing to rewrite a recipe with comments, which would be lost. \
+					 Pass --force to rewrite it anyway"
+				);
+				return Err(());
+			}
+			let recipe = match Recipe::deserialize_as_written(&bytes, format) {
+				Ok(recipe) => recipe,
+				Err(err) => {
+					if matches!(err, RecipeError::NotSyntacticRecipe(_))
+						&& Recipe::extends(&bytes, format)
+					{
+						tracing::info!("Recipes that `extend` another cannot be formatted");
+					}
+					log_recipe_error(&recipe_path, err);
+					return Err(());
+				}
+			};
+			cmd.write(&recipe_path, recipe.serialize_to_format(format));
+			Ok(())
+		}
+	}
+}
+
+/// The path of `binary` as written in a recipe in `recipe_dir`, which is
+/// relative if `binary` is within `recipe_dir`.
+fn host_path_relative_to(binary: &Path, recipe_dir: &Path) -> String {
+	let current_dir = env::current_dir().unwrap();
+	let binary = current_dir.join(binary);
+	let recipe_dir = current_dir.join(recipe_dir);
+	match binary.strip_prefix(&recipe_dir) {
+		Ok(relative) => format!("./{}", relative.display()),
+		Err(_) => binary.display().to_string(),
+	}
+}
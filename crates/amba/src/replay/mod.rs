@@ -0,0 +1,277 @@
+//! The replay subcommand
+
+use std::{
+	collections::BTreeMap,
+	env,
+	ffi::OsString,
+	fs::File,
+	io::{self, Write},
+	os::unix::{ffi::OsStringExt, process::CommandExt},
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
+};
+
+use ipc::NodeMetadata;
+use recipe::{FileSource, Hook, Recipe};
+
+use crate::{
+	cmd::{shell_quote, Cmd},
+	open::{self, SessionEvent},
+	run::session::SEEDS_DIR,
+	BaseConfig, ReplayArgs,
+};
+
+/// The concrete inputs of a state, as recorded in the session file.
+pub struct RecordedInputs {
+	/// Named by S2E, as `Recipe::concrete_inputs` expects
+	pub concrete_inputs: Vec<(String, Vec<u8>)>,
+	/// The states that this state was forked or merged from
+	pub parents: Vec<u32>,
+}
+
+/// The last recorded inputs of every state of the session in `session_dir`,
+/// by amba state id.
+pub fn recorded_states(session_dir: &Path) -> Result<BTreeMap<u32, RecordedInputs>, ()> {
+	let mut states = BTreeMap::new();
+	for event in open::load(session_dir)?.events {
+		let SessionEvent::NewEdges { state_edges, .. } = event else {
+			continue;
+		};
+		for (from, to) in state_edges {
+			let parent = record_state(&mut states, from, None);
+			record_state(&mut states, to, parent);
+		}
+	}
+	Ok(states)
+}
+
+/// Returns the amba state id of `node`, if it is a state.
+fn record_state(
+	states: &mut BTreeMap<u32, RecordedInputs>,
+	node: NodeMetadata,
+	parent: Option<u32>,
+) -> Option<u32> {
+	let NodeMetadata::State {
+		amba_state_id,
+		concrete_inputs,
+		..
+	} = node
+	else {
+		return None;
+	};
+	let recorded = states
+		.entry(amba_state_id)
+		.or_insert_with(|| RecordedInputs {
+			concrete_inputs: Vec::new(),
+			parents: Vec::new(),
+		});
+	recorded.concrete_inputs = concrete_inputs;
+	if let Some(parent) = parent.filter(|parent| !recorded.parents.contains(parent)) {
+		recorded.parents.push(parent);
+	}
+	Some(amba_state_id)
+}
+
+/// Where the session keeps the contents that the file at `guest_path` starts
+/// out with in a state that originated from the seed at index `seed`.
+pub fn initial_contents_path(
+	session_dir: &Path,
+	recipe: &Recipe,
+	seed: Option<usize>,
+	guest_path: &str,
+) -> PathBuf {
+	match seed {
+		Some(index) if recipe.seeded_file() == Some(guest_path) => session_dir
+			.join(SEEDS_DIR)
+			.join(recipe::seed_file_name(index)),
+		_ => session_dir
+			.join("hostfiles")
+			.join(recipe::host_file_name(guest_path)),
+	}
+}
+
+/// Re-run the analyzed program natively, with the concrete inputs of a state.
+pub fn replay(
+	cmd: &mut Cmd,
+	base: &BaseConfig,
+	ReplayArgs {
+		session,
+		state_id,
+		gdb,
+	}: ReplayArgs,
+) -> Result<(), ()> {
+	let session_dir = base.session_dir(session);
+	let host_files_dir = session_dir.join("hostfiles");
+	let mut states = recorded_states(&session_dir)?;
+	let recipe = Recipe::deserialize_from(&cmd.read(host_files_dir.join("recipe.json")))
+		.map_err(|err| tracing::error!(?err, "Invalid recipe in session"))?;
+	let Some(RecordedInputs {
+		concrete_inputs, ..
+	}) = states.remove(&state_id)
+	else {
+		tracing::error!(state_id, ?session_dir, "No such state in session");
+		return Err(());
+	};
+
+	let seed = recipe::seed_of(&concrete_inputs);
+	if let Some(index) = seed {
+		tracing::info!(state_id, "State originated from seed {index}");
+	}
+	let inputs = recipe.concrete_inputs(
+		|guest_path| {
+			cmd.read(initial_contents_path(
+				&session_dir,
+				&recipe,
+				seed,
+				guest_path,
+			))
+		},
+		&concrete_inputs,
+	);
+	for variable in &inputs.unknown {
+		tracing::warn!(
+			variable,
+			"Symbolic variable matches no input of the recipe, keeping the seed instead"
+		);
+	}
+
+	// Mirrors the guest, with `work` as the directory that bootstrap runs in
+	// and `tmp` holding the symbolic copies of symbolic files
+	let scratch_dir = env::temp_dir().join(format!(
+		"amba-replay-{}-{state_id}",
+		session_dir.file_name().unwrap().to_string_lossy()
+	));
+	let _ = cmd.try_remove_dir_all(&scratch_dir);
+	let work_dir = &scratch_dir.join("work");
+	let tmp_dir = &scratch_dir.join("tmp");
+	cmd.create_dir_all(work_dir);
+	for (guest_path, source) in &recipe.files {
+		let path = work_dir.join(guest_path);
+		cmd.create_dir_all(path.parent().unwrap());
+		match source {
+			FileSource::Symlink { symlink } => cmd.symlink(symlink, &path),
+			_ => cmd.copy(
+				initial_contents_path(&session_dir, &recipe, seed, guest_path),
+				&path,
+			),
+		}
+		if let Some(mode) = source.mode() {
+			cmd.set_permissions(&path, mode.0);
+		}
+	}
+	for (guest_path, contents) in &inputs.files {
+		let path = tmp_dir.join(guest_path);
+		cmd.create_dir_all(path.parent().unwrap());
+		cmd.write(&path, contents);
+		if let Some(mode) = recipe.files[guest_path].mode() {
+			cmd.set_permissions(&path, mode.0);
+		}
+	}
+	// Guest paths of the symbolic copies lead to their replayed copies
+	let host_path = |guest_path: &str| -> PathBuf {
+		match guest_path.strip_prefix("/tmp/") {
+			Some(symbolic) if inputs.files.contains_key(symbolic) => tmp_dir.join(symbolic),
+			_ => work_dir.join(guest_path),
+		}
+	};
+
+	let executable_path = host_path(&recipe.executable_path);
+	let executable_mode = recipe
+		.guest_file(&recipe.executable_path)
+		.and_then(|(_, source)| source.mode());
+	if !recipe.executable_path.starts_with('/') && executable_mode.is_none() {
+		cmd.set_permissions(&executable_path, 0o555);
+	}
+	let arguments: Vec<OsString> = inputs
+		.arguments
+		.iter()
+		.map(|argument| match std::str::from_utf8(argument) {
+			Ok(guest_path) if guest_path.starts_with("/tmp/") => {
+				host_path(guest_path).into_os_string()
+			}
+			_ => OsString::from_vec(argument.clone()),
+		})
+		.collect();
+
+	let mut command = if gdb {
+		let mut set_args = OsString::from("set args");
+		for argument in &arguments {
+			set_args.push(" ");
+			set_args.push(shell_quote(argument));
+		}
+		set_args.push(" < ");
+		set_args.push(shell_quote(
+			host_path(&recipe.stdin_path).as_os_str(),
+		));
+		let mut command = Command::new("gdb");
+		command
+			.args(["-q", "-ex", "set startup-with-shell on", "-ex"])
+			.arg(set_args)
+			.arg(&executable_path);
+		command
+	} else {
+		Command::new(&executable_path)
+	};
+	command.current_dir(work_dir);
+	for key in &recipe.environment.remove {
+		command.env_remove(key);
+	}
+	if !recipe.environment.inherit {
+		command.env_clear();
+	}
+	for (key, value) in &inputs.environment {
+		command.env(key, OsString::from_vec(value.clone()));
+	}
+
+	run_hooks(cmd, work_dir, "setup", &recipe.setup)?;
+	if gdb {
+		tracing::info!("Start the program in gdb with `run`, argv[0] is its path under gdb");
+		cmd.command_spawn_wait(&mut command);
+	} else {
+		let stdout_path = &scratch_dir.join("stdout");
+		let stderr_path = &scratch_dir.join("stderr");
+		command
+			.args(&arguments)
+			.arg0(recipe.arg0.as_ref().unwrap_or(&recipe.executable_path))
+			.stdin(Stdio::from(
+				File::open(host_path(&recipe.stdin_path)).unwrap(),
+			))
+			.stdout(Stdio::from(File::create(stdout_path).unwrap()))
+			.stderr(Stdio::from(File::create(stderr_path).unwrap()));
+		let status = cmd.command_spawn_wait(&mut command);
+
+		let mut stdout = io::stdout().lock();
+		writeln!(stdout, "{status}").unwrap();
+		writeln!(stdout, "--- stdout ---").unwrap();
+		stdout.write_all(&cmd.read(stdout_path)).unwrap();
+		writeln!(stdout, "--- stderr ---").unwrap();
+		stdout.write_all(&cmd.read(stderr_path)).unwrap();
+	}
+	run_hooks(cmd, work_dir, "teardown", &recipe.teardown)?;
+	tracing::info!(?scratch_dir, "Replayed state {state_id}");
+	Ok(())
+}
+
+/// Run the `hooks` of a recipe in `work_dir`, like bootstrap does.
+fn run_hooks(cmd: &mut Cmd, work_dir: &Path, what: &str, hooks: &[Hook]) -> Result<(), ()> {
+	for hook in hooks {
+		let mut command = match hook {
+			Hook::Shell(command) => {
+				let mut cmd = Command::new("/bin/sh");
+				cmd.arg("-c").arg(command);
+				cmd
+			}
+			Hook::Exec(argv) => {
+				let mut cmd = Command::new(&argv[0]);
+				cmd.args(&argv[1..]);
+				cmd
+			}
+		};
+		let status = cmd.command_spawn_wait(command.current_dir(work_dir));
+		if !status.success() {
+			tracing::error!(?hook, %status, "{what} command failed");
+			return Err(());
+		}
+	}
+	Ok(())
+}
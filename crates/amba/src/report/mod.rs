@@ -0,0 +1,426 @@
+//! Reports of what a headless run explored, for CI jobs to keep as artifacts
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt::Write,
+	path::Path,
+};
+
+use disassembler::DisasmContext;
+use ipc::StateTermination;
+use model::{Exploration, ExploredState, Model};
+use recipe::Recipe;
+use serde::{Deserialize, Serialize};
+
+use crate::{cmd::Cmd, SessionConfig};
+
+/// The machine-readable report, in the session directory.
+pub const REPORT_JSON_FILE: &str = "report.json";
+/// The same report as a Markdown summary, in the session directory.
+pub const REPORT_MARKDOWN_FILE: &str = "report.md";
+
+#[derive(Serialize, Deserialize)]
+pub struct Report {
+	pub recipe: String,
+	pub states: usize,
+	/// The number of forks on the longest path from the initial state to a leaf
+	pub depth: usize,
+	/// The number of leaves with each outcome
+	pub outcomes: BTreeMap<Outcome, usize>,
+	pub blocks: usize,
+	pub functions: BTreeSet<String>,
+	leaves: Vec<Leaf>,
+}
+
+/// How a leaf of the state graph ended, like the "By outcome" colouring of the
+/// gui.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+	Success,
+	Failure,
+	Crash,
+	Killed,
+	Running,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Leaf {
+	amba_state_id: u32,
+	s2e_state_id: i32,
+	outcome: Outcome,
+	termination: Option<String>,
+	concrete_inputs: Vec<ConcreteInput>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	stdout: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	stderr: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConcreteInput {
+	name: String,
+	/// Decoded by the layout of the input if it has one, and otherwise as
+	/// lossy UTF-8
+	value: String,
+	bytes: Vec<u8>,
+}
+
+/// Write [`REPORT_JSON_FILE`] and [`REPORT_MARKDOWN_FILE`] for the session of
+/// `config`, from what `model` has received.
+pub fn write_report(cmd: &mut Cmd, config: &SessionConfig, model: &Model) {
+	let disasm_context = DisasmContext::new(
+		config.executable_host_path().as_deref(),
+		config.recipe_path.parent().unwrap(),
+	)
+	.unwrap();
+	let report = Report::new(
+		&config.recipe_path,
+		&config.recipe,
+		model.exploration(&disasm_context),
+	);
+	cmd.write(
+		config.session_dir.join(REPORT_JSON_FILE),
+		serde_json::to_vec_pretty(&report).unwrap(),
+	);
+	cmd.write(
+		config.session_dir.join(REPORT_MARKDOWN_FILE),
+		report.markdown(),
+	);
+	tracing::info!(
+		states = report.states,
+		leaves = report.leaves.len(),
+		blocks = report.blocks,
+		"Wrote report to {}",
+		config.session_dir.join(REPORT_MARKDOWN_FILE).display()
+	);
+}
+
+impl Report {
+	fn new(recipe_path: &Path, recipe: &Recipe, exploration: Exploration) -> Self {
+		let Exploration {
+			states,
+			state_edges,
+			blocks,
+			functions,
+		} = exploration;
+		let input_layouts = recipe.input_layouts();
+		let state_count = states.len();
+		// A state whose only edge is to itself has not forked
+		let parents: BTreeSet<u32> = state_edges
+			.iter()
+			.filter(|&&(from, to)| from != to)
+			.map(|&(from, _)| from)
+			.collect();
+		let mut outcomes = BTreeMap::new();
+		let leaves: Vec<Leaf> = states
+			.into_iter()
+			.filter(|(amba_state_id, _)| !parents.contains(amba_state_id))
+			.map(
+				|(
+					amba_state_id,
+					ExploredState {
+						s2e_state_id,
+						concrete_inputs,
+						termination,
+						output,
+					},
+				)| {
+					let outcome = match termination {
+						None => Outcome::Running,
+						Some(StateTermination::Exited(0)) => Outcome::Success,
+						Some(StateTermination::Exited(_)) => Outcome::Failure,
+						Some(StateTermination::Signalled(_)) => Outcome::Crash,
+						Some(StateTermination::Killed(_)) => Outcome::Killed,
+					};
+					*outcomes.entry(outcome).or_default() += 1;
+					Leaf {
+						amba_state_id,
+						s2e_state_id,
+						outcome,
+						termination: termination.map(|termination| termination.to_string()),
+						concrete_inputs: concrete_inputs
+							.into_iter()
+							.map(|(name, bytes)| ConcreteInput {
+								value: match input_layouts.get(&name) {
+									Some(layout) => layout.decode(&bytes),
+									None => String::from_utf8_lossy(&bytes).into_owned(),
+								},
+								name,
+								bytes,
+							})
+							.collect(),
+						stdout: output
+							.as_ref()
+							.map(|output| String::from_utf8_lossy(&output.stdout).into_owned()),
+						stderr: output
+							.as_ref()
+							.map(|output| String::from_utf8_lossy(&output.stderr).into_owned()),
+					}
+				},
+			)
+			.collect();
+		Self {
+			recipe: recipe_path.display().to_string(),
+			states: state_count,
+			depth: depth(&state_edges),
+			outcomes,
+			blocks,
+			functions,
+			leaves,
+		}
+	}
+
+	fn markdown(&self) -> String {
+		let mut md = String::new();
+		writeln!(md, "# amba report for `{}`\n", self.recipe).unwrap();
+		writeln!(md, "| | |\n|---|---|").unwrap();
+		writeln!(md, "| States | {} |", self.states).unwrap();
+		writeln!(md, "| Leaves | {} |", self.leaves.len()).unwrap();
+		writeln!(md, "| Fork depth | {} |", self.depth).unwrap();
+		writeln!(md, "| Basic blocks | {} |", self.blocks).unwrap();
+		writeln!(md, "| Functions | {} |", self.functions.len()).unwrap();
+
+		writeln!(md, "\n## Leaf outcomes\n").unwrap();
+		writeln!(md, "| Outcome | Leaves |\n|---|---|").unwrap();
+		for (outcome, count) in &self.outcomes {
+			writeln!(md, "| {outcome:?} | {count} |").unwrap();
+		}
+
+		if !self.functions.is_empty() {
+			writeln!(md, "\n## Functions covered\n").unwrap();
+			for function in &self.functions {
+				writeln!(md, "- `{function}`").unwrap();
+			}
+		}
+
+		writeln!(md, "\n## Leaves").unwrap();
+		for leaf in &self.leaves {
+			writeln!(
+				md,
+				"\n### State {} ({:?})\n",
+				leaf.amba_state_id, leaf.outcome
+			)
+			.unwrap();
+			if let Some(termination) = &leaf.termination {
+				writeln!(md, "{termination}\n").unwrap();
+			}
+			for ConcreteInput { name, value, .. } in &leaf.concrete_inputs {
+				writeln!(md, "`{name}`:\n```\n{value}\n```").unwrap();
+			}
+			for (name, output) in [("stdout", &leaf.stdout), ("stderr", &leaf.stderr)] {
+				match output {
+					Some(output) if !output.is_empty() => {
+						writeln!(md, "{name}:\n```\n{}\n```", output.trim_end()).unwrap();
+					}
+					_ => {}
+				}
+			}
+		}
+		md
+	}
+}
+
+/// The number of edges on the longest path of the state graph. Merges can
+/// make it a DAG rather than a tree.
+fn depth(state_edges: &BTreeSet<(u32, u32)>) -> usize {
+	let mut children: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+	for &(from, to) in state_edges {
+		children.entry(from).or_default().push(to);
+	}
+	// States in reverse topological order, so that children come first
+	let mut order = Vec::new();
+	let mut visited = BTreeSet::new();
+	for &root in children.keys() {
+		let mut stack = vec![(root, false)];
+		while let Some((state, expanded)) = stack.pop() {
+			if expanded {
+				order.push(state);
+				continue;
+			}
+			if !visited.insert(state) {
+				continue;
+			}
+			stack.push((state, true));
+			for &child in children.get(&state).into_iter().flatten() {
+				stack.push((child, false));
+			}
+		}
+	}
+	let mut depths: BTreeMap<u32, usize> = BTreeMap::new();
+	for state in order {
+		let depth = children
+			.get(&state)
+			.into_iter()
+			.flatten()
+			.filter_map(|child| depths.get(child))
+			.map(|depth| depth + 1)
+			.max()
+			.unwrap_or(0);
+		depths.insert(state, depth);
+	}
+	depths.into_values().max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+	use ipc::KillReason;
+	use model::StateOutput;
+	use serde_json::json;
+
+	use crate::report::*;
+
+	fn state(s2e_state_id: i32, termination: Option<StateTermination>) -> ExploredState {
+		ExploredState {
+			s2e_state_id,
+			concrete_inputs: Vec::new(),
+			termination,
+			output: None,
+		}
+	}
+
+	#[test]
+	fn depth_of_trees_and_dags() {
+		let depth_of = |edges: &[(u32, u32)]| depth(&edges.iter().copied().collect());
+		assert_eq!(depth_of(&[]), 0);
+		assert_eq!(depth_of(&[(1, 2), (1, 3), (3, 4)]), 2);
+		// Merging into a state reached along a shorter path too
+		assert_eq!(depth_of(&[(1, 2), (2, 3), (1, 3), (3, 4)]), 3);
+		assert_eq!(depth_of(&[(1, 1)]), 0);
+		assert_eq!(depth_of(&[(1, 2), (2, 2)]), 1);
+	}
+
+	#[test]
+	fn report_of_exploration() {
+		let recipe: Recipe = serde_json::from_value(json!({
+			"files": { "input.txt": { "seed": "abcd", "symbolic": [[0, 4]] } },
+			"executable_path": "/bin/prog",
+			"stdin_path": "/tmp/input.txt",
+		}))
+		.unwrap();
+
+		// 1 forks into 2, 3, 7 and 9, and 3 into 4, 5 and 8. 4 and 5 merge
+		// into 6, and 2 has an edge to itself.
+		let states = BTreeMap::from([
+			(1, state(0, None)),
+			(
+				2,
+				ExploredState {
+					s2e_state_id: 1,
+					concrete_inputs: vec![("input.txt".to_owned(), b"ab\xFF".to_vec())],
+					termination: Some(StateTermination::Exited(0)),
+					output: Some(StateOutput {
+						stdout: b"ok\n".to_vec(),
+						stderr: Vec::new(),
+					}),
+				},
+			),
+			(3, state(2, None)),
+			(4, state(3, None)),
+			(5, state(4, None)),
+			(6, state(5, Some(StateTermination::Signalled(11)))),
+			(7, state(6, Some(StateTermination::Exited(1)))),
+			(
+				8,
+				state(
+					7,
+					Some(StateTermination::Killed(KillReason::Timeout)),
+				),
+			),
+			(9, state(8, None)),
+		]);
+		let exploration = Exploration {
+			states,
+			state_edges: BTreeSet::from([
+				(1, 2),
+				(1, 3),
+				(1, 7),
+				(1, 9),
+				(2, 2),
+				(3, 4),
+				(3, 5),
+				(3, 8),
+				(4, 6),
+				(5, 6),
+			]),
+			blocks: 12,
+			functions: BTreeSet::from(["main".to_owned()]),
+		};
+
+		let report = Report::new("recipe.json".as_ref(), &recipe, exploration);
+		assert_eq!(report.depth, 3);
+		assert_eq!(
+			serde_json::to_value(&report).unwrap(),
+			json!({
+				"recipe": "recipe.json",
+				"states": 9,
+				"depth": 3,
+				"outcomes": { "success": 1, "failure": 1, "crash": 1, "killed": 1, "running": 1 },
+				"blocks": 12,
+				"functions": ["main"],
+				"leaves": [
+					{
+						"amba_state_id": 2,
+						"s2e_state_id": 1,
+						"outcome": "success",
+						"termination": "Exited with code 0",
+						"concrete_inputs": [
+							{ "name": "input.txt", "value": "ab\u{FFFD}", "bytes": [97, 98, 255] },
+						],
+						"stdout": "ok\n",
+						"stderr": "",
+					},
+					{
+						"amba_state_id": 6,
+						"s2e_state_id": 5,
+						"outcome": "crash",
+						"termination": "Terminated by signal 11",
+						"concrete_inputs": [],
+					},
+					{
+						"amba_state_id": 7,
+						"s2e_state_id": 6,
+						"outcome": "failure",
+						"termination": "Exited with code 1",
+						"concrete_inputs": [],
+					},
+					{
+						"amba_state_id": 8,
+						"s2e_state_id": 7,
+						"outcome": "killed",
+						"termination": "Killed by S2E for timing out",
+						"concrete_inputs": [],
+					},
+					{
+						"amba_state_id": 9,
+						"s2e_state_id": 8,
+						"outcome": "running",
+						"termination": null,
+						"concrete_inputs": [],
+					},
+				],
+			})
+		);
+
+		let markdown = report.markdown();
+		for line in [
+			"# amba report for `recipe.json`",
+			"| States | 9 |",
+			"| Leaves | 5 |",
+			"| Fork depth | 3 |",
+			"| Basic blocks | 12 |",
+			"| Functions | 1 |",
+			"| Success | 1 |",
+			"| Running | 1 |",
+			"- `main`",
+			"### State 2 (Success)",
+			"### State 8 (Killed)",
+			"Killed by S2E for timing out",
+			"`input.txt`:\n```\nab\u{FFFD}\n```",
+			"stdout:\n```\nok\n```",
+		] {
+			assert!(markdown.contains(line), "{line} in\n{markdown}");
+		}
+		assert!(!markdown.contains("stderr:"), "{markdown}");
+	}
+}
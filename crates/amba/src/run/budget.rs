@@ -0,0 +1,74 @@
+//! Enforcing the exploration budget of a recipe
+
+use std::time::{Duration, Instant};
+
+use model::Model;
+use recipe::Budget;
+
+/// How often the budget is checked, when there is one.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tracks a run against its [`Budget`], as counted by the model.
+pub struct BudgetWatch<'a> {
+	budget: &'a Budget,
+	started: Instant,
+	blocks: usize,
+	/// When the number of covered blocks last grew, once there are any
+	last_new_coverage: Option<Instant>,
+	exhausted: bool,
+}
+
+impl<'a> BudgetWatch<'a> {
+	pub fn new(budget: &'a Budget) -> Self {
+		Self {
+			budget,
+			started: Instant::now(),
+			blocks: 0,
+			last_new_coverage: None,
+			exhausted: false,
+		}
+	}
+
+	/// How long to wait for messages before checking the budget again, or
+	/// `None` if there is nothing left to check.
+	pub fn poll_interval(&self) -> Option<Duration> {
+		match self.exhausted || self.budget.is_unlimited() {
+			true => None,
+			false => Some(POLL_INTERVAL),
+		}
+	}
+
+	/// Describes the limit that was exceeded, the first time that one is.
+	pub fn check(&mut self, model: &Model) -> Option<String> {
+		self.poll_interval()?;
+		let now = Instant::now();
+		let blocks = model.block_count();
+		if blocks > self.blocks {
+			self.blocks = blocks;
+			self.last_new_coverage = Some(now);
+		}
+
+		let reason = if let Some(max_time) = self
+			.budget
+			.max_time
+			.filter(|max_time| now - self.started >= max_time.0)
+		{
+			format!("ran for the maximum time of {max_time}")
+		} else if let Some(max_states) = self
+			.budget
+			.max_states
+			.filter(|&max_states| model.state_count() >= max_states)
+		{
+			format!("explored the maximum of {max_states} states")
+		} else if let Some(timeout) = self.budget.stop_when_no_new_coverage_for.filter(|timeout| {
+			self.last_new_coverage
+				.is_some_and(|last_new_coverage| now - last_new_coverage >= timeout.0)
+		}) {
+			format!("covered no new blocks for {timeout}")
+		} else {
+			return None;
+		};
+		self.exhausted = true;
+		Some(reason)
+	}
+}
@@ -5,17 +5,23 @@ use std::{
 	net::Shutdown,
 	os::unix::net::UnixStream,
 	path::Path,
-	sync::{mpsc, Arc},
+	sync::{
+		mpsc::{self, RecvTimeoutError},
+		Arc,
+	},
 	thread::{self, ScopedJoinHandle},
 };
 
 use eframe::egui::Context;
-use ipc::{IpcInstance, IpcTx, NodeMetadata};
+use ipc::{IpcServer, IpcServerTx};
 use model::Model;
+use qmp_client::{QmpClient, QmpCommand};
+use recipe::Budget;
 
 use crate::{
 	cmd::Cmd,
-	run::{embed, runners},
+	open::SessionEvent,
+	run::{budget::BudgetWatch, embed, runners},
 	SessionConfig,
 };
 
@@ -23,19 +29,15 @@ pub enum ControllerMsg {
 	GuiShutdown,
 	QemuShutdown,
 	TellQemuPid(u32),
-	UpdateEdges {
-		block_edges: Vec<(NodeMetadata, NodeMetadata)>,
-		state_edges: Vec<(NodeMetadata, NodeMetadata)>,
-	},
+	TellQmpStream(UnixStream),
+	/// Passed on to the embedder, with the concrete inputs named
+	Event(SessionEvent),
 	EmbeddingParamsOrViewUpdated,
 	NewPriority(usize),
 }
 
 pub enum EmbedderMsg {
-	UpdateEdges {
-		block_edges: Vec<(NodeMetadata, NodeMetadata)>,
-		state_edges: Vec<(NodeMetadata, NodeMetadata)>,
-	},
+	Event(SessionEvent),
 	WakeUp,
 	QemuShutdown,
 }
@@ -60,10 +62,10 @@ impl Controller {
 		config: &SessionConfig,
 		model: Arc<Model>,
 	) -> Result<(), ()> {
-		runners::prepare_run(cmd, config)?;
-
 		let ipc_socket = &config.temp_dir.join("amba-ipc.socket");
 		let qmp_socket = &config.temp_dir.join("qmp.socket");
+		let metadata = runners::prepare_run(cmd, config, qmp_socket)?;
+
 		let controller_tx_from_ipc = self.tx.clone();
 		let controller_tx_from_qemu = self.tx.clone();
 		let controller_tx_from_qmp = self.tx.clone();
@@ -78,12 +80,12 @@ impl Controller {
 					runners::run_qemu(cmd, config, qmp_socket, controller_tx_from_qemu)
 				})
 				.unwrap();
-			let ipc_instance = IpcInstance::new_gui(ipc_socket);
-			let (ipc_rx, ipc_tx) = ipc_instance.into();
+			let ipc_server = IpcServer::new(ipc_socket);
+			let (ipc_rx, ipc_tx) = ipc_server.into();
 			let ipc = thread::Builder::new()
 				.name("ipc".to_owned())
 				.spawn_scoped(s, || {
-					runners::run_ipc(ipc_rx, controller_tx_from_ipc)
+					runners::run_ipc(ipc_rx, config, controller_tx_from_ipc)
 				})
 				.unwrap();
 			let qmp = thread::Builder::new()
@@ -100,42 +102,116 @@ impl Controller {
 						&embedder_model,
 						embedder_rx,
 						embedder_gui_context,
-						config,
+						config.executable_host_path().as_deref(),
+						config.recipe_path.parent().unwrap(),
 					)
 				})
 				.unwrap();
-			self.run_controller(ipc_tx, model);
-			self.shutdown_controller(ipc_socket, ipc, qemu, qmp, embedder)
+			let qemu_shut_down = self.run_controller(
+				Some(ipc_tx),
+				Arc::clone(&model),
+				&config.recipe.budget,
+			);
+			self.shutdown_controller(
+				ipc_socket,
+				qemu_shut_down,
+				ipc,
+				qemu,
+				qmp,
+				embedder,
+			)
 		});
 		cmd.try_remove(ipc_socket);
 		cmd.try_remove(qmp_socket);
+		metadata.finish(cmd, &config.session_dir, &model, res.is_ok());
 		res
 	}
 
-	fn run_controller(&mut self, mut ipc_tx: IpcTx, model: Arc<Model>) {
+	/// Show the recorded `events` of a finished session, without QEMU.
+	pub fn open(
+		mut self,
+		events: Vec<SessionEvent>,
+		executable: Option<&Path>,
+		recipe_dir: &Path,
+		model: Arc<Model>,
+	) -> Result<(), ()> {
+		let (embedder_tx, embedder_rx) = mpsc::channel();
+		for event in events {
+			embedder_tx.send(EmbedderMsg::Event(event)).unwrap();
+		}
+		self.embedder_tx = Some(embedder_tx);
+		let embedder_gui_context = self.gui_context.clone();
+
+		thread::scope(|s| {
+			let embedder_model = model.clone();
+			let embedder = thread::Builder::new()
+				.name("embedder".to_owned())
+				.spawn_scoped(s, move || {
+					embed::run_embedder(
+						&embedder_model,
+						embedder_rx,
+						embedder_gui_context,
+						executable,
+						recipe_dir,
+					)
+				})
+				.unwrap();
+			self.run_controller(None, model, &Budget::default());
+			mem::drop(self.embedder_tx);
+			embedder.join().unwrap()
+		})
+	}
+
+	/// Handle messages until the gui, or without one QEMU, shuts down. Without
+	/// `ipc_tx`, the session has already finished. QEMU is asked to quit once
+	/// `budget` is exhausted, leaving the gui open.
+	///
+	/// Returns whether QEMU has shut down.
+	fn run_controller(
+		&mut self,
+		mut ipc_tx: Option<IpcServerTx>,
+		model: Arc<Model>,
+		budget: &Budget,
+	) -> bool {
+		let mut budget = BudgetWatch::new(budget);
+		let mut qmp_stream = None;
+		let mut qemu_shut_down = false;
 		loop {
-			match self.rx.recv().unwrap() {
-				ControllerMsg::GuiShutdown => return,
+			let msg = match budget.poll_interval() {
+				Some(timeout) => match self.rx.recv_timeout(timeout) {
+					Ok(msg) => Some(msg),
+					Err(RecvTimeoutError::Timeout) => None,
+					Err(RecvTimeoutError::Disconnected) => unreachable!(),
+				},
+				None => Some(self.rx.recv().unwrap()),
+			};
+			if let Some(reason) = budget.check(&model) {
+				tracing::info!(
+					reason,
+					"Exploration budget exhausted, stopping QEMU"
+				);
+				self.stop_qemu(qmp_stream.as_ref());
+			}
+			let Some(msg) = msg else {
+				continue;
+			};
+			match msg {
+				ControllerMsg::GuiShutdown => return qemu_shut_down,
 				ControllerMsg::QemuShutdown => {
+					qemu_shut_down = true;
 					if self.gui_context.is_none() {
-						return;
+						return qemu_shut_down;
 					}
 					self.embedder_tx.as_ref().map(|tx| {
 						tx.send(EmbedderMsg::QemuShutdown).unwrap();
 					});
 				}
 				ControllerMsg::TellQemuPid(pid) => self.qemu_pid = Some(pid),
-				ControllerMsg::UpdateEdges {
-					block_edges,
-					state_edges,
-				} => {
-					self.embedder_tx.as_ref().map(|tx| {
-						tx.send(EmbedderMsg::UpdateEdges {
-							block_edges,
-							state_edges,
-						})
-						.unwrap();
-					});
+				ControllerMsg::TellQmpStream(stream) => qmp_stream = Some(stream),
+				ControllerMsg::Event(event) => {
+					if let Some(tx) = self.embedder_tx.as_ref() {
+						tx.send(EmbedderMsg::Event(event)).unwrap();
+					}
 				}
 				ControllerMsg::EmbeddingParamsOrViewUpdated => {
 					if let Some(tx) = self.embedder_tx.as_ref() {
@@ -143,6 +219,10 @@ impl Controller {
 					}
 				}
 				ControllerMsg::NewPriority(prio) => {
+					let Some(ipc_tx) = ipc_tx.as_mut() else {
+						tracing::warn!("Cannot prioritise states of a finished session");
+						continue;
+					};
 					let states = model.as_ref().get_neighbour_states(prio);
 
 					tracing::info!("Sending state prio: {states:#?}");
@@ -157,9 +237,28 @@ impl Controller {
 		}
 	}
 
+	/// Quit QEMU gracefully over QMP, so that S2E still flushes what it has
+	/// not yet sent, or with SIGTERM before QMP is connected.
+	fn stop_qemu(&self, qmp_stream: Option<&UnixStream>) {
+		match qmp_stream {
+			Some(stream) => QmpClient::new(stream).blocking_send_command(&QmpCommand::Quit),
+			None => self.terminate_qemu(),
+		}
+	}
+
+	fn terminate_qemu(&self) {
+		if let Some(pid) = self.qemu_pid {
+			let (Ok(()) | Err(_)) = nix::sys::signal::kill(
+				nix::unistd::Pid::from_raw(pid.try_into().unwrap()),
+				Some(nix::sys::signal::Signal::SIGTERM),
+			);
+		}
+	}
+
 	fn shutdown_controller(
 		self,
 		ipc_socket: &Path,
+		qemu_shut_down: bool,
 		ipc: ScopedJoinHandle<'_, Result<(), ()>>,
 		qemu: ScopedJoinHandle<'_, Result<(), ()>>,
 		qmp: ScopedJoinHandle<'_, Result<(), ()>>,
@@ -169,16 +268,20 @@ impl Controller {
 			Ok(conn) => conn.shutdown(Shutdown::Both).unwrap(),
 			Err(_) => {}
 		}
-		self.qemu_pid.map(|pid| {
-			nix::sys::signal::kill(
-				nix::unistd::Pid::from_raw(pid.try_into().unwrap()),
-				Some(nix::sys::signal::Signal::SIGTERM),
-			)
-		});
-		mem::drop(self.embedder_tx);
+		if !qemu_shut_down {
+			self.terminate_qemu();
+		}
 		qmp.join().unwrap()?;
 		qemu.join().unwrap()?;
 		ipc.join().unwrap()?;
+		// S2E flushes its last edges as QEMU shuts down, which must reach the
+		// model before the session is summarized
+		while let Ok(msg) = self.rx.try_recv() {
+			if let (ControllerMsg::Event(event), Some(tx)) = (msg, self.embedder_tx.as_ref()) {
+				tx.send(EmbedderMsg::Event(event)).unwrap();
+			}
+		}
+		mem::drop(self.embedder_tx);
 		embedder.join().unwrap()?;
 		Ok(())
 	}
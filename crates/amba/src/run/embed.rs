@@ -1,26 +1,23 @@
 //! The worker thread for the gui
 
-use std::{collections::VecDeque, sync::mpsc, thread};
+use std::{collections::VecDeque, path::Path, sync::mpsc, thread};
 
 use disassembler::DisasmContext;
 use eframe::egui::Context;
 use graphui::EmbedderHasConverged;
-use model::Model;
+use model::{Model, StateOutput};
 
-use crate::{run::control::EmbedderMsg, SessionConfig};
+use crate::{open::SessionEvent, run::control::EmbedderMsg};
 
 pub fn run_embedder(
 	model: &Model,
 	rx: mpsc::Receiver<EmbedderMsg>,
 	gui_context: Option<Context>,
-	config: &SessionConfig,
+	executable: Option<&Path>,
+	recipe_dir: &Path,
 ) -> Result<(), ()> {
 	let mut blocking = true;
-	let mut disasm_context = DisasmContext::new(
-		config.executable_host_path().as_ref().map(AsRef::as_ref),
-		config.recipe_path.parent().unwrap(),
-	)
-	.unwrap();
+	let mut disasm_context = DisasmContext::new(executable, recipe_dir).unwrap();
 	let mut thread_pool_size = (thread::available_parallelism().unwrap().get() / 2).max(1);
 	let mut thread_pool = rayon::ThreadPoolBuilder::new()
 		.num_threads(thread_pool_size)
@@ -29,18 +26,20 @@ pub fn run_embedder(
 	let mut unhandled_messages = VecDeque::new();
 	loop {
 		// Poll available messages
+		let mut disconnected = false;
 		loop {
 			match rx.try_recv() {
 				Ok(msg) => unhandled_messages.push_back(msg),
 				Err(mpsc::TryRecvError::Empty) => break,
+				// Still add the edges received last to the model, for reports
 				Err(mpsc::TryRecvError::Disconnected) => {
-					tracing::info!("exiting");
-					return Ok(());
+					disconnected = true;
+					break;
 				}
 			}
 		}
 		// Block awaiting message if none unhandled and `blocking = true`
-		if blocking && unhandled_messages.is_empty() {
+		if blocking && unhandled_messages.is_empty() && !disconnected {
 			match rx.recv() {
 				Ok(msg) => unhandled_messages.push_back(msg),
 				Err(mpsc::RecvError) => {
@@ -52,16 +51,16 @@ pub fn run_embedder(
 		// Handle messages
 		while let Some(message) = unhandled_messages.pop_front() {
 			match message {
-				EmbedderMsg::UpdateEdges {
+				EmbedderMsg::Event(SessionEvent::NewEdges {
 					mut block_edges,
 					mut state_edges,
-				} => {
+				}) => {
 					let mut update_chunk_count = 1;
-					// Append additional sequential `EmbedderMsg::UpdateEdges`
-					while let Some(EmbedderMsg::UpdateEdges {
+					// Append additional sequential `SessionEvent::NewEdges`
+					while let Some(EmbedderMsg::Event(SessionEvent::NewEdges {
 						block_edges: block_extra,
 						state_edges: state_extra,
-					}) = unhandled_messages.front()
+					})) = unhandled_messages.front()
 					{
 						block_edges.extend_from_slice(&block_extra);
 						state_edges.extend_from_slice(&state_extra);
@@ -74,6 +73,25 @@ pub fn run_embedder(
 					blocking = false;
 					continue;
 				}
+				EmbedderMsg::Event(SessionEvent::StateOutput {
+					amba_state_id,
+					stdout,
+					stderr,
+				}) => {
+					model.add_state_output(
+						amba_state_id,
+						StateOutput { stdout, stderr },
+						&mut disasm_context,
+					);
+					blocking = false;
+				}
+				EmbedderMsg::Event(SessionEvent::StateTermination {
+					amba_state_id,
+					termination,
+				}) => {
+					model.add_state_termination(amba_state_id, termination, &mut disasm_context);
+					blocking = false;
+				}
 				EmbedderMsg::WakeUp => {
 					blocking = false;
 					continue;
@@ -90,6 +108,10 @@ pub fn run_embedder(
 				}
 			}
 		}
+		if disconnected {
+			tracing::info!("exiting");
+			return Ok(());
+		}
 		match thread_pool.install(|| model.run_layout_iterations()) {
 			EmbedderHasConverged::Yes => blocking = true,
 			EmbedderHasConverged::No => {}
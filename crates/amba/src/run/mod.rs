@@ -1,4 +1,6 @@
+pub mod budget;
 pub mod control;
 pub mod embed;
 pub mod runners;
 pub mod session;
+pub mod vm;
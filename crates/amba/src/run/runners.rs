@@ -3,25 +3,33 @@
 #![allow(unsafe_code)]
 
 use std::{
+	collections::HashMap,
 	ffi::{OsStr, OsString},
 	os::unix::{net::UnixStream, process::CommandExt},
 	path::Path,
-	process::{self, Command, ExitStatus},
+	process::{self, Command},
 	sync::mpsc,
 	thread,
 	time::Duration,
 };
 
-use ipc::{IpcError, IpcMessage, IpcRx};
+use ipc::{IpcError, IpcMessage, IpcServerRx};
+use model::WorkerStateIds;
 use qmp_client::{QmpClient, QmpCommand, QmpError, QmpEvent};
 
 use crate::{
 	cmd::Cmd,
+	open::{SessionEvent, SessionRecorder},
 	run::{control::ControllerMsg, session::S2EConfig},
+	sessions::SessionMetadata,
 	SessionConfig,
 };
 
-pub fn prepare_run(cmd: &mut Cmd, config: &SessionConfig) -> Result<(), ()> {
+pub fn prepare_run(
+	cmd: &mut Cmd,
+	config: &SessionConfig,
+	qmp_socket: &Path,
+) -> Result<SessionMetadata, ()> {
 	fn data_dir_has_been_initialized(cmd: &mut Cmd, data_dir: &Path) -> bool {
 		let version_file = &data_dir.join("version.txt");
 		let version = version_file
@@ -39,16 +47,11 @@ pub fn prepare_run(cmd: &mut Cmd, config: &SessionConfig) -> Result<(), ()> {
 			?config.base.data_dir,
 			"AMBA_DATA_DIR has not been initialized"
 		);
+		// Created by `SessionConfig::new`, and still empty
+		cmd.remove_dir(&config.session_dir);
 		return Err(());
 	}
 
-	if config.session_dir.exists() {
-		tracing::error!(
-			?config.session_dir,
-			"session_dir already exists, are multiple amba instances running concurrently?"
-		);
-		return Err(());
-	}
 	if config.temp_dir.exists() {
 		tracing::error!(
 			?config.temp_dir,
@@ -56,8 +59,8 @@ pub fn prepare_run(cmd: &mut Cmd, config: &SessionConfig) -> Result<(), ()> {
 		);
 		return Err(());
 	}
-	cmd.create_dir_all(&config.session_dir);
 	cmd.create_dir_all(&config.temp_dir);
+	let metadata = SessionMetadata::start(cmd, config);
 
 	// Populate the `session_dir`
 	S2EConfig::new(
@@ -65,6 +68,8 @@ pub fn prepare_run(cmd: &mut Cmd, config: &SessionConfig) -> Result<(), ()> {
 		&config.session_dir,
 		&config.recipe_path,
 		&config.recipe,
+		&config.seeds,
+		&qemu_command(config, qmp_socket),
 	)
 	.save_to(
 		cmd,
@@ -72,29 +77,89 @@ pub fn prepare_run(cmd: &mut Cmd, config: &SessionConfig) -> Result<(), ()> {
 		&config.session_dir,
 	);
 
-	Ok(())
+	Ok(metadata)
 }
 
-pub fn run_ipc(mut ipc_rx: IpcRx, controller_tx: mpsc::Sender<ControllerMsg>) -> Result<(), ()> {
+/// Receive from every S2E process, giving their states ids that are unique
+/// within the session before anything is recorded.
+pub fn run_ipc(
+	mut ipc_rx: IpcServerRx,
+	config: &SessionConfig,
+	controller_tx: mpsc::Sender<ControllerMsg>,
+) -> Result<(), ()> {
+	let mut session_recorder = SessionRecorder::new(&config.session_dir, &config.seeds);
+	let mut state_ids = WorkerStateIds::new();
+	let mut workers_by_pid = HashMap::new();
 	loop {
-		match ipc_rx.blocking_receive() {
-			Ok(IpcMessage::NewEdges {
-				state_edges,
-				block_edges,
-			}) => {
-				controller_tx
-					.send(ControllerMsg::UpdateEdges {
-						state_edges,
-						block_edges,
-					})
-					.unwrap_or_else(|mpsc::SendError(_)| {
-						tracing::warn!("ipc failed messaging controller: already shut down");
-					});
+		let mut event = match ipc_rx.blocking_receive() {
+			Ok((worker, IpcMessage::Hello { pid, forked_from })) => {
+				tracing::info!(worker, pid, ?forked_from, "S2E process started");
+				workers_by_pid.insert(pid, worker);
+				if let Some(origin) = forked_from {
+					if let Some(&parent) = workers_by_pid.get(&origin.parent_pid) {
+						state_ids.fork(worker, parent, origin);
+					} else {
+						tracing::warn!(
+							worker,
+							?origin,
+							"S2E process forked from an unknown process"
+						);
+					}
+				}
+				continue;
+			}
+			Ok((
+				worker,
+				IpcMessage::NewEdges {
+					mut state_edges,
+					mut block_edges,
+				},
+			)) => {
+				state_ids.translate_edges(worker, &mut state_edges);
+				state_ids.translate_edges(worker, &mut block_edges);
+				SessionEvent::NewEdges {
+					state_edges,
+					block_edges,
+				}
+			}
+			Ok((
+				worker,
+				IpcMessage::StateOutput {
+					amba_state_id,
+					s2e_state_id: _,
+					stdout,
+					stderr,
+				},
+			)) => SessionEvent::StateOutput {
+				amba_state_id: state_ids.global(worker, amba_state_id),
+				stdout,
+				stderr,
+			},
+			Ok((
+				worker,
+				IpcMessage::StateTermination {
+					amba_state_id,
+					s2e_state_id: _,
+					termination,
+				},
+			)) => SessionEvent::StateTermination {
+				amba_state_id: state_ids.global(worker, amba_state_id),
+				termination,
+			},
+			Ok((worker, msg)) => {
+				tracing::info!(worker, ?msg);
+				continue;
 			}
-			Ok(msg) => tracing::info!(?msg),
 			Err(IpcError::EndOfFile) => break,
 			Err(other) => panic!("ipc error: {other:?}"),
-		}
+		};
+		session_recorder.record(&event);
+		event.name_concrete_inputs(&config.recipe, &config.seeds);
+		controller_tx
+			.send(ControllerMsg::Event(event))
+			.unwrap_or_else(|mpsc::SendError(_)| {
+				tracing::warn!("ipc failed messaging controller: already shut down");
+			});
 	}
 	Ok(())
 }
@@ -105,6 +170,50 @@ pub fn run_qemu(
 	qmp_socket: &Path,
 	controller_tx: mpsc::Sender<ControllerMsg>,
 ) -> Result<(), ()> {
+	let mut command = qemu_command(config, qmp_socket);
+	assert!(config.session_dir.join("s2e-config.lua").exists());
+
+	if config.sigstop_before_qemu_exec {
+		// Before exec, and hence actually starting QEMU, the child process sends
+		// SIGSTOP to itself. We can then start debugging by attaching to the QEMU pid
+		// and sending SIGCONT
+		// SAFETY: `raise` and `write` are async-safe. We do not allocate memory.
+		unsafe {
+			let mut buf = String::with_capacity(256);
+			command.pre_exec(move || {
+				use std::fmt::Write;
+
+				let _ = writeln!(
+					buf,
+					"[pre-exec before SIGSTOP] stopped with pid={}",
+					process::id()
+				);
+
+				nix::unistd::write(2, buf.as_ref())?;
+				nix::sys::signal::raise(nix::sys::signal::Signal::SIGSTOP)?;
+				nix::unistd::write(2, b"[pre-exec after SIGSTOP] resuming!\n")?;
+
+				Ok(())
+			});
+		}
+	}
+
+	let status = cmd.command_spawn_wait_with_pid(&mut command, |pid| {
+		controller_tx.send(ControllerMsg::TellQemuPid(pid)).unwrap();
+	});
+	match status.success() {
+		true => Ok(()),
+		false => {
+			tracing::error!(?status, "qemu exited with error code");
+			Err(())
+		}
+	}
+}
+
+/// QEMU+S2E as launched for the session of `config`, with the resources and
+/// options of `config.vm`. The same command is written to `launch-s2e.sh` in
+/// the session directory.
+pub fn qemu_command(config: &SessionConfig, qmp_socket: &Path) -> Command {
 	// supporting single- vs multi-path
 	let s2e_mode = match true {
 		true => "s2e",
@@ -119,89 +228,25 @@ pub fn run_qemu(
 	let libs2e_dir = &config.base.dependencies_dir.join("share/libs2e");
 	let libs2e = &libs2e_dir.join(format!("libs2e-{arch}-{s2e_mode}.so"));
 	let s2e_config = &config.session_dir.join("s2e-config.lua");
-	let max_processes = 1;
 	let image = &config
 		.base
 		.data_dir
 		.join("images/ubuntu-22.04-x86_64/image.raw.s2e");
-
-	let status = run_qemu_inner(
-		cmd,
-		config.sigstop_before_qemu_exec,
-		qemu,
-		&config.temp_dir,
-		libs2e,
-		libs2e_dir,
-		s2e_config,
-		max_processes,
-		image,
-		qmp_socket,
-		|pid| controller_tx.send(ControllerMsg::TellQemuPid(pid)).unwrap(),
-	);
-	match status.success() {
-		true => Ok(()),
-		false => {
-			tracing::error!(?status, "qemu exited with error code");
-			Err(())
-		}
-	}
-}
-
-pub fn run_qemu_inner(
-	cmd: &mut Cmd,
-	sigstop_qemu_on_fork: bool,
-	qemu: &Path,
-	temp_dir: &Path,
-	libs2e: &Path,
-	libs2e_dir: &Path,
-	s2e_config: &Path,
-	max_processes: u16,
-	image: &Path,
-	qmp_socket: &Path,
-	with_pid: impl FnOnce(u32),
-) -> ExitStatus {
 	assert!(qemu.exists());
 	assert!(libs2e.exists());
-	assert!(s2e_config.exists());
 	assert!(libs2e_dir.exists());
 
 	let mut command = Command::new(qemu);
 	command
-		.current_dir(temp_dir)
+		.current_dir(&config.temp_dir)
 		.env("LD_PRELOAD", libs2e)
 		.env("S2E_CONFIG", s2e_config)
 		.env("S2E_SHARED_DIR", libs2e_dir)
-		.env("S2E_MAX_PROCESSES", max_processes.to_string())
-		.env("S2E_UNBUFFERED_STREAM", "1");
-
-	if sigstop_qemu_on_fork {
-		// Before exec, and hence actually starting QEMU, the child process sends
-		// SIGSTOP to itself. We can then start debugging by attaching to the QEMU pid
-		// and sending SIGCONT
-		// SAFETY: `raise` and `write` are async-safe. We do not allocate memory.
-		unsafe {
-			let mut buf = String::with_capacity(256);
-			command.pre_exec(move || {
-				use std::fmt::Write;
-
-				let _ = writeln!(
-					buf,
-					"[pre-exec before SIGSTOP] stopped with pid={}",
-					process::id()
-				);
-
-				nix::unistd::write(2, buf.as_ref())?;
-				nix::sys::signal::raise(nix::sys::signal::Signal::SIGSTOP)?;
-				nix::unistd::write(2, b"[pre-exec after SIGSTOP] resuming!\n")?;
-
-				Ok(())
-			});
-		}
-	}
-	if max_processes > 1 {
-		command.arg("-nographic");
-	}
-	command
+		.env(
+			"S2E_MAX_PROCESSES",
+			config.vm.processes.to_string(),
+		)
+		.env("S2E_UNBUFFERED_STREAM", "1")
 		.arg("-qmp")
 		.arg({
 			let mut line = OsString::new();
@@ -222,24 +267,8 @@ pub fn run_qemu_inner(
 			line.push(",format=s2e,cache=writeback");
 			line
 		})
-		.args([
-			"-k",
-			"en-us",
-			"-monitor",
-			"null",
-			"-m",
-			"256M",
-			"-enable-kvm",
-			"-serial",
-			"file:/dev/stdout",
-			"-net",
-			"none",
-			"-net",
-			"nic,model=e1000",
-			"-loadvm",
-			"ready",
-		]);
-	cmd.command_spawn_wait_with_pid(&mut command, with_pid)
+		.args(config.vm.qemu_args());
+	command
 }
 
 pub fn run_qmp(socket: &Path, controller_tx: mpsc::Sender<ControllerMsg>) -> Result<(), ()> {
@@ -277,6 +306,14 @@ pub fn run_qmp(socket: &Path, controller_tx: mpsc::Sender<ControllerMsg>) -> Res
 		.unwrap();
 	tracing::info!(?status, "QMP");
 
+	// For the controller to quit QEMU with once the budget is exhausted
+	let controller_stream = stream
+		.try_clone()
+		.map_err(|err| tracing::error!(?err, "Cloning QMP stream"))?;
+	controller_tx
+		.send(ControllerMsg::TellQmpStream(controller_stream))
+		.unwrap();
+
 	loop {
 		match qmp.blocking_receive() {
 			Ok(response) => {
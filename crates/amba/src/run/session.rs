@@ -2,15 +2,17 @@
 
 use std::{
 	error::Error,
+	ffi::OsStr,
 	path::{Path, PathBuf},
+	process::Command,
 };
 
 use include_dir::{include_dir, Dir};
-use recipe::{FileSource, Recipe};
+use recipe::{ConstrainedRange, FileSource, Recipe};
 use serde::Serialize;
 use tera::{Context, Tera};
 
-use crate::cmd::Cmd;
+use crate::cmd::{shell_quote, Cmd};
 
 /// All data required to populate the templates in `crates/amba/templates/`.
 /// The templates are kept as close to the upstream S2E templates as possible.
@@ -21,6 +23,7 @@ pub struct S2EConfig {
 	creation_time: &'static str,
 	project_dir: PathBuf,
 	host_files_dir: PathBuf,
+	seeds_dir: PathBuf,
 	use_seeds: bool,
 	project_name: &'static str,
 	modules: Vec<[String; 1]>,
@@ -40,6 +43,19 @@ pub struct S2EConfig {
 	guestfs_paths: Vec<PathBuf>,
 	use_test_case_generator: bool,
 	enable_cfi: bool,
+	/// The inclusive byte ranges allowed by each constrained symbolic range, in
+	/// the order of `Recipe::constrained_ranges`
+	byte_constraints: Vec<Vec<[u8; 2]>>,
+	launch: Launch,
+}
+
+/// The QEMU command line of the session, shell quoted for `launch-s2e.sh`.
+#[derive(Serialize)]
+pub struct Launch {
+	current_dir: String,
+	/// As `NAME=value`
+	env: Vec<String>,
+	command_line: String,
 }
 
 #[derive(Serialize)]
@@ -56,6 +72,10 @@ pub struct Args {
 	resolved_args: Vec<String>,
 }
 
+/// The directory within the session directory holding the seeds of the
+/// recipe, named by `recipe::seed_file_name`.
+pub const SEEDS_DIR: &str = "seeds";
+
 const LIBRARY_LUA: &str = include_str!("../../data/library.lua");
 const TEMPLATE_DIR: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/templates");
 const BOOTSTRAP_SH_CONTENT: &str = "
@@ -67,28 +87,51 @@ RUST_BACKTRACE=1 ./bootstrap.elf 2>&1
 impl S2EConfig {
 	/// Default template parameters. Update this to change the S2E run time
 	/// configuration.
-	pub fn new(cmd: &mut Cmd, session_dir: &Path, recipe_path: &Path, recipe: &Recipe) -> Self {
+	pub fn new(
+		cmd: &mut Cmd,
+		session_dir: &Path,
+		recipe_path: &Path,
+		recipe: &Recipe,
+		seeds: &[PathBuf],
+		qemu_command: &Command,
+	) -> Self {
 		let host_files_dir = session_dir.join("hostfiles");
 		cmd.create_dir_all(&host_files_dir);
+		// Bootstrap moves the files to their guest paths and creates the
+		// symlinks, see `recipe::host_file_name`
 		for (guest_path, source) in &recipe.files {
-			let guest_path = Path::new(guest_path);
-			assert!(guest_path.is_relative());
+			assert!(Path::new(guest_path).is_relative());
+			let host_file_path = host_files_dir.join(recipe::host_file_name(guest_path));
 			match source {
-				FileSource::Host(host_path) | FileSource::SymbolicHost { host_path, .. } => {
+				FileSource::Host(host_path)
+				| FileSource::HostWithMode { host_path, .. }
+				| FileSource::SymbolicHost { host_path, .. } => {
 					cmd.copy(
 						recipe_path.parent().unwrap().join(host_path),
-						host_files_dir.join(guest_path),
+						host_file_path,
 					);
 				}
 				FileSource::SymbolicContent { seed, .. } => {
-					cmd.write(host_files_dir.join(guest_path), seed);
+					cmd.write(host_file_path, seed);
 				}
+				FileSource::Symlink { .. } => {}
 			}
 		}
+		// The recipe is already resolved, so bootstrap never sees `extend` or
+		// `${VAR}` placeholders
 		cmd.write(
 			host_files_dir.join("recipe.json"),
 			&serde_json::to_vec(recipe).unwrap(),
 		);
+		// `SeedSearcher` picks up seeds from here, and bootstrap fetches them
+		// by name
+		let seeds_dir = session_dir.join(SEEDS_DIR);
+		if recipe.seeds.is_some() {
+			cmd.create_dir_all(&seeds_dir);
+		}
+		for (i, seed) in seeds.iter().enumerate() {
+			cmd.copy(seed, seeds_dir.join(recipe::seed_file_name(i)));
+		}
 
 		Self {
 			library_lua_path: session_dir.join("library.lua"),
@@ -96,7 +139,8 @@ impl S2EConfig {
 			creation_time: "CREATION_TIME",
 			project_dir: session_dir.to_owned(),
 			host_files_dir,
-			use_seeds: false,
+			seeds_dir,
+			use_seeds: recipe.seeds.is_some(),
 			project_name: "PROJECT_NAME",
 			modules: vec![[recipe.executable_path.clone()]],
 			processes: vec![recipe.executable_path.clone()],
@@ -123,6 +167,12 @@ impl S2EConfig {
 			guestfs_paths: Vec::new(),
 			use_test_case_generator: true,
 			enable_cfi: false,
+			byte_constraints: recipe
+				.constrained_ranges()
+				.iter()
+				.map(ConstrainedRange::allowed)
+				.collect(),
+			launch: Launch::new(qemu_command),
 		}
 	}
 
@@ -149,6 +199,37 @@ impl S2EConfig {
 		tracing::debug!(TEMPLATE_DIR = ?TEMPLATE_DIR.path(), "Using templates from");
 		let mut renderer = Renderer::new(cmd, session_dir, self);
 		renderer.render("s2e-config.lua");
+		renderer.render("launch-s2e.sh");
+		cmd.set_permissions(session_dir.join("launch-s2e.sh"), 0o755);
+	}
+}
+
+impl Launch {
+	fn new(command: &Command) -> Self {
+		Self {
+			current_dir: shell_quote(
+				command
+					.get_current_dir()
+					.map_or(OsStr::new("."), Path::as_os_str),
+			)
+			.to_string_lossy()
+			.into_owned(),
+			env: command
+				.get_envs()
+				.filter_map(|(name, value)| {
+					Some(format!(
+						"{}={}",
+						name.to_string_lossy(),
+						shell_quote(value?).to_string_lossy()
+					))
+				})
+				.collect(),
+			command_line: std::iter::once(command.get_program())
+				.chain(command.get_args())
+				.map(|arg| shell_quote(arg).to_string_lossy().into_owned())
+				.collect::<Vec<_>>()
+				.join(" "),
+		}
 	}
 }
 
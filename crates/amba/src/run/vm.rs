@@ -0,0 +1,117 @@
+//! The resources and launch options of the QEMU virtual machine
+
+use std::{fs, io};
+
+use serde::Deserialize;
+
+use crate::{BaseConfig, VmArgs};
+
+/// The optional config file within the data directory, in place of which
+/// another can be given with `--vm-config`. Options given on the command line
+/// take precedence over it.
+pub const VM_CONFIG_FILE: &str = "vm.json";
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct VmConfig {
+	/// Guest memory, as for `-m` of QEMU
+	pub memory: String,
+	pub accelerator: Accelerator,
+	/// The snapshot of the guest image to boot from, which `amba init` creates
+	/// as `ready`
+	pub snapshot: String,
+	/// The most S2E processes that states are spread over
+	pub processes: u16,
+	/// Appended to the QEMU command line
+	pub qemu_args: Vec<String>,
+}
+
+/// How QEMU executes the guest.
+#[derive(Deserialize, clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Accelerator {
+	/// Through the KVM interface, which libs2e provides to QEMU
+	Kvm,
+	/// The Tiny Code Generator of QEMU, for hosts without KVM
+	Tcg,
+}
+
+impl Default for VmConfig {
+	fn default() -> Self {
+		Self {
+			memory: "256M".to_owned(),
+			accelerator: Accelerator::Kvm,
+			snapshot: "ready".to_owned(),
+			processes: 1,
+			qemu_args: Vec::new(),
+		}
+	}
+}
+
+impl VmConfig {
+	/// The config file given by `args`, or otherwise [`VM_CONFIG_FILE`] if it
+	/// exists, overridden by the options of `args`.
+	pub fn load(base: &BaseConfig, args: &VmArgs) -> Result<Self, ()> {
+		let path = match &args.vm_config {
+			Some(path) => path.clone(),
+			None => base.data_dir.join(VM_CONFIG_FILE),
+		};
+		let mut vm: Self = match fs::read(&path) {
+			Ok(json) => serde_json::from_slice(&json)
+				.map_err(|err| tracing::error!(?err, ?path, "Invalid VM config"))?,
+			Err(err) if args.vm_config.is_none() && err.kind() == io::ErrorKind::NotFound => {
+				Self::default()
+			}
+			Err(err) => {
+				tracing::error!(?err, ?path, "Reading VM config");
+				return Err(());
+			}
+		};
+
+		if let Some(memory) = &args.memory {
+			vm.memory = memory.clone();
+		}
+		if let Some(accelerator) = args.accelerator {
+			vm.accelerator = accelerator;
+		}
+		if let Some(snapshot) = &args.snapshot {
+			vm.snapshot = snapshot.clone();
+		}
+		if let Some(processes) = args.processes {
+			vm.processes = processes;
+		}
+		vm.qemu_args.extend(args.qemu_arg.iter().cloned());
+
+		if vm.processes == 0 {
+			tracing::error!("S2E needs at least one process");
+			return Err(());
+		}
+		Ok(vm)
+	}
+
+	/// The QEMU arguments that do not refer to files of the session.
+	pub fn qemu_args(&self) -> Vec<String> {
+		let mut args = Vec::new();
+		// Processes forked by S2E cannot share a display
+		if self.processes > 1 {
+			args.push("-nographic");
+		}
+		args.extend(["-k", "en-us", "-monitor", "null", "-m", &self.memory]);
+		match self.accelerator {
+			Accelerator::Kvm => args.push("-enable-kvm"),
+			Accelerator::Tcg => args.extend(["-accel", "tcg"]),
+		}
+		args.extend([
+			"-serial",
+			"file:/dev/stdout",
+			"-net",
+			"none",
+			"-net",
+			"nic,model=e1000",
+			"-loadvm",
+			&self.snapshot,
+		]);
+		args.extend(self.qemu_args.iter().map(String::as_str));
+		args.into_iter().map(str::to_owned).collect()
+	}
+}
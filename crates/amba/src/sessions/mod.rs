@@ -0,0 +1,442 @@
+//! The sessions subcommand, and the metadata file that sessions are listed by
+
+use std::{
+	env, fmt, fs, io,
+	path::{Path, PathBuf},
+	process,
+	time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Local};
+use model::Model;
+use serde::{Deserialize, Serialize};
+
+use crate::{cmd::Cmd, BaseConfig, SessionConfig, SessionsArgs, SessionsCommand};
+
+/// The file in the session directory that describes the session.
+pub const METADATA_FILE: &str = "metadata.json";
+
+/// Written when a session starts, and again when it finishes.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SessionMetadata {
+	/// The recipe, as given to `amba run`
+	pub recipe_path: PathBuf,
+	/// The command line of `amba run`
+	pub arguments: Vec<String>,
+	/// The amba process running the session
+	pub pid: u32,
+	/// In RFC 3339 format
+	pub started_at: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub finished: Option<SessionEnd>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SessionEnd {
+	/// In RFC 3339 format
+	pub finished_at: String,
+	pub success: bool,
+	pub states: usize,
+	pub blocks: usize,
+}
+
+impl SessionMetadata {
+	pub fn start(cmd: &mut Cmd, config: &SessionConfig) -> Self {
+		let metadata = Self {
+			recipe_path: env::current_dir().unwrap().join(&config.recipe_path),
+			arguments: env::args().collect(),
+			pid: process::id(),
+			started_at: Local::now().to_rfc3339(),
+			finished: None,
+		};
+		metadata.save(cmd, &config.session_dir);
+		metadata
+	}
+
+	pub fn finish(mut self, cmd: &mut Cmd, session_dir: &Path, model: &Model, success: bool) {
+		self.finished = Some(SessionEnd {
+			finished_at: Local::now().to_rfc3339(),
+			success,
+			states: model.state_count(),
+			blocks: model.block_count(),
+		});
+		self.save(cmd, session_dir);
+	}
+
+	fn save(&self, cmd: &mut Cmd, session_dir: &Path) {
+		cmd.write(
+			session_dir.join(METADATA_FILE),
+			serde_json::to_vec_pretty(self).unwrap(),
+		);
+	}
+
+	fn load(session_dir: &Path) -> Option<Self> {
+		let metadata = fs::read(session_dir.join(METADATA_FILE)).ok()?;
+		serde_json::from_slice(&metadata)
+			.map_err(|err| tracing::warn!(?err, ?session_dir, "Invalid session metadata"))
+			.ok()
+	}
+}
+
+/// Create a new session directory within `data_dir`, named by the current time
+/// and, should another session have started within the same second, a
+/// counter.
+pub fn create_session_dir(data_dir: &Path) -> Result<PathBuf, ()> {
+	let timestamp = Local::now().format("%Y-%m-%dT%H:%M:%S");
+	for attempt in 1.. {
+		let session_dir = match attempt {
+			1 => data_dir.join(timestamp.to_string()),
+			n => data_dir.join(format!("{timestamp}-{n}")),
+		};
+		// Unlike `create_dir_all`, fails if another amba instance got there first
+		match fs::create_dir(&session_dir) {
+			Ok(()) => return Ok(session_dir),
+			Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+			Err(err) => {
+				tracing::error!(
+					?err,
+					?session_dir,
+					"Creating session directory, has amba been initialized?"
+				);
+				return Err(());
+			}
+		}
+	}
+	unreachable!()
+}
+
+struct Session {
+	id: String,
+	dir: PathBuf,
+	/// `None` for sessions started before sessions had metadata
+	metadata: Option<SessionMetadata>,
+	started_at: DateTime<Local>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+	Running,
+	Finished,
+	Failed,
+	/// Amba exited without finishing the session
+	Interrupted,
+	Unknown,
+}
+
+impl fmt::Display for Status {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.pad(match self {
+			Self::Running => "running",
+			Self::Finished => "finished",
+			Self::Failed => "failed",
+			Self::Interrupted => "interrupted",
+			Self::Unknown => "unknown",
+		})
+	}
+}
+
+impl Session {
+	/// `None` if `dir` is not a session directory.
+	fn load(dir: PathBuf) -> Option<Self> {
+		// Older sessions are recognised by the S2E config that every session has
+		if !dir.join(METADATA_FILE).is_file() && !dir.join("s2e-config.lua").is_file() {
+			return None;
+		}
+		let metadata = SessionMetadata::load(&dir);
+		let started_at = metadata
+			.as_ref()
+			.and_then(|metadata| DateTime::parse_from_rfc3339(&metadata.started_at).ok())
+			.map_or_else(
+				|| {
+					let modified = fs::metadata(&dir)
+						.and_then(|metadata| metadata.modified())
+						.unwrap_or(SystemTime::UNIX_EPOCH);
+					DateTime::from(modified)
+				},
+				DateTime::from,
+			);
+		Some(Self {
+			id: dir.file_name()?.to_string_lossy().into_owned(),
+			dir,
+			metadata,
+			started_at,
+		})
+	}
+
+	fn status(&self) -> Status {
+		let Some(metadata) = &self.metadata else {
+			return Status::Unknown;
+		};
+		match &metadata.finished {
+			Some(SessionEnd { success: true, .. }) => Status::Finished,
+			Some(SessionEnd { success: false, .. }) => Status::Failed,
+			None if process_is_alive(metadata.pid) => Status::Running,
+			None => Status::Interrupted,
+		}
+	}
+
+	fn duration(&self) -> Option<Duration> {
+		let finished_at = match &self.metadata.as_ref()?.finished {
+			Some(end) => DateTime::parse_from_rfc3339(&end.finished_at).ok()?.into(),
+			None if self.status() == Status::Running => Local::now(),
+			None => return None,
+		};
+		(finished_at - self.started_at).to_std().ok()
+	}
+
+	fn recipe(&self) -> String {
+		match &self.metadata {
+			Some(metadata) => metadata.recipe_path.display().to_string(),
+			None => "-".to_owned(),
+		}
+	}
+}
+
+fn process_is_alive(pid: u32) -> bool {
+	let Ok(pid) = pid.try_into() else {
+		return false;
+	};
+	nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), None).is_ok()
+}
+
+/// The sessions within `data_dir`, oldest first.
+fn sessions(data_dir: &Path) -> Result<Vec<Session>, ()> {
+	let entries = fs::read_dir(data_dir)
+		.map_err(|err| tracing::error!(?err, ?data_dir, "Reading data directory"))?;
+	let mut sessions = Vec::new();
+	for entry in entries {
+		let dir = entry
+			.map_err(|err| tracing::error!(?err, ?data_dir, "Reading data directory"))?
+			.path();
+		sessions.extend(Session::load(dir));
+	}
+	sessions.sort_by(|a, b| (a.started_at, &a.id).cmp(&(b.started_at, &b.id)));
+	Ok(sessions)
+}
+
+pub fn sessions_command(
+	cmd: &mut Cmd,
+	base: &BaseConfig,
+	SessionsArgs { command }: SessionsArgs,
+) -> Result<(), ()> {
+	match command {
+		SessionsCommand::List => list(base),
+		SessionsCommand::Show { session } => show(base, session),
+		SessionsCommand::Prune {
+			older_than,
+			keep,
+			dry_run,
+		} => prune(cmd, base, older_than, keep, dry_run),
+	}
+}
+
+fn list(base: &BaseConfig) -> Result<(), ()> {
+	let sessions = sessions(&base.data_dir)?;
+	println!(
+		"{:<24} {:<11} {:<19} {:>10} {:>7} {:>7}  RECIPE",
+		"ID", "STATUS", "STARTED", "DURATION", "STATES", "BLOCKS"
+	);
+	for session in &sessions {
+		let end = session
+			.metadata
+			.as_ref()
+			.and_then(|metadata| metadata.finished.as_ref());
+		println!(
+			"{:<24} {:<11} {:<19} {:>10} {:>7} {:>7}  {}",
+			session.id,
+			session.status(),
+			session.started_at.format("%Y-%m-%d %H:%M:%S"),
+			session.duration().map_or("-".to_owned(), format_duration),
+			end.map_or("-".to_owned(), |end| end.states.to_string()),
+			end.map_or("-".to_owned(), |end| end.blocks.to_string()),
+			session.recipe(),
+		);
+	}
+	Ok(())
+}
+
+fn show(base: &BaseConfig, session: PathBuf) -> Result<(), ()> {
+	let dir = base.session_dir(session);
+	let Some(session) = Session::load(dir.clone()) else {
+		tracing::error!(?dir, "Not a session directory");
+		return Err(());
+	};
+	println!("id:        {}", session.id);
+	println!("directory: {}", session.dir.display());
+	println!("status:    {}", session.status());
+	println!("recipe:    {}", session.recipe());
+	println!(
+		"started:   {}",
+		session.started_at.format("%Y-%m-%d %H:%M:%S")
+	);
+	if let Some(metadata) = &session.metadata {
+		if let Some(end) = &metadata.finished {
+			if let Ok(finished_at) = DateTime::parse_from_rfc3339(&end.finished_at) {
+				println!(
+					"finished:  {}",
+					DateTime::<Local>::from(finished_at).format("%Y-%m-%d %H:%M:%S")
+				);
+			}
+		}
+		println!("command:   {}", metadata.arguments.join(" "));
+		println!("pid:       {}", metadata.pid);
+	}
+	if let Some(duration) = session.duration() {
+		println!("duration:  {}", format_duration(duration));
+	}
+	if let Some(end) = session
+		.metadata
+		.as_ref()
+		.and_then(|metadata| metadata.finished.as_ref())
+	{
+		println!("states:    {}", end.states);
+		println!("blocks:    {}", end.blocks);
+	}
+	Ok(())
+}
+
+/// Remove the sessions that are older than `older_than`, or not among the
+/// newest `keep`, or both if both are given. Running sessions are kept.
+fn prune(
+	cmd: &mut Cmd,
+	base: &BaseConfig,
+	older_than: Option<Duration>,
+	keep: Option<usize>,
+	dry_run: bool,
+) -> Result<(), ()> {
+	let sessions = sessions(&base.data_dir)?;
+	for session in prunable(sessions, Local::now(), older_than, keep) {
+		println!("{}", session.dir.display());
+		if !dry_run {
+			cmd.remove_dir_all(&session.dir);
+		}
+	}
+	Ok(())
+}
+
+/// The sessions that `prune` removes, out of `sessions` sorted oldest first.
+fn prunable(
+	sessions: Vec<Session>,
+	now: DateTime<Local>,
+	older_than: Option<Duration>,
+	keep: Option<usize>,
+) -> Vec<Session> {
+	let newest_kept = sessions.len().saturating_sub(keep.unwrap_or(0));
+	sessions
+		.into_iter()
+		.enumerate()
+		.filter(|(index, session)| {
+			let too_old = match older_than {
+				Some(older_than) => (now - session.started_at)
+					.to_std()
+					.is_ok_and(|age| age > older_than),
+				None => true,
+			};
+			let too_many = keep.is_none() || *index < newest_kept;
+			if !too_old || !too_many {
+				return false;
+			}
+			if session.status() == Status::Running {
+				tracing::info!(session = session.id, "Keeping running session");
+				return false;
+			}
+			true
+		})
+		.map(|(_, session)| session)
+		.collect()
+}
+
+/// As `{h}h{m}m{s}s`, like the timestamps of the logs.
+pub fn format_duration(duration: Duration) -> String {
+	let seconds = duration.as_secs();
+	format!(
+		"{}h{}m{}s",
+		seconds / (60 * 60),
+		(seconds / 60) % 60,
+		seconds % 60
+	)
+}
+
+#[cfg(test)]
+mod test {
+	use chrono::TimeZone;
+
+	use crate::sessions::*;
+
+	/// Sessions started 1 to 5 days before `now`, oldest first, of which the
+	/// one started 3 days ago is still running.
+	fn sessions(now: DateTime<Local>) -> Vec<Session> {
+		(1..=5)
+			.rev()
+			.map(|days| {
+				let started_at = now - chrono::Duration::days(days);
+				let finished = (days != 3).then(|| SessionEnd {
+					finished_at: now.to_rfc3339(),
+					success: true,
+					states: 1,
+					blocks: 1,
+				});
+				Session {
+					id: format!("{days}d"),
+					dir: PathBuf::from(format!("{days}d")),
+					metadata: Some(SessionMetadata {
+						recipe_path: PathBuf::from("recipe.json"),
+						arguments: Vec::new(),
+						// This test process is alive, so the unfinished session is running
+						pid: process::id(),
+						started_at: started_at.to_rfc3339(),
+						finished,
+					}),
+					started_at,
+				}
+			})
+			.collect()
+	}
+
+	fn prunable_ids(older_than: Option<Duration>, keep: Option<usize>) -> Vec<String> {
+		let now = Local.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap();
+		prunable(sessions(now), now, older_than, keep)
+			.into_iter()
+			.map(|session| session.id)
+			.collect()
+	}
+
+	const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+	#[test]
+	fn older_than() {
+		assert_eq!(
+			prunable_ids(Some(DAY * 2 + DAY / 2), None),
+			["5d", "4d"]
+		);
+		assert_eq!(
+			prunable_ids(Some(DAY / 2), None),
+			["5d", "4d", "2d", "1d"]
+		);
+		assert!(prunable_ids(Some(DAY * 6), None).is_empty());
+	}
+
+	#[test]
+	fn keep() {
+		assert_eq!(prunable_ids(None, Some(2)), ["5d", "4d"]);
+		assert_eq!(prunable_ids(None, Some(1)), ["5d", "4d", "2d"]);
+		assert_eq!(
+			prunable_ids(None, Some(0)),
+			["5d", "4d", "2d", "1d"]
+		);
+		assert!(prunable_ids(None, Some(5)).is_empty());
+		assert!(prunable_ids(None, Some(10)).is_empty());
+	}
+
+	#[test]
+	fn older_than_and_keep() {
+		// Only the sessions matching both are removed
+		assert_eq!(prunable_ids(Some(DAY / 2), Some(3)), ["5d", "4d"]);
+		assert_eq!(
+			prunable_ids(Some(DAY * 4 + DAY / 2), Some(1)),
+			["5d"]
+		);
+		assert!(prunable_ids(Some(DAY * 6), Some(0)).is_empty());
+		assert!(prunable_ids(Some(DAY / 2), Some(5)).is_empty());
+	}
+}
@@ -0,0 +1,9 @@
+#!/bin/sh
+# QEMU+S2E as amba launched it for this session, with the VM options that the
+# session was run with. S2E expects amba to be listening on the IPC socket in
+# the working directory.
+mkdir -p {{ launch.current_dir }}
+cd {{ launch.current_dir }}
+{% for var in launch.env %}export {{ var }}
+{% endfor -%}
+exec {{ launch.command_line }}
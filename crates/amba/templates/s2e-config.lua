@@ -56,6 +56,9 @@ add_plugin("HostFiles")
 pluginsConfig.HostFiles = {
     baseDirs = {
         "{{ host_files_dir }}",
+        {% if use_seeds == true %}
+        "{{ seeds_dir }}",
+        {% endif %}
     },
     allowWrite = true,
 }
@@ -303,7 +306,7 @@ add_plugin("MultiSearcher")
 add_plugin("SeedSearcher")
 pluginsConfig.SeedSearcher = {
     enableSeeds = true,
-    seedDirectory = "{{ project_dir }}/seeds",
+    seedDirectory = "{{ seeds_dir }}",
 
     -- Save a copy of fetched seeds in s2e-last. This is useful in case
     -- you modify seeds between runs and would like to keep track of the history.
@@ -496,6 +499,12 @@ add_plugin("AmbaPlugin")
 pluginsConfig.AmbaPlugin = {
 	-- The relative path to our binary on the guest
 	module_path = "{{ executable_path }}",
+	-- The inclusive byte ranges allowed by each constrained symbolic range
+	byte_constraints = {
+{%- for allowed in byte_constraints %}
+		{ {% for bytes in allowed %}{ {{ bytes[0] }}, {{ bytes[1] }} }, {% endfor %}},
+{%- endfor %}
+	},
 }
 
 {{ custom_lua_string }}
@@ -0,0 +1,159 @@
+//! Everything bootstrap does to the guest system and S2E, behind [`Guest`]
+//! so that the rest of bootstrap can be tested on the host.
+
+use std::{
+	os::unix::process::CommandExt,
+	path::{Path, PathBuf},
+	process::{self, Command, ExitStatus},
+	thread,
+	time::Duration,
+};
+
+use crate::s2e;
+
+pub trait Guest {
+	/// Where the guest path `path` is. Relative paths are relative to the
+	/// directory that bootstrap runs in.
+	fn path(&self, path: &Path) -> PathBuf;
+
+	/// Prepare the system for running the analyzed program.
+	fn prepare(&mut self);
+
+	/// Fetch `path` from the host, relative to the directory that bootstrap
+	/// runs in.
+	fn get(&mut self, path: &str);
+
+	/// Wait for the `SeedSearcher` plugin to fork this state for a seed, and
+	/// return the name of the seed file to fetch from the host. Returns `None`
+	/// in the one state that the plugin runs without a seed.
+	fn next_seed(&mut self) -> Option<String>;
+
+	/// Make the `ranges` of the file at `path` symbolic, as space separated
+	/// `start-length` pairs.
+	fn symbfile(&mut self, path: &Path, ranges: &str);
+
+	/// Constrain the current state to `value <= max`, where `value` may have
+	/// been read from a symbolic file.
+	fn assume_at_most(&mut self, value: u64, max: u64);
+
+	/// Have the process spawned by `cmd` be the only one that the plugin
+	/// traces.
+	fn trace_process(&mut self, cmd: &mut Command);
+
+	/// Attach what the analyzed program printed to the current state.
+	fn send_program_output(&mut self, stdout: &[u8], stderr: &[u8]);
+
+	/// Record how the analyzed program ended in the current state.
+	fn send_program_exit(&mut self, status: ExitStatus);
+}
+
+/// The guest of an S2E virtual machine, with `s2ecmd` in the directory that
+/// bootstrap runs in.
+pub struct S2eGuest;
+
+impl Guest for S2eGuest {
+	fn path(&self, path: &Path) -> PathBuf {
+		path.to_owned()
+	}
+
+	fn prepare(&mut self) {
+		assert!(Path::new("./s2ecmd").exists());
+		assert_eq!(nix::unistd::gethostname().unwrap(), "s2e");
+
+		if !run_capture(&["mount"]).contains("/tmp type tmpfs") {
+			run(&[
+				"sudo",
+				"mount",
+				"-t",
+				"tmpfs",
+				"-osize=10m",
+				"tmpfs",
+				"/tmp",
+			]);
+			let mount_output = run_capture(&["mount"]);
+			assert!(
+				mount_output.contains("/tmp type tmpfs"),
+				"expected /tmp on tmpfs in mount output:\n{mount_output}"
+			);
+		}
+
+		nix::sys::resource::setrlimit(nix::sys::resource::Resource::RLIMIT_CORE, 0, 0).unwrap();
+		run(&["sudo", "sysctl", "-w", "debug.exception-trace=0"]);
+		run(&["sudo", "swapoff", "-a"]);
+		run(&["sudo", "modprobe", "s2e"]);
+	}
+
+	fn get(&mut self, path: &str) {
+		run(&["./s2ecmd", "get", path]);
+	}
+
+	fn next_seed(&mut self) -> Option<String> {
+		run(&["./s2ecmd", "seedsearcher_enable"]);
+		loop {
+			let output = Command::new("./s2ecmd")
+				.arg("get_seed_file")
+				.output()
+				.unwrap();
+			// Fails until the plugin has a seed, or decides to run this state
+			// without one
+			if !output.status.success() {
+				thread::sleep(Duration::from_secs(1));
+				continue;
+			}
+			let seed = String::from_utf8(output.stdout).unwrap();
+			let seed = seed.trim();
+			return (!seed.is_empty()).then(|| seed.to_owned());
+		}
+	}
+
+	fn symbfile(&mut self, path: &Path, ranges: &str) {
+		tracing::trace!(?path, ?ranges, "Running ./s2ecmd symfile with");
+		Command::new("./s2ecmd")
+			.env("S2E_SYMFILE_RANGES", ranges)
+			.args(["symbfile", "1"])
+			.arg(path)
+			.spawn()
+			.unwrap()
+			.wait()
+			.unwrap();
+	}
+
+	fn assume_at_most(&mut self, value: u64, max: u64) {
+		s2e::assume_range(value, 0, max);
+	}
+
+	#[allow(unsafe_code)]
+	fn trace_process(&mut self, cmd: &mut Command) {
+		// SAFETY: `send_trace_process` is async-signal-safe.
+		unsafe {
+			cmd.pre_exec(|| {
+				s2e::send_trace_process(process::id());
+				Ok(())
+			});
+		}
+	}
+
+	fn send_program_output(&mut self, stdout: &[u8], stderr: &[u8]) {
+		s2e::send_program_output(stdout, stderr);
+	}
+
+	fn send_program_exit(&mut self, status: ExitStatus) {
+		s2e::send_program_exit(status);
+	}
+}
+
+fn run(cmd: &[&str]) {
+	tracing::trace!(?cmd, "running");
+	Command::new(cmd[0])
+		.args(&cmd[1..])
+		.spawn()
+		.unwrap()
+		.wait()
+		.unwrap();
+}
+
+fn run_capture(cmd: &[&str]) -> String {
+	let output = Command::new(cmd[0]).args(&cmd[1..]).output().unwrap();
+	assert!(output.status.success());
+	String::from_utf8(output.stdout).unwrap()
+}
@@ -14,10 +14,13 @@
 compile_error!("bootstrap supports only 'x86_64-unknown-linux-musl'",);
 
 use std::{
+	ffi::OsString,
 	fs::{self, File, Permissions},
-	io,
+	io::{self, Read},
 	os::unix::{
-		fs::{MetadataExt, PermissionsExt},
+		self,
+		ffi::OsStringExt,
+		fs::{FileExt, MetadataExt, PermissionsExt},
 		process::CommandExt,
 	},
 	path::Path,
@@ -26,10 +29,17 @@ use std::{
 	time::Duration,
 };
 
-use recipe::{ArgumentSource, EnvVarSource, FileSource, Recipe, SymbolicRange};
+use recipe::{ArgumentSource, EnvVarSource, FileSource, Hook, Recipe, SymbolicRange};
 use tracing_subscriber::{filter::targets::Targets, layer::Layer};
 
+use crate::guest::{Guest, S2eGuest};
+
+mod guest;
+mod s2e;
+
 const RECIPE_PATH: &str = "recipe.json";
+/// How much of each of stdout and stderr of the analyzed program to keep
+const MAX_OUTPUT_LEN: u64 = 1 << 16;
 
 fn main() {
 	tracing::subscriber::set_global_default(
@@ -47,57 +57,100 @@ fn main() {
 
 	tracing::info!("started within guest");
 
-	assert!(Path::new("./s2ecmd").exists());
-	assert_eq!(nix::unistd::gethostname().unwrap(), "s2e");
-
-	if !run_capture(&["mount"]).contains("/tmp type tmpfs") {
-		run(&[
-			"sudo",
-			"mount",
-			"-t",
-			"tmpfs",
-			"-osize=10m",
-			"tmpfs",
-			"/tmp",
-		]);
-		let mount_output = run_capture(&["mount"]);
-		assert!(
-			mount_output.contains("/tmp type tmpfs"),
-			"expected /tmp on tmpfs in mount output:\n{mount_output}"
-		);
-	}
+	let guest = &mut S2eGuest;
+	guest.prepare();
+	bootstrap(guest);
+}
 
-	nix::sys::resource::setrlimit(nix::sys::resource::Resource::RLIMIT_CORE, 0, 0).unwrap();
-	run(&["sudo", "sysctl", "-w", "debug.exception-trace=0"]);
-	run(&["sudo", "swapoff", "-a"]);
-	run(&["sudo", "modprobe", "s2e"]);
-	run(&["./s2ecmd", "get", RECIPE_PATH]);
+/// Fetch the recipe and everything it names from the host, and run the
+/// analyzed program as it describes, between its setup and teardown commands.
+fn bootstrap(guest: &mut impl Guest) {
+	guest.get(RECIPE_PATH);
+	let recipe = Recipe::deserialize_from(
+		fs::read_to_string(guest.path(Path::new(RECIPE_PATH)))
+			.unwrap()
+			.as_bytes(),
+	)
+	.expect("deserializing Recipe");
 
-	let recipe = Recipe::deserialize_from(fs::read_to_string(RECIPE_PATH).unwrap().as_bytes())
-		.expect("deserializing Recipe");
+	place_files(guest, &recipe);
+	if recipe.seeds.is_some() {
+		use_seed(guest, &recipe);
+	}
+	run_hooks(guest, "setup", &recipe.setup);
 
-	for (guest_path, source) in &recipe.files {
-		run(&["./s2ecmd", "get", guest_path]);
+	// Counts constrained symbolic ranges in the order of
+	// `Recipe::constrained_ranges`.
+	let mut next_constrained = 0;
+	for (i, (guest_path, source)) in recipe.files.iter().enumerate() {
 		let guest_path = Path::new(guest_path);
-		assert!(guest_path.is_relative());
 		match source {
-			FileSource::Host(_) => {}
-			FileSource::SymbolicContent { symbolic, .. }
-			| FileSource::SymbolicHost { symbolic, .. } => {
+			FileSource::Host(_) | FileSource::HostWithMode { .. } | FileSource::Symlink { .. } => {}
+			FileSource::SymbolicContent {
+				symbolic,
+				max_length,
+				..
+			}
+			| FileSource::SymbolicHost {
+				symbolic,
+				max_length,
+				..
+			} => {
 				let tmp_guest_path = &Path::new("/tmp").join(guest_path);
-				fs::copy(guest_path, tmp_guest_path).unwrap();
-				symbfile(tmp_guest_path, symbolic);
+				fs::create_dir_all(guest.path(tmp_guest_path.parent().unwrap())).unwrap();
+				// Not `fs::copy`, since the copy must be writable whatever the
+				// mode of the file
+				let seed = fs::read(guest.path(guest_path)).unwrap();
+				fs::write(guest.path(tmp_guest_path), &seed).unwrap();
+				let seed_len = seed.len() as u64;
+				match *max_length {
+					None => make_symbolic(
+						guest,
+						tmp_guest_path,
+						symbolic,
+						&mut next_constrained,
+					),
+					Some(max_length) => {
+						let file = File::options()
+							.write(true)
+							.open(guest.path(tmp_guest_path))
+							.unwrap();
+						file.set_len(max_length).unwrap();
+						make_symbolic(
+							guest,
+							tmp_guest_path,
+							&with_symbolic_padding(symbolic, seed_len, max_length),
+							&mut next_constrained,
+						);
+						let length_path = recipe::symbolic_file_length_guest_path(i);
+						let length = symbolic_length(
+							guest,
+							Path::new(&length_path),
+							seed_len,
+							max_length,
+						);
+						file.set_len(length).unwrap();
+					}
+				}
+				if let Some(mode) = source.mode() {
+					fs::set_permissions(
+						guest.path(tmp_guest_path),
+						Permissions::from_mode(mode.0),
+					)
+					.unwrap();
+				}
 			}
 		};
 	}
 
-	if fs::metadata(&recipe.executable_path).unwrap().uid() == nix::unistd::Uid::current().as_raw()
+	let executable_path = guest.path(Path::new(&recipe.executable_path));
+	let executable_mode = recipe
+		.guest_file(&recipe.executable_path)
+		.and_then(|(_, source)| source.mode());
+	if executable_mode.is_none()
+		&& fs::metadata(&executable_path).unwrap().uid() == nix::unistd::Uid::current().as_raw()
 	{
-		fs::set_permissions(
-			&recipe.executable_path,
-			Permissions::from_mode(0o555),
-		)
-		.unwrap();
+		fs::set_permissions(&executable_path, Permissions::from_mode(0o555)).unwrap();
 	}
 	tracing::info!(
 		recipe.executable_path,
@@ -111,17 +164,56 @@ fn main() {
 	thread::sleep(Duration::from_millis(50));
 
 	let mut child = {
-		let mut cmd = Command::new(&recipe.executable_path);
+		let mut cmd = Command::new(&executable_path);
 		cmd.arg0(recipe.arg0.unwrap_or(recipe.executable_path))
+			.current_dir(guest.path(Path::new(".")))
 			.stdin(Stdio::piped())
-			.stdout(Stdio::null())
-			.stderr(Stdio::null());
-		for arg in recipe.arguments {
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped());
+		for (i, arg) in recipe.arguments.iter().enumerate() {
 			match arg {
 				ArgumentSource::Concrete(value) => {
 					cmd.arg(value);
 				}
-				ArgumentSource::Symbolic { seed, .. } => todo!("symbolic argv (arg = `{seed:?}`)"),
+				ArgumentSource::Symbolic {
+					seed,
+					symbolic,
+					max_length,
+				} => {
+					let path = recipe::symbolic_argument_guest_path(i + 1);
+					let value = match *max_length {
+						None => symbolic_value(
+							guest,
+							Path::new(&path),
+							seed.as_bytes(),
+							symbolic,
+							&mut next_constrained,
+						),
+						Some(max_length) => {
+							let mut padded = seed.clone().into_bytes();
+							padded.resize(max_length as usize, 0);
+							let symbolic =
+								with_symbolic_padding(symbolic, seed.len() as u64, max_length);
+							let mut value = symbolic_value(
+								guest,
+								Path::new(&path),
+								&padded,
+								&symbolic,
+								&mut next_constrained,
+							);
+							let length_path = recipe::symbolic_argument_length_guest_path(i + 1);
+							let length = symbolic_length(
+								guest,
+								Path::new(&length_path),
+								seed.len() as u64,
+								max_length,
+							);
+							value.truncate(length as usize);
+							value
+						}
+					};
+					cmd.arg(OsString::from_vec(value));
+				}
 			}
 		}
 		for env_key_to_remove in &recipe.environment.remove {
@@ -130,49 +222,264 @@ fn main() {
 		if !recipe.environment.inherit {
 			cmd.env_clear();
 		}
-		for (env_key, env_value) in &recipe.environment.add {
+		for (i, (env_key, env_value)) in recipe.environment.add.iter().enumerate() {
 			match env_value {
 				EnvVarSource::Concrete(value) => {
 					cmd.env(env_key, value);
 				}
-				EnvVarSource::Symbolic { value, .. } => {
-					todo!("symbolic envp ({env_key:?}={value:?})");
+				EnvVarSource::Symbolic { value, symbolic } => {
+					let path = recipe::symbolic_environment_guest_path(i);
+					cmd.env(
+						env_key,
+						OsString::from_vec(symbolic_value(
+							guest,
+							Path::new(&path),
+							value.as_bytes(),
+							symbolic,
+							&mut next_constrained,
+						)),
+					);
 				}
 			}
 		}
 
+		guest.trace_process(&mut cmd);
 		cmd.spawn().unwrap()
 	};
 
+	let stdout = read_output(child.stdout.take().unwrap());
+	let stderr = read_output(child.stderr.take().unwrap());
 	io::copy(
-		&mut File::open(recipe.stdin_path).unwrap(),
+		&mut File::open(guest.path(Path::new(&recipe.stdin_path))).unwrap(),
 		&mut child.stdin.as_mut().unwrap(),
 	)
 	.unwrap();
 
 	let status = child.wait().unwrap();
 	tracing::info!("analyzed program status: {status:?}");
+	guest.send_program_output(&stdout.join().unwrap(), &stderr.join().unwrap());
+	guest.send_program_exit(status);
+
+	run_hooks(guest, "teardown", &recipe.teardown);
+}
+
+/// Fetch the files of the recipe from the host, move them to their guest
+/// paths and give them their modes, and create the symlinks.
+fn place_files(guest: &mut impl Guest, recipe: &Recipe) {
+	for (guest_path, source) in &recipe.files {
+		assert!(Path::new(guest_path).is_relative());
+		let path = guest.path(Path::new(guest_path));
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		if let FileSource::Symlink { symlink } = source {
+			unix::fs::symlink(symlink, &path).unwrap();
+			continue;
+		}
+		let host_file_name = recipe::host_file_name(guest_path);
+		guest.get(&host_file_name);
+		let fetched = guest.path(Path::new(&host_file_name));
+		if fetched != path {
+			fs::rename(fetched, &path).unwrap();
+		}
+		if let Some(mode) = source.mode() {
+			fs::set_permissions(&path, Permissions::from_mode(mode.0)).unwrap();
+		}
+	}
+}
+
+/// Put the seed that the `SeedSearcher` plugin forks this state for in place
+/// of the seed of `Recipe::seeded_file`, and mark the state with it. The state
+/// that gets no seed keeps the seed of the recipe.
+fn use_seed(guest: &mut impl Guest, recipe: &Recipe) {
+	let Some(seed) = guest.next_seed() else {
+		tracing::info!("starting seedless execution");
+		return;
+	};
+	let index = recipe::seed_file_index(&seed).expect("seed file named by amba");
+	let seeded_file = recipe.seeded_file().unwrap();
+	tracing::info!(seed, seeded_file, "starting from seed");
+
+	guest.get(&seed);
+	let path = guest.path(Path::new(seeded_file));
+	fs::rename(guest.path(Path::new(&seed)), &path).unwrap();
+	if let Some(mode) = recipe.files[seeded_file].mode() {
+		fs::set_permissions(&path, Permissions::from_mode(mode.0)).unwrap();
+	}
+	let marker = recipe::seed_marker_guest_path(index);
+	fs::write(guest.path(Path::new(&marker)), [0]).unwrap();
+	symbfile(
+		guest,
+		Path::new(&marker),
+		&[SymbolicRange::Begin(0, ())],
+	);
+}
+
+/// Run the `hooks` of a recipe concretely, one after another.
+fn run_hooks(guest: &mut impl Guest, what: &str, hooks: &[Hook]) {
+	for hook in hooks {
+		tracing::info!(?hook, "running {what} command");
+		let mut cmd = match hook {
+			Hook::Shell(command) => {
+				let mut cmd = Command::new("/bin/sh");
+				cmd.arg("-c").arg(command);
+				cmd
+			}
+			Hook::Exec(argv) => {
+				let mut cmd = Command::new(&argv[0]);
+				cmd.args(&argv[1..]);
+				cmd
+			}
+		};
+		let status = cmd
+			.current_dir(guest.path(Path::new(".")))
+			.status()
+			.unwrap();
+		assert!(
+			status.success(),
+			"{what} command {hook:?} failed: {status}"
+		);
+	}
 }
 
-fn run(cmd: &[&str]) {
-	tracing::trace!(?cmd, "running");
-	Command::new(cmd[0])
-		.args(&cmd[1..])
-		.spawn()
-		.unwrap()
-		.wait()
+/// Read up to `MAX_OUTPUT_LEN` bytes of `pipe` on a thread of its own,
+/// discarding the rest so that the analyzed program never blocks writing.
+fn read_output(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+	thread::spawn(move || {
+		let mut ret = Vec::new();
+		(&mut pipe)
+			.take(MAX_OUTPUT_LEN)
+			.read_to_end(&mut ret)
+			.unwrap();
+		io::copy(&mut pipe, &mut io::sink()).unwrap();
+		ret
+	})
+}
+
+/// Write `seed` to `path`, make the `symbolic` ranges of it symbolic and read
+/// it back. The plugin constrains the symbolic bytes to be nonzero, so the
+/// value can be passed through `execve`.
+fn symbolic_value(
+	guest: &mut impl Guest,
+	path: &Path,
+	seed: &[u8],
+	symbolic: &[SymbolicRange],
+	next_constrained: &mut usize,
+) -> Vec<u8> {
+	fs::write(guest.path(path), seed).unwrap();
+	make_symbolic(guest, path, symbolic, next_constrained);
+	fs::read(guest.path(path)).unwrap()
+}
+
+/// Make the `symbolic` ranges of the file at `path` symbolic. Each constrained
+/// range is made symbolic through a file of its own, see
+/// `recipe::symbolic_constrained_guest_path`, so that the plugin can recognize
+/// it and constrain its bytes.
+fn make_symbolic(
+	guest: &mut impl Guest,
+	path: &Path,
+	symbolic: &[SymbolicRange],
+	next_constrained: &mut usize,
+) {
+	let (constrained, unconstrained): (Vec<SymbolicRange>, Vec<SymbolicRange>) = symbolic
+		.iter()
+		.cloned()
+		.partition(|range| range.constraint().is_some());
+	if constrained.is_empty() || !unconstrained.is_empty() {
+		symbfile(guest, path, &unconstrained);
+	}
+
+	let file = File::options()
+		.read(true)
+		.write(true)
+		.open(guest.path(path))
+		.unwrap();
+	let total_len = file.metadata().unwrap().len();
+	for range in &constrained {
+		let constrained_path = recipe::symbolic_constrained_guest_path(*next_constrained);
+		let constrained_path = Path::new(&constrained_path);
+		*next_constrained += 1;
+		if range.start() >= total_len {
+			continue;
+		}
+		let mut bytes = vec![0; range.len().min(total_len - range.start()) as usize];
+		file.read_exact_at(&mut bytes, range.start()).unwrap();
+		fs::write(guest.path(constrained_path), &bytes).unwrap();
+		symbfile(
+			guest,
+			constrained_path,
+			&[SymbolicRange::Begin(0, ())],
+		);
+		file.write_all_at(
+			&fs::read(guest.path(constrained_path)).unwrap(),
+			range.start(),
+		)
 		.unwrap();
+	}
 }
 
-fn run_capture(cmd: &[&str]) -> String {
-	let output = Command::new(cmd[0]).args(&cmd[1..]).output().unwrap();
-	assert!(output.status.success());
-	String::from_utf8(output.stdout).unwrap()
+/// The `symbolic` ranges of a variable-length input, extended to cover the
+/// padding between the end of its seed and `max_length` wherever no range
+/// already covers it.
+fn with_symbolic_padding(
+	symbolic: &[SymbolicRange],
+	seed_len: u64,
+	max_length: u64,
+) -> Vec<SymbolicRange> {
+	let mut ret = symbolic.to_vec();
+	// Normalized ranges are sorted by their start.
+	let mut covered = seed_len;
+	for range in symbolic {
+		let gap_end = range.start().min(max_length);
+		if covered < gap_end {
+			ret.push(SymbolicRange::Range(covered, gap_end));
+		}
+		covered = covered.max(range.start().saturating_add(range.len()));
+	}
+	if covered < max_length {
+		ret.push(SymbolicRange::Range(covered, max_length));
+	}
+	SymbolicRange::normalize(&mut ret);
+	ret
+}
+
+/// Make the length of a variable-length input symbolic, with `seed_len` as its
+/// initial concrete value. It is constrained to at most `max_length`, and
+/// comparing it against every shorter length forks the state once per length
+/// in `0..=max_length`, so the returned length is concrete within each state.
+fn symbolic_length(
+	guest: &mut impl Guest,
+	length_path: &Path,
+	seed_len: u64,
+	max_length: u64,
+) -> u64 {
+	fs::write(
+		guest.path(length_path),
+		seed_len.min(max_length).to_le_bytes(),
+	)
+	.unwrap();
+	symbfile(guest, length_path, &[SymbolicRange::Begin(0, ())]);
+	let symbolic_length = u64::from_le_bytes(
+		fs::read(guest.path(length_path))
+			.unwrap()
+			.try_into()
+			.unwrap(),
+	);
+	guest.assume_at_most(symbolic_length, max_length);
+	let length = (0..max_length)
+		.find(|&candidate| symbolic_length == candidate)
+		.unwrap_or(max_length);
+	tracing::info!(?length_path, length, "forked on symbolic length");
+	length
+}
+
+fn symbfile(guest: &mut impl Guest, path: &Path, symbolic: &[SymbolicRange]) {
+	let total_len = fs::metadata(guest.path(path)).unwrap().len();
+	guest.symbfile(path, &symbfile_ranges(symbolic, total_len));
 }
 
-fn symbfile(path: &Path, symbolic: &[SymbolicRange]) {
-	let total_len = fs::metadata(path).unwrap().len();
-	let symbolic: String = symbolic
+/// The `symbolic` ranges of a file of length `total_len`, as space separated
+/// `start-length` pairs cut off at the end of the file.
+fn symbfile_ranges(symbolic: &[SymbolicRange], total_len: u64) -> String {
+	symbolic
 		.iter()
 		.filter(|range| range.start() < total_len)
 		.map(|range| {
@@ -183,14 +490,305 @@ fn symbfile(path: &Path, symbolic: &[SymbolicRange]) {
 			)
 		})
 		.collect::<Vec<String>>()
-		.join(" ");
-	tracing::trace!(?path, ?symbolic, "Running ./s2ecmd symfile with");
-	Command::new("./s2ecmd")
-		.env("S2E_SYMFILE_RANGES", &symbolic)
-		.args(["symbfile", "1"])
-		.arg(path)
-		.spawn()
-		.unwrap()
-		.wait()
+		.join(" ")
+}
+
+#[cfg(test)]
+mod test {
+	use std::{
+		fs,
+		os::unix::fs::PermissionsExt,
+		path::{Path, PathBuf},
+		process::{Command, ExitStatus},
+	};
+
+	use recipe::SymbolicRange;
+
+	use crate::{guest::Guest, symbfile_ranges, with_symbolic_padding};
+
+	/// Runs bootstrap in a temporary directory, recording what it asks of
+	/// `s2ecmd` and leaving symbolic files concrete.
+	struct StubGuest {
+		/// Stands in for the S2E project directory on the host
+		host: PathBuf,
+		/// Stands in for the root of the guest file system
+		root: PathBuf,
+		calls: Vec<Call>,
+		output: Option<(Vec<u8>, Vec<u8>)>,
+		status: Option<ExitStatus>,
+		/// The seed that the state is forked for
+		seed: Option<String>,
+	}
+
+	#[derive(Debug, PartialEq)]
+	enum Call {
+		Get(String),
+		NextSeed,
+		Symbfile(PathBuf, String),
+		AssumeAtMost(u64, u64),
+		TraceProcess,
+	}
+
+	impl StubGuest {
+		fn new(name: &str) -> Self {
+			let dir = std::env::temp_dir().join(format!(
+				"amba-bootstrap-{name}-{}",
+				std::process::id()
+			));
+			let (host, root) = (dir.join("host"), dir.join("guest"));
+			for dir in [&host, &root.join("tmp"), &root.join("bootstrap")] {
+				fs::create_dir_all(dir).unwrap();
+			}
+			Self {
+				host,
+				root,
+				calls: Vec::new(),
+				output: None,
+				status: None,
+				seed: None,
+			}
+		}
+	}
+
+	impl Drop for StubGuest {
+		fn drop(&mut self) {
+			fs::remove_dir_all(self.root.parent().unwrap()).unwrap();
+		}
+	}
+
+	impl Guest for StubGuest {
+		fn path(&self, path: &Path) -> PathBuf {
+			match path.strip_prefix("/") {
+				Ok(absolute) => self.root.join(absolute),
+				Err(_) => self.root.join("bootstrap").join(path),
+			}
+		}
+
+		fn prepare(&mut self) {}
+
+		fn get(&mut self, path: &str) {
+			fs::copy(self.host.join(path), self.path(Path::new(path))).unwrap();
+			self.calls.push(Call::Get(path.to_owned()));
+		}
+
+		fn next_seed(&mut self) -> Option<String> {
+			self.calls.push(Call::NextSeed);
+			self.seed.clone()
+		}
+
+		fn symbfile(&mut self, path: &Path, ranges: &str) {
+			self.calls
+				.push(Call::Symbfile(path.to_owned(), ranges.to_owned()));
+		}
+
+		fn assume_at_most(&mut self, value: u64, max: u64) {
+			self.calls.push(Call::AssumeAtMost(value, max));
+		}
+
+		fn trace_process(&mut self, _: &mut Command) {
+			self.calls.push(Call::TraceProcess);
+		}
+
+		fn send_program_output(&mut self, stdout: &[u8], stderr: &[u8]) {
+			self.output = Some((stdout.to_vec(), stderr.to_vec()));
+		}
+
+		fn send_program_exit(&mut self, status: ExitStatus) {
+			self.status = Some(status);
+		}
+	}
+
+	#[test]
+	fn runs_recipe() {
+		let mut guest = StubGuest::new("recipe");
+		fs::write(
+			guest.host.join("recipe.json"),
+			r#"{
+				"files": {
+					"prog": "./prog",
+					"input.txt": {
+						"seed": "hello",
+						"symbolic": [[0, 2], { "range": 4, "constraint": "printable" }]
+					}
+				},
+				"executable_path": "./prog",
+				"stdin_path": "/tmp/input.txt",
+				"arguments": ["-v", { "seed": "ab", "symbolic": [0], "max_length": 4 }],
+				"environment": {
+					"inherit": false,
+					"add": { "KEY": { "value": "val", "symbolic": [[1, 2]] } }
+				},
+				"setup": ["echo created > state.txt"],
+				"teardown": [["rm", "state.txt"]]
+			}"#,
+		)
+		.unwrap();
+		fs::write(guest.host.join("input.txt"), "hello").unwrap();
+		let prog = guest.host.join("prog");
+		fs::write(
+			&prog,
+			"#!/bin/sh\necho \"$*\"\necho \"KEY=$KEY\"\nread -r line\necho \"$line\"\nread -r state < state.txt\necho \"$state\"\necho oops >&2\nexit 3\n",
+		)
 		.unwrap();
+		fs::set_permissions(&prog, fs::Permissions::from_mode(0o755)).unwrap();
+
+		crate::bootstrap(&mut guest);
+
+		let symbfile = |path: String, ranges: &str| Call::Symbfile(path.into(), ranges.to_owned());
+		assert_eq!(
+			guest.calls,
+			[
+				Call::Get("recipe.json".to_owned()),
+				Call::Get("input.txt".to_owned()),
+				Call::Get("prog".to_owned()),
+				symbfile("/tmp/input.txt".to_owned(), "0-2"),
+				symbfile(recipe::symbolic_constrained_guest_path(0), "0-1"),
+				symbfile(recipe::symbolic_argument_guest_path(2), "0-1 2-2"),
+				symbfile(
+					recipe::symbolic_argument_length_guest_path(2),
+					"0-8"
+				),
+				Call::AssumeAtMost(2, 4),
+				symbfile(recipe::symbolic_environment_guest_path(0), "1-1"),
+				Call::TraceProcess,
+			]
+		);
+		assert_eq!(
+			guest.output,
+			Some((
+				b"-v ab\nKEY=val\nhello\ncreated\n".to_vec(),
+				b"oops\n".to_vec()
+			))
+		);
+		assert_eq!(guest.status.unwrap().code(), Some(3));
+		assert!(!guest.path(Path::new("state.txt")).exists());
+	}
+
+	#[test]
+	fn places_files() {
+		let mut guest = StubGuest::new("files");
+		fs::write(
+			guest.host.join("recipe.json"),
+			r#"{
+				"files": {
+					"bin/prog": { "host_path": "./prog", "mode": "750" },
+					"conf/app.ini": { "host_path": "./app.ini", "mode": "444" },
+					"data/in.txt": { "seed": "in", "symbolic": [0], "mode": "400" },
+					"run": { "symlink": "bin/prog" }
+				},
+				"executable_path": "./run",
+				"stdin_path": "/tmp/data/in.txt"
+			}"#,
+		)
+		.unwrap();
+		fs::write(guest.host.join("conf%2Fapp.ini"), "[app]").unwrap();
+		fs::write(guest.host.join("data%2Fin.txt"), "in").unwrap();
+		fs::write(
+			guest.host.join("bin%2Fprog"),
+			"#!/bin/sh\ncat conf/app.ini\n",
+		)
+		.unwrap();
+
+		crate::bootstrap(&mut guest);
+
+		assert_eq!(
+			guest.calls,
+			[
+				Call::Get("recipe.json".to_owned()),
+				Call::Get("bin%2Fprog".to_owned()),
+				Call::Get("conf%2Fapp.ini".to_owned()),
+				Call::Get("data%2Fin.txt".to_owned()),
+				Call::Symbfile("/tmp/data/in.txt".into(), "0-1".to_owned()),
+				Call::TraceProcess,
+			]
+		);
+		assert_eq!(
+			guest.output,
+			Some((b"[app]".to_vec(), Vec::new()))
+		);
+		let mode = |path: &str| {
+			let metadata = fs::metadata(guest.path(Path::new(path))).unwrap();
+			metadata.permissions().mode() & 0o7777
+		};
+		assert_eq!(mode("bin/prog"), 0o750);
+		assert_eq!(mode("conf/app.ini"), 0o444);
+		assert_eq!(mode("data/in.txt"), 0o400);
+		assert_eq!(mode("/tmp/data/in.txt"), 0o400);
+		assert_eq!(
+			fs::read_link(guest.path(Path::new("run"))).unwrap(),
+			Path::new("bin/prog")
+		);
+	}
+
+	#[test]
+	fn starts_from_seed() {
+		let mut guest = StubGuest::new("seed");
+		fs::write(
+			guest.host.join("recipe.json"),
+			r#"{
+				"files": {
+					"prog": "./prog",
+					"input": { "seed": "recipe seed", "symbolic": [[0, 4]] }
+				},
+				"executable_path": "./prog",
+				"stdin_path": "/tmp/input",
+				"seeds": { "directory": "corpus" }
+			}"#,
+		)
+		.unwrap();
+		fs::write(guest.host.join("input"), "recipe seed").unwrap();
+		fs::write(
+			guest.host.join(recipe::seed_file_name(2)),
+			"corpus seed",
+		)
+		.unwrap();
+		fs::write(
+			guest.host.join("prog"),
+			"#!/bin/sh
+cat
+",
+		)
+		.unwrap();
+		guest.seed = Some(recipe::seed_file_name(2));
+
+		crate::bootstrap(&mut guest);
+
+		assert_eq!(
+			guest.calls,
+			[
+				Call::Get("recipe.json".to_owned()),
+				Call::Get("input".to_owned()),
+				Call::Get("prog".to_owned()),
+				Call::NextSeed,
+				Call::Get(recipe::seed_file_name(2)),
+				Call::Symbfile(
+					recipe::seed_marker_guest_path(2).into(),
+					"0-1".to_owned()
+				),
+				Call::Symbfile("/tmp/input".into(), "0-4".to_owned()),
+				Call::TraceProcess,
+			]
+		);
+		assert_eq!(
+			guest.output,
+			Some((b"corpus seed".to_vec(), Vec::new()))
+		);
+	}
+
+	#[test]
+	fn symbfile_ranges_end_at_file_end() {
+		let symbolic = [
+			SymbolicRange::Range(0, 2),
+			SymbolicRange::Range(4, 10),
+			SymbolicRange::Begin(12, ()),
+		];
+		assert_eq!(symbfile_ranges(&symbolic, 8), "0-2 4-4");
+		assert_eq!(
+			symbfile_ranges(
+				&with_symbolic_padding(&[SymbolicRange::Index(0)], 2, 4),
+				4
+			),
+			"0-1 2-2"
+		);
+	}
 }
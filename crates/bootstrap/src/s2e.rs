@@ -0,0 +1,121 @@
+//! Sending data to `AmbaPlugin` from within the guest, through the S2E
+//! instruction behind `s2e_invoke_plugin` in S2E's `s2e.h`. The plugin
+//! receives it in `AmbaPlugin::handleOpcodeInvocation`. Constraining the
+//! current state goes through the instruction behind `s2e_assume_range`.
+
+#![allow(unsafe_code)]
+
+use std::{arch::asm, mem, os::unix::process::ExitStatusExt, process::ExitStatus};
+
+/// Mirrors `GuestCommandKind` in `AmbaPlugin.h`.
+#[repr(u64)]
+enum GuestCommandKind {
+	ProgramOutput = 1,
+	ProgramExit = 2,
+	TraceProcess = 3,
+}
+
+/// Mirrors `GuestProgramOutput` in `AmbaPlugin.h`.
+#[repr(C)]
+struct GuestProgramOutput {
+	kind: GuestCommandKind,
+	stdout_ptr: u64,
+	stdout_len: u64,
+	stderr_ptr: u64,
+	stderr_len: u64,
+}
+
+/// Mirrors `GuestProgramExit` in `AmbaPlugin.h`.
+#[repr(C)]
+struct GuestProgramExit {
+	kind: GuestCommandKind,
+	signalled: u64,
+	status: i64,
+}
+
+/// Mirrors `GuestTraceProcess` in `AmbaPlugin.h`.
+#[repr(C)]
+struct GuestTraceProcess {
+	kind: GuestCommandKind,
+	pid: u64,
+}
+
+/// Attach what the analyzed program printed to the current state.
+pub fn send_program_output(stdout: &[u8], stderr: &[u8]) {
+	let mut command = GuestProgramOutput {
+		kind: GuestCommandKind::ProgramOutput,
+		stdout_ptr: stdout.as_ptr() as u64,
+		stdout_len: stdout.len() as u64,
+		stderr_ptr: stderr.as_ptr() as u64,
+		stderr_len: stderr.len() as u64,
+	};
+	invoke_amba_plugin(&mut command)
+		.unwrap_or_else(|ret| tracing::warn!(ret, "AmbaPlugin rejected program output"));
+}
+
+/// Record how the analyzed program ended in the current state.
+pub fn send_program_exit(status: ExitStatus) {
+	let (signalled, status) = match (status.code(), status.signal()) {
+		(Some(code), _) => (false, code),
+		(None, Some(signal)) => (true, signal),
+		(None, None) => unreachable!("a waited-for child either exited or was signalled"),
+	};
+	let mut command = GuestProgramExit {
+		kind: GuestCommandKind::ProgramExit,
+		signalled: signalled.into(),
+		status: status.into(),
+	};
+	invoke_amba_plugin(&mut command)
+		.unwrap_or_else(|ret| tracing::warn!(ret, "AmbaPlugin rejected program exit"));
+}
+
+/// Have the plugin trace the process `pid` and no other. This is called
+/// between `fork` and `exec`, so it must neither allocate nor log.
+pub fn send_trace_process(pid: u32) {
+	let mut command = GuestTraceProcess {
+		kind: GuestCommandKind::TraceProcess,
+		pid: pid.into(),
+	};
+	let (Ok(()) | Err(_)) = invoke_amba_plugin(&mut command);
+}
+
+/// Constrain the current state to `lower <= value <= upper`, where `value` may
+/// be symbolic.
+pub fn assume_range(value: u64, lower: u64, upper: u64) {
+	// SAFETY: The instruction only reads the registers. It is illegal outside
+	// of S2E, which is why only `S2eGuest` constrains states.
+	unsafe {
+		asm!(
+			".byte 0x0f, 0x3f",
+			".byte 0x00, 0x0d, 0x00, 0x00",
+			".byte 0x00, 0x00, 0x00, 0x00",
+			in("rax") value,
+			in("rcx") lower,
+			in("rdx") upper,
+			options(nostack),
+		);
+	}
+}
+
+fn invoke_amba_plugin<T>(command: &mut T) -> Result<(), u64> {
+	const PLUGIN: &[u8] = b"AmbaPlugin\0";
+	let ret: u64;
+	// SAFETY: The instruction only reads the plugin name and `command`, both
+	// of which outlive it. It is illegal outside of S2E, which is why only
+	// `S2eGuest` sends commands.
+	unsafe {
+		asm!(
+			".byte 0x0f, 0x3f",
+			".byte 0x00, 0x0b, 0x00, 0x00",
+			".byte 0x00, 0x00, 0x00, 0x00",
+			inlateout("rax") PLUGIN.as_ptr() as u64 => ret,
+			in("rcx") command as *mut T as u64,
+			in("rdx") mem::size_of::<T>() as u64,
+			options(nostack),
+		);
+	}
+	match ret as u32 {
+		0 => Ok(()),
+		_ => Err(ret),
+	}
+}
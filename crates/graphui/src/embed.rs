@@ -62,9 +62,26 @@ pub struct NodeDrawingData {
 	pub state: usize,
 	pub scc_group: usize,
 	pub function: usize,
+	pub outcome: Outcome,
 	pub lod_text: LodText,
 }
 
+/// How the state of a leaf in a state graph ended, for
+/// `ColouringMode::ByOutcome`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+	NotALeaf,
+	Running,
+	/// Exited with code zero
+	Success,
+	/// Exited with a nonzero code
+	Failure,
+	/// Terminated by a signal
+	Crash,
+	/// Killed by the symbolic execution engine
+	Killed,
+}
+
 #[derive(Clone, Debug)]
 pub struct Graph2D {
 	pub(crate) node_positions: Vec<DVec2>,
@@ -168,6 +185,11 @@ impl Graph2D {
 		self.node_drawing_data[node_id].lod_text.get_full()
 	}
 
+	/// Redraw a single node without touching the layout.
+	pub fn replace_node_drawing_data(&mut self, node_id: usize, node: NodeDrawingData) {
+		self.node_drawing_data[node_id] = node;
+	}
+
 	fn initial_node_positions(node_count: usize, edges: &[(usize, usize)]) -> Vec<DVec2> {
 		let rng = &Rng::with_seed(0);
 
@@ -2,6 +2,6 @@ mod embed;
 mod lod;
 mod widget;
 
-pub use embed::{EmbedderHasConverged, EmbeddingParameters, Graph2D, NodeDrawingData};
+pub use embed::{EmbedderHasConverged, EmbeddingParameters, Graph2D, NodeDrawingData, Outcome};
 pub use lod::LodText;
 pub use widget::{ColouringMode, GraphWidget};
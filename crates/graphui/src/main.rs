@@ -6,6 +6,7 @@ use std::{
 
 use graphui::{
 	EmbedderHasConverged, EmbeddingParameters, Graph2D, GraphWidget, LodText, NodeDrawingData,
+	Outcome,
 };
 use tracing_subscriber::{filter::targets::Targets, layer::Layer};
 
@@ -62,6 +63,7 @@ fn main() {
 						state: 0,
 						scc_group: 0,
 						function: 0,
+						outcome: Outcome::NotALeaf,
 						lod_text: ret,
 					}
 				})
@@ -3,7 +3,7 @@ use std::fmt;
 use egui::{self, Color32 as Colour32, Rect, Response, Sense, Stroke, Ui, Widget};
 use emath::Vec2;
 
-use crate::{EmbeddingParameters, Graph2D, LodText};
+use crate::{EmbeddingParameters, Graph2D, LodText, Outcome};
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum ColouringMode {
@@ -11,6 +11,7 @@ pub enum ColouringMode {
 	ByState,
 	StronglyConnectedComponents,
 	Function,
+	ByOutcome,
 }
 
 impl fmt::Display for ColouringMode {
@@ -22,6 +23,7 @@ impl fmt::Display for ColouringMode {
 				write!(f, "Strongly connected components")
 			}
 			ColouringMode::Function => write!(f, "Function"),
+			ColouringMode::ByOutcome => write!(f, "By outcome"),
 		}
 	}
 }
@@ -274,6 +276,8 @@ fn draw_graph(
 					get_colour(graph.node_drawing_data[i].scc_group)
 				}
 				ColouringMode::Function => get_colour(graph.node_drawing_data[i].function),
+				ColouringMode::ByOutcome => get_outcome_colour(graph.node_drawing_data[i].outcome)
+					.unwrap_or(style_widgets.hovered.bg_fill),
 			};
 			let (bg_colour, stroke) = if active_node_and_pan.map_or(false, |(node, _)| node == i) {
 				(style_selection.bg_fill, style_selection.stroke)
@@ -436,3 +440,14 @@ fn get_colour(i: usize) -> Colour32 {
 
 	COLOURS[i % COLOURS.len()]
 }
+
+fn get_outcome_colour(outcome: Outcome) -> Option<Colour32> {
+	match outcome {
+		Outcome::NotALeaf => None,
+		Outcome::Running => Some(Colour32::from_rgb(0x71, 0xA6, 0xF5)),
+		Outcome::Success => Some(Colour32::from_rgb(0x7B, 0xD1, 0x6E)),
+		Outcome::Failure => Some(Colour32::from_rgb(0xF5, 0xE1, 0x89)),
+		Outcome::Crash => Some(Colour32::from_rgb(0xE0, 0x4F, 0x45)),
+		Outcome::Killed => Some(Colour32::from_rgb(0xA8, 0x56, 0x9C)),
+	}
+}
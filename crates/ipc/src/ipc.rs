@@ -1,8 +1,9 @@
 use std::{
+	fmt,
 	io::{self, BufRead, BufReader, BufWriter, Read, Write},
 	mem,
 	net::Shutdown,
-	os::unix::net::{UnixListener, UnixStream},
+	os::unix::net::UnixStream,
 	path::Path,
 	time::Duration,
 };
@@ -25,28 +26,18 @@ impl From<IpcInstance> for (IpcRx, IpcTx) {
 
 impl IpcInstance {
 	pub fn new_plugin(socket: &Path) -> Self {
-		let stream = IoArc::new(UnixStream::connect(socket).unwrap());
-
-		let reader = {
-			let rx = BufReader::new(stream.clone());
-			rx.get_ref()
-				.as_ref()
-				.set_read_timeout(Some(Duration::from_nanos(1)))
-				.unwrap();
-			IpcRx { rx }
-		};
-
-		let writer = IpcTx {
-			tx: BufWriter::new(stream),
-		};
+		let stream = UnixStream::connect(socket).unwrap();
+		stream
+			.set_read_timeout(Some(Duration::from_nanos(1)))
+			.unwrap();
+		let instance = Self::from_stream(stream);
 
 		tracing::info!("Plugin IPC setup");
-		IpcInstance { reader, writer }
+		instance
 	}
 
-	pub fn new_gui(socket: &Path) -> Self {
-		let ipc_listener = UnixListener::bind(socket).unwrap();
-		let stream = IoArc::new(ipc_listener.accept().unwrap().0);
+	pub(crate) fn from_stream(stream: UnixStream) -> Self {
+		let stream = IoArc::new(stream);
 
 		let reader = IpcRx {
 			rx: BufReader::new(stream.clone()),
@@ -56,7 +47,6 @@ impl IpcInstance {
 			tx: BufWriter::new(stream),
 		};
 
-		tracing::info!("GUI IPC setup");
 		IpcInstance { reader, writer }
 	}
 
@@ -162,12 +152,103 @@ impl IpcRx {
 #[derive(Serialize, Deserialize, Debug)]
 pub enum IpcMessage {
 	Ping,
+	/// The first message of every S2E process, telling the gui which process
+	/// the connection belongs to
+	Hello {
+		pid: u32,
+		/// `None` for the process that QEMU started
+		forked_from: Option<ForkOrigin>,
+	},
 	NewEdges {
 		state_edges: Vec<(NodeMetadata, NodeMetadata)>,
 		block_edges: Vec<(NodeMetadata, NodeMetadata)>,
 	},
 	PrioritiseStates(Vec<i32>),
 	ResetPriority,
+	/// What the analyzed program printed before exiting in a state
+	StateOutput {
+		amba_state_id: u32,
+		s2e_state_id: i32,
+		stdout: Vec<u8>,
+		stderr: Vec<u8>,
+	},
+	/// How a state ended
+	StateTermination {
+		amba_state_id: u32,
+		s2e_state_id: i32,
+		termination: StateTermination,
+	},
+}
+
+/// Where a forked S2E process came from. It inherits the states of its
+/// parent, along with their amba state ids, and numbers its own states from
+/// `first_own_state_id`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ForkOrigin {
+	pub parent_pid: u32,
+	pub first_own_state_id: u32,
+}
+
+/// How a state ended. States without one are still running.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateTermination {
+	/// The analyzed program exited with this code
+	Exited(i32),
+	/// The analyzed program was terminated by this signal
+	Signalled(i32),
+	/// S2E killed the state before the analyzed program exited
+	Killed(KillReason),
+}
+
+/// Why S2E killed a state, as far as the plugin can tell.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KillReason {
+	/// amba quit S2E while the state was still running, because the budget of
+	/// the run was exhausted or the gui was closed
+	Stopped,
+	/// The state forked more often than S2E allows
+	ForkLimit,
+	/// The state ran for longer than S2E allows
+	Timeout,
+	/// The constraint solver failed or timed out on a query of the state
+	SolverFailure,
+	/// S2E killed the state without a message the plugin recognises, see the
+	/// S2E log
+	Unknown,
+}
+
+impl KillReason {
+	/// The reason behind `message`, which S2E's executor logs when it kills a
+	/// state. The wording comes from the messages of S2E and KLEE.
+	pub fn from_terminate_message(message: &str) -> Self {
+		let message = message.to_lowercase();
+		let mentions = |words: &[&str]| words.iter().any(|word| message.contains(word));
+		if mentions(&["solver", "query timed out"]) {
+			Self::SolverFailure
+		} else if mentions(&["timeout", "timed out", "time limit", "max-instruction-time"]) {
+			Self::Timeout
+		} else if mentions(&["fork"]) && mentions(&["limit", "max", "too many"]) {
+			Self::ForkLimit
+		} else {
+			Self::Unknown
+		}
+	}
+}
+
+impl fmt::Display for StateTermination {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Exited(code) => write!(f, "Exited with code {code}"),
+			Self::Signalled(signal) => write!(f, "Terminated by signal {signal}"),
+			Self::Killed(KillReason::Stopped) => write!(f, "Stopped by amba while running"),
+			Self::Killed(KillReason::ForkLimit) => write!(f, "Killed by S2E at the fork limit"),
+			Self::Killed(KillReason::Timeout) => write!(f, "Killed by S2E for timing out"),
+			Self::Killed(KillReason::SolverFailure) => {
+				write!(f, "Killed by S2E after a solver failure")
+			}
+			Self::Killed(KillReason::Unknown) => write!(f, "Killed by S2E"),
+		}
+	}
 }
 
 #[derive(Debug)]
@@ -196,3 +277,41 @@ impl From<bincode::Error> for IpcError {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::KillReason;
+
+	#[test]
+	fn kill_reasons_from_terminate_messages() {
+		for (message, reason) in [
+			(
+				"Query timed out (fork).",
+				KillReason::SolverFailure,
+			),
+			(
+				"Solver failure in resolve",
+				KillReason::SolverFailure,
+			),
+			(
+				"max-instruction-time exceeded",
+				KillReason::Timeout,
+			),
+			("State timeout", KillReason::Timeout),
+			("Reached the fork limit", KillReason::ForkLimit),
+			("Too many forks", KillReason::ForkLimit),
+			("Forked state", KillReason::Unknown),
+			(
+				"State was terminated by opcode",
+				KillReason::Unknown,
+			),
+			("", KillReason::Unknown),
+		] {
+			assert_eq!(
+				KillReason::from_terminate_message(message),
+				reason,
+				"{message}"
+			);
+		}
+	}
+}
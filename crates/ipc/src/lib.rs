@@ -1,9 +1,13 @@
 mod graph;
 mod ipc;
 mod metadata;
+mod server;
 
 pub use crate::{
 	graph::GraphIpc,
-	ipc::{IpcError, IpcInstance, IpcMessage, IpcRx, IpcTx},
+	ipc::{
+		ForkOrigin, IpcError, IpcInstance, IpcMessage, IpcRx, IpcTx, KillReason, StateTermination,
+	},
 	metadata::{CompressedBasicBlock, NodeMetadata},
+	server::{IpcServer, IpcServerRx, IpcServerTx, WorkerId},
 };
@@ -0,0 +1,144 @@
+use std::{
+	io,
+	os::unix::net::UnixListener,
+	path::Path,
+	sync::{
+		mpsc::{self, RecvTimeoutError},
+		Arc, Mutex,
+	},
+	thread,
+	time::Duration,
+};
+
+use crate::ipc::{IpcError, IpcInstance, IpcMessage, IpcRx, IpcTx};
+
+/// How long to wait for messages before accepting new connections again
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The connection a message came from. Every S2E process connects on its own,
+/// numbered in the order they were accepted.
+pub type WorkerId = usize;
+
+/// The gui end of IPC, which every S2E process connects to.
+pub struct IpcServer {
+	reader: IpcServerRx,
+	writer: IpcServerTx,
+}
+
+impl From<IpcServer> for (IpcServerRx, IpcServerTx) {
+	fn from(val: IpcServer) -> Self {
+		(val.reader, val.writer)
+	}
+}
+
+impl IpcServer {
+	/// Blocks until the first S2E process has connected. Processes that S2E
+	/// forks later are accepted while receiving.
+	pub fn new(socket: &Path) -> Self {
+		let listener = UnixListener::bind(socket).unwrap();
+		let (stream, _) = listener.accept().unwrap();
+		listener.set_nonblocking(true).unwrap();
+
+		let (tx, rx) = mpsc::channel();
+		let writers = Arc::new(Mutex::new(Vec::new()));
+		let mut reader = IpcServerRx {
+			listener,
+			writers: Arc::clone(&writers),
+			tx,
+			rx,
+			connections: 0,
+			open: 0,
+		};
+		reader.add_connection(IpcInstance::from_stream(stream));
+
+		tracing::info!("GUI IPC setup");
+		IpcServer {
+			reader,
+			writer: IpcServerTx { writers },
+		}
+	}
+}
+
+pub struct IpcServerRx {
+	listener: UnixListener,
+	writers: Arc<Mutex<Vec<IpcTx>>>,
+	tx: mpsc::Sender<(WorkerId, Result<IpcMessage, IpcError>)>,
+	rx: mpsc::Receiver<(WorkerId, Result<IpcMessage, IpcError>)>,
+	connections: usize,
+	/// Connections that have not yet reached their end
+	open: usize,
+}
+
+impl IpcServerRx {
+	/// The next message of any S2E process. `IpcError::EndOfFile` once every
+	/// connection has ended. A forked process keeps the connection of its
+	/// parent open, so that does not happen before it has connected itself.
+	pub fn blocking_receive(&mut self) -> Result<(WorkerId, IpcMessage), IpcError> {
+		loop {
+			self.accept_connections()?;
+			if self.open == 0 {
+				return Err(IpcError::EndOfFile);
+			}
+			match self.rx.recv_timeout(ACCEPT_INTERVAL) {
+				Ok((worker, Ok(msg))) => return Ok((worker, msg)),
+				Ok((_, Err(IpcError::EndOfFile))) => self.open -= 1,
+				Ok((_, Err(err))) => return Err(err),
+				Err(RecvTimeoutError::Timeout) => {}
+				Err(RecvTimeoutError::Disconnected) => unreachable!(),
+			}
+		}
+	}
+
+	fn accept_connections(&mut self) -> Result<(), IpcError> {
+		loop {
+			match self.listener.accept() {
+				Ok((stream, _)) => {
+					stream.set_nonblocking(false)?;
+					self.add_connection(IpcInstance::from_stream(stream));
+				}
+				Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+				Err(err) => return Err(err.into()),
+			}
+		}
+	}
+
+	fn add_connection(&mut self, instance: IpcInstance) {
+		let worker = self.connections;
+		self.connections += 1;
+		self.open += 1;
+		let (mut reader, writer): (IpcRx, IpcTx) = instance.into();
+		self.writers.lock().unwrap().push(writer);
+
+		let tx = self.tx.clone();
+		thread::Builder::new()
+			.name(format!("ipc-worker-{worker}"))
+			.spawn(move || loop {
+				let res = reader.blocking_receive();
+				let end = res.is_err();
+				if tx.send((worker, res)).is_err() || end {
+					break;
+				}
+			})
+			.unwrap();
+		tracing::info!(worker, "S2E process connected");
+	}
+}
+
+pub struct IpcServerTx {
+	writers: Arc<Mutex<Vec<IpcTx>>>,
+}
+
+impl IpcServerTx {
+	/// Send `msg` to every S2E process. Fails only if it reached none of them.
+	pub fn blocking_send(&mut self, msg: &IpcMessage) -> Result<(), IpcError> {
+		let mut res = Err(IpcError::EndOfFile);
+		for writer in self.writers.lock().unwrap().iter_mut() {
+			match writer.blocking_send(msg) {
+				Ok(()) => res = Ok(()),
+				Err(err) if res.is_err() => res = Err(err),
+				Err(_) => {}
+			}
+		}
+		res
+	}
+}
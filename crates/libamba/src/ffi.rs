@@ -2,7 +2,7 @@
 
 use std::{pin::Pin, slice, sync::Mutex};
 
-use ipc::{IpcInstance, IpcMessage};
+use ipc::{ForkOrigin, IpcInstance, IpcMessage, KillReason, StateTermination};
 
 use crate::node_metadata::NodeMetadataFFIPair;
 
@@ -26,6 +26,26 @@ unsafe fn send_ipc_message(ipc: *mut Mutex<IpcInstance>, msg: &ipc::IpcMessage)
 		.unwrap_or_else(|err| println!("libamba ipc error: {err:?}"));
 }
 
+/// `parent_pid` and `first_own_state_id` are only read if `forked`.
+#[no_mangle]
+pub unsafe extern "C" fn rust_ipc_send_hello(
+	ipc: *mut Mutex<IpcInstance>,
+	pid: u32,
+	forked: bool,
+	parent_pid: u32,
+	first_own_state_id: u32,
+) {
+	let msg = ipc::IpcMessage::Hello {
+		pid,
+		forked_from: forked.then_some(ForkOrigin {
+			parent_pid,
+			first_own_state_id,
+		}),
+	};
+
+	send_ipc_message(ipc, &msg);
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rust_ipc_send_edges(
 	ipc: *mut Mutex<IpcInstance>,
@@ -50,6 +70,69 @@ pub unsafe extern "C" fn rust_ipc_send_edges(
 	send_ipc_message(ipc, &msg);
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rust_ipc_send_state_output(
+	ipc: *mut Mutex<IpcInstance>,
+	amba_state_id: u32,
+	s2e_state_id: i32,
+	stdout_data: *const u8,
+	stdout_len: u64,
+	stderr_data: *const u8,
+	stderr_len: u64,
+) {
+	let msg = ipc::IpcMessage::StateOutput {
+		amba_state_id,
+		s2e_state_id,
+		stdout: slice::from_raw_parts(stdout_data, stdout_len as _).to_vec(),
+		stderr: slice::from_raw_parts(stderr_data, stderr_len as _).to_vec(),
+	};
+
+	send_ipc_message(ipc, &msg);
+}
+
+/// `kind` and `value` are as in `StateTerminationKind` and `KillReason` of
+/// `LibambaRs.h`.
+#[no_mangle]
+pub unsafe extern "C" fn rust_ipc_send_state_termination(
+	ipc: *mut Mutex<IpcInstance>,
+	amba_state_id: u32,
+	s2e_state_id: i32,
+	kind: u32,
+	value: i32,
+) {
+	let termination = match (kind, value) {
+		(0, code) => StateTermination::Exited(code),
+		(1, signal) => StateTermination::Signalled(signal),
+		(2, 0) => StateTermination::Killed(KillReason::Stopped),
+		(2, 1) => StateTermination::Killed(KillReason::Unknown),
+		(2, 2) => StateTermination::Killed(KillReason::ForkLimit),
+		(2, 3) => StateTermination::Killed(KillReason::Timeout),
+		(2, 4) => StateTermination::Killed(KillReason::SolverFailure),
+		_ => panic!("invalid state termination kind {kind} with value {value}"),
+	};
+	let msg = ipc::IpcMessage::StateTermination {
+		amba_state_id,
+		s2e_state_id,
+		termination,
+	};
+
+	send_ipc_message(ipc, &msg);
+}
+
+/// The `KillReason` of `LibambaRs.h` for the message S2E's executor logged when
+/// killing a state.
+#[no_mangle]
+pub unsafe extern "C" fn rust_kill_reason(message: *const u8, message_len: u64) -> i32 {
+	let message = slice::from_raw_parts(message, message_len as _);
+	match KillReason::from_terminate_message(&String::from_utf8_lossy(message)) {
+		KillReason::Stopped => 0,
+		KillReason::Unknown => 1,
+		KillReason::ForkLimit => 2,
+		KillReason::Timeout => 3,
+		KillReason::SolverFailure => 4,
+	}
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rust_ipc_receive_message(
 	ipc: *mut Mutex<IpcInstance>,
@@ -1,7 +1,9 @@
 mod control_flow;
 mod model;
+mod workers;
 
 pub use crate::{
 	control_flow::ControlFlowGraph,
-	model::{GraphToView, Model},
+	model::{Exploration, ExploredState, GraphToView, Model, StateOutput},
+	workers::WorkerStateIds,
 };
@@ -1,5 +1,5 @@
 use std::{
-	collections::BTreeSet,
+	collections::{BTreeMap, BTreeSet},
 	fmt::{self, Debug},
 	mem,
 	num::NonZeroU64,
@@ -11,8 +11,11 @@ use std::{
 };
 
 use disassembler::DisasmContext;
-use graphui::{EmbedderHasConverged, EmbeddingParameters, Graph2D, LodText, NodeDrawingData};
-use ipc::{CompressedBasicBlock, NodeMetadata};
+use graphui::{
+	EmbedderHasConverged, EmbeddingParameters, Graph2D, LodText, NodeDrawingData, Outcome,
+};
+use ipc::{CompressedBasicBlock, NodeMetadata, StateTermination};
+use recipe::InputLayout;
 
 use crate::control_flow::ControlFlowGraph;
 
@@ -28,13 +31,17 @@ pub struct Model {
 	merged_compressed_block_graph: RwLock<Graph2D>,
 	embedding_parameters: Mutex<EmbeddingParameters>,
 	graph_to_view: AtomicU8,
+	/// Layouts to decode concrete inputs with, keyed by concrete input name
+	input_layouts: BTreeMap<String, InputLayout>,
+	/// How states ended, keyed by amba state id
+	state_endings: RwLock<BTreeMap<u32, StateEnding>>,
 	/// Model supports mixed read/write, but only by a single writer.
 	/// EXCLUDING `embedding_parameters` that can be written to by anyone.
 	modelwide_single_writer_lock: Mutex<()>,
 }
 
 impl Model {
-	pub fn new() -> Self {
+	pub fn new(input_layouts: BTreeMap<String, InputLayout>) -> Self {
 		Self {
 			block_control_flow: RwLock::new(ControlFlowGraph::new()),
 			merged_control_flow: RwLock::new(ControlFlowGraph::new()),
@@ -46,6 +53,8 @@ impl Model {
 			merged_compressed_block_graph: RwLock::new(Graph2D::empty()),
 			embedding_parameters: Mutex::new(EmbeddingParameters::default()),
 			graph_to_view: AtomicU8::new(GraphToView::RawBlock as u8),
+			input_layouts,
+			state_endings: RwLock::new(BTreeMap::new()),
 			modelwide_single_writer_lock: Mutex::new(()),
 		}
 	}
@@ -86,10 +95,13 @@ impl Model {
 								state: symbolic_state_id as _,
 								scc_group: scc_groups[&idx],
 								function: 0,
+								outcome: Outcome::NotALeaf,
 								lod_text: new_lod_text_impl(
 									&metadata,
 									has_self_edge,
 									disasm_context,
+									&self.input_layouts,
+									&BTreeMap::new(),
 								),
 							}
 						})
@@ -120,10 +132,13 @@ impl Model {
 								state: symbolic_state_id as _,
 								scc_group: scc_groups[&idx],
 								function: 0,
+								outcome: Outcome::NotALeaf,
 								lod_text: new_lod_text_impl(
 									&metadata,
 									has_self_edge,
 									disasm_context,
+									&self.input_layouts,
+									&BTreeMap::new(),
 								),
 							}
 						})
@@ -153,10 +168,13 @@ impl Model {
 								state,
 								scc_group: scc_groups[&idx],
 								function: 0,
+								outcome: Outcome::NotALeaf,
 								lod_text: new_lod_text_impl(
 									&metadata,
 									has_self_edge,
 									disasm_context,
+									&self.input_layouts,
+									&BTreeMap::new(),
 								),
 							}
 						})
@@ -186,10 +204,13 @@ impl Model {
 								state,
 								scc_group: scc_groups[&idx],
 								function: 0,
+								outcome: Outcome::NotALeaf,
 								lod_text: new_lod_text_impl(
 									&metadata,
 									has_self_edge,
 									disasm_context,
+									&self.input_layouts,
+									&BTreeMap::new(),
 								),
 							}
 						})
@@ -208,32 +229,156 @@ impl Model {
 			for (from, to) in state_edges.into_iter() {
 				state_control_flow.update(from, to);
 			}
+			self.rebuild_state_graph(&state_control_flow, disasm_context);
+		}
+		mem::drop(mutex);
+	}
 
-			let (state_nodes, state_edges) = {
-				let (nodes, self_edge, edges) =
-					state_control_flow.get_raw_metadata_and_selfedge_and_sequential_edges();
-				(
-					nodes
-						.into_iter()
-						.zip(self_edge)
-						.map(|(metadata, has_self_edge)| NodeDrawingData {
-							state: 0,
-							scc_group: 0,
-							function: 0,
-							lod_text: new_lod_text_impl(&metadata, has_self_edge, disasm_context),
-						})
-						.collect(),
-					edges,
-				)
-			};
-			self.raw_state_graph
+	/// Attach what the analyzed program printed to the state it exited in.
+	pub fn add_state_output(
+		&self,
+		amba_state_id: u32,
+		output: StateOutput,
+		disasm_context: &mut DisasmContext,
+	) {
+		self.update_state_ending(amba_state_id, disasm_context, |ending| {
+			ending.output = Some(output);
+		});
+	}
+
+	pub fn add_state_termination(
+		&self,
+		amba_state_id: u32,
+		termination: StateTermination,
+		disasm_context: &mut DisasmContext,
+	) {
+		self.update_state_ending(amba_state_id, disasm_context, |ending| {
+			ending.termination = Some(termination);
+		});
+	}
+
+	/// Redraws only the nodes of the updated state, since the state graph
+	/// itself is unchanged.
+	fn update_state_ending(
+		&self,
+		amba_state_id: u32,
+		disasm_context: &DisasmContext,
+		update: impl FnOnce(&mut StateEnding),
+	) {
+		let mutex: MutexGuard<'_, ()> = self.modelwide_single_writer_lock.lock().unwrap();
+		update(
+			self.state_endings
 				.write()
 				.unwrap()
-				.seeded_replace_self_with(state_nodes, state_edges);
+				.entry(amba_state_id)
+				.or_default(),
+		);
+		let state_endings = self.state_endings.read().unwrap();
+		let state_control_flow = self.state_control_flow.read().unwrap();
+		let mut raw_state_graph = self.raw_state_graph.write().unwrap();
+		for (idx, metadata) in state_control_flow.metadata.iter().enumerate() {
+			let NodeMetadata::State {
+				amba_state_id: node_state_id,
+				..
+			} = *metadata
+			else {
+				panic!()
+			};
+			if node_state_id != amba_state_id {
+				continue;
+			}
+			let to = &state_control_flow.graph.nodes[&(idx as u64)].to;
+			let has_self_edge = to.contains(&(idx as u64));
+			let is_leaf = to.iter().all(|&link| link == idx as u64);
+			raw_state_graph.replace_node_drawing_data(
+				idx,
+				self.state_node_drawing_data(
+					metadata,
+					has_self_edge,
+					is_leaf,
+					disasm_context,
+					&state_endings,
+				),
+			);
 		}
 		mem::drop(mutex);
 	}
 
+	fn rebuild_state_graph(
+		&self,
+		state_control_flow: &ControlFlowGraph,
+		disasm_context: &DisasmContext,
+	) {
+		let state_endings = self.state_endings.read().unwrap();
+		let (state_nodes, state_edges) = {
+			let (nodes, self_edge, edges) =
+				state_control_flow.get_raw_metadata_and_selfedge_and_sequential_edges();
+			let mut is_leaf = vec![true; nodes.len()];
+			for &(from, to) in &edges {
+				if from != to {
+					is_leaf[from] = false;
+				}
+			}
+			(
+				nodes
+					.iter()
+					.zip(self_edge)
+					.zip(is_leaf)
+					.map(|((metadata, has_self_edge), is_leaf)| {
+						self.state_node_drawing_data(
+							metadata,
+							has_self_edge,
+							is_leaf,
+							disasm_context,
+							&state_endings,
+						)
+					})
+					.collect(),
+				edges,
+			)
+		};
+		self.raw_state_graph
+			.write()
+			.unwrap()
+			.seeded_replace_self_with(state_nodes, state_edges);
+	}
+
+	fn state_node_drawing_data(
+		&self,
+		metadata: &NodeMetadata,
+		has_self_edge: bool,
+		is_leaf: bool,
+		disasm_context: &DisasmContext,
+		state_endings: &BTreeMap<u32, StateEnding>,
+	) -> NodeDrawingData {
+		let NodeMetadata::State { amba_state_id, .. } = *metadata else {
+			panic!()
+		};
+		let termination = state_endings
+			.get(&amba_state_id)
+			.and_then(|ending| ending.termination);
+		NodeDrawingData {
+			state: 0,
+			scc_group: 0,
+			function: 0,
+			outcome: match (is_leaf, termination) {
+				(false, _) => Outcome::NotALeaf,
+				(true, None) => Outcome::Running,
+				(true, Some(StateTermination::Exited(0))) => Outcome::Success,
+				(true, Some(StateTermination::Exited(_))) => Outcome::Failure,
+				(true, Some(StateTermination::Signalled(_))) => Outcome::Crash,
+				(true, Some(StateTermination::Killed(_))) => Outcome::Killed,
+			},
+			lod_text: new_lod_text_impl(
+				metadata,
+				has_self_edge,
+				disasm_context,
+				&self.input_layouts,
+				state_endings,
+			),
+		}
+	}
+
 	pub fn run_layout_iterations(&self) -> EmbedderHasConverged {
 		let params: EmbeddingParameters = *self.embedding_parameters.lock().unwrap();
 		let graph_to_view = GraphToView::from_raw(self.graph_to_view.load(MemoryOrdering::SeqCst));
@@ -290,6 +435,96 @@ impl Model {
 		self.embedding_parameters.lock().unwrap()
 	}
 
+	/// The number of states seen so far.
+	pub fn state_count(&self) -> usize {
+		self.state_control_flow
+			.read()
+			.unwrap()
+			.metadata
+			.iter()
+			.filter_map(|metadata| match metadata {
+				NodeMetadata::State { amba_state_id, .. } => Some(*amba_state_id),
+				_ => None,
+			})
+			.collect::<BTreeSet<u32>>()
+			.len()
+	}
+
+	/// The number of distinct basic blocks seen so far, in any state.
+	pub fn block_count(&self) -> usize {
+		self.merged_control_flow.read().unwrap().metadata.len()
+	}
+
+	/// A snapshot of everything explored so far, with function names looked up
+	/// in `disasm_context`.
+	pub fn exploration(&self, disasm_context: &DisasmContext) -> Exploration {
+		let mutex: MutexGuard<'_, ()> = self.modelwide_single_writer_lock.lock().unwrap();
+		let state_endings = self.state_endings.read().unwrap();
+		let state_control_flow = self.state_control_flow.read().unwrap();
+		let (nodes, _, edges) =
+			state_control_flow.get_raw_metadata_and_selfedge_and_sequential_edges();
+		let amba_state_ids: Vec<u32> = nodes
+			.iter()
+			.map(|metadata| {
+				let NodeMetadata::State { amba_state_id, .. } = metadata else {
+					panic!()
+				};
+				*amba_state_id
+			})
+			.collect();
+
+		let mut states = BTreeMap::new();
+		// Later nodes of the same state have its most recent concrete inputs
+		for metadata in nodes {
+			let NodeMetadata::State {
+				amba_state_id,
+				s2e_state_id,
+				concrete_inputs,
+			} = metadata
+			else {
+				panic!()
+			};
+			let ending = state_endings.get(&amba_state_id);
+			states.insert(
+				amba_state_id,
+				ExploredState {
+					s2e_state_id,
+					concrete_inputs,
+					termination: ending.and_then(|ending| ending.termination),
+					output: ending.and_then(|ending| ending.output.clone()),
+				},
+			);
+		}
+		let state_edges = edges
+			.into_iter()
+			.map(|(from, to)| (amba_state_ids[from], amba_state_ids[to]))
+			.filter(|(from, to)| from != to)
+			.collect();
+
+		let merged_control_flow = self.merged_control_flow.read().unwrap();
+		let functions = merged_control_flow
+			.metadata
+			.iter()
+			.filter_map(|metadata| match metadata {
+				NodeMetadata::BasicBlock {
+					basic_block_elf_vaddr,
+					..
+				} => disasm_context
+					.get_function_name(basic_block_elf_vaddr.map_or(0, NonZeroU64::get))
+					.ok(),
+				_ => None,
+			})
+			.collect();
+		let exploration = Exploration {
+			states,
+			state_edges,
+			blocks: merged_control_flow.metadata.len(),
+			functions,
+		};
+		mem::drop(mutex);
+		exploration
+	}
+
 	pub fn get_neighbour_states(&self, prio: usize) -> Vec<i32> {
 		fn get_neighbours_inner(idx: u64, state_cfg: &ControlFlowGraph, out: &mut BTreeSet<i32>) {
 			let NodeMetadata::State { s2e_state_id , .. } = state_cfg.metadata[idx as usize] else {panic!()};
@@ -307,6 +542,43 @@ impl Model {
 	}
 }
 
+/// What the analyzed program printed before exiting in a state.
+#[derive(Clone, Debug, Default)]
+pub struct StateOutput {
+	pub stdout: Vec<u8>,
+	pub stderr: Vec<u8>,
+}
+
+/// Everything explored in a session, see [`Model::exploration`].
+#[derive(Clone, Debug)]
+pub struct Exploration {
+	/// Keyed by amba state id
+	pub states: BTreeMap<u32, ExploredState>,
+	/// Forks between states, from the amba state id of the parent to that of
+	/// the child
+	pub state_edges: BTreeSet<(u32, u32)>,
+	/// The number of distinct basic blocks run, in any state
+	pub blocks: usize,
+	/// The functions that the basic blocks are within, if the analyzed
+	/// executable has debug data
+	pub functions: BTreeSet<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExploredState {
+	pub s2e_state_id: i32,
+	pub concrete_inputs: Vec<(String, Vec<u8>)>,
+	pub termination: Option<StateTermination>,
+	pub output: Option<StateOutput>,
+}
+
+/// What is known about how a state ended.
+#[derive(Debug, Default)]
+struct StateEnding {
+	output: Option<StateOutput>,
+	termination: Option<StateTermination>,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum GraphToView {
@@ -347,6 +619,8 @@ fn new_lod_text_impl(
 	metadata: &NodeMetadata,
 	has_self_edge: bool,
 	disasm_context: &DisasmContext,
+	input_layouts: &BTreeMap<String, InputLayout>,
+	state_endings: &BTreeMap<u32, StateEnding>,
 ) -> LodText {
 	let mut ret = LodText::new();
 	let marker = if has_self_edge { "↺" } else { "" };
@@ -407,16 +681,40 @@ fn new_lod_text_impl(
 		} => {
 			use std::fmt::Write;
 
+			let ending = state_endings.get(amba_state_id);
 			let mut full = format!("{amba_state_id} ({s2e_state_id})\n");
+			if let Some(termination) = ending.and_then(|ending| ending.termination) {
+				writeln!(full, "{termination}").unwrap();
+			}
 			for (var_name, var_value) in concrete_inputs {
-				write!(
-					full,
-					"\n{var_name}:\n=\t{:?}\n=\t{}",
-					&var_value,
-					String::from_utf8_lossy(&var_value)
-				)
+				match input_layouts.get(var_name) {
+					Some(layout) => write!(
+						full,
+						"\n{var_name}:\n=\t{}",
+						layout.decode(var_value)
+					),
+					None => write!(
+						full,
+						"\n{var_name}:\n=\t{:?}\n=\t{}",
+						&var_value,
+						String::from_utf8_lossy(&var_value)
+					),
+				}
 				.unwrap();
 			}
+			if let Some(StateOutput { stdout, stderr }) =
+				ending.and_then(|ending| ending.output.as_ref())
+			{
+				for (name, output) in [("stdout", stdout), ("stderr", stderr)] {
+					if output.is_empty() {
+						continue;
+					}
+					write!(full, "\n{name}:").unwrap();
+					for line in String::from_utf8_lossy(output).lines() {
+						write!(full, "\n=\t{line}").unwrap();
+					}
+				}
+			}
 			ret.coarser(full);
 			ret.coarser(format!("{amba_state_id} ({s2e_state_id})"));
 			ret.coarser(format!("{amba_state_id}"));
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use ipc::{ForkOrigin, NodeMetadata, WorkerId};
+
+/// Amba state ids are counted by each S2E process on its own, so the states of
+/// different processes share ids. `WorkerStateIds` gives every state an id
+/// that is unique within the session, in the order states are first seen, so
+/// that the graphs of all processes can be merged into one model.
+#[derive(Debug, Default)]
+pub struct WorkerStateIds {
+	workers: HashMap<WorkerId, Worker>,
+	next_id: u32,
+}
+
+#[derive(Debug, Default)]
+struct Worker {
+	/// The process this one was forked from, and the first state id that is
+	/// not inherited from it
+	parent: Option<(WorkerId, u32)>,
+	ids: HashMap<u32, u32>,
+}
+
+impl WorkerStateIds {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// `worker` was forked from `parent` as described by `origin`, so shares
+	/// the ids of the states it inherited.
+	pub fn fork(&mut self, worker: WorkerId, parent: WorkerId, origin: ForkOrigin) {
+		self.workers.entry(worker).or_default().parent = Some((parent, origin.first_own_state_id));
+	}
+
+	/// The session-wide id of the state that `worker` calls `amba_state_id`.
+	pub fn global(&mut self, worker: WorkerId, amba_state_id: u32) -> u32 {
+		let entry = self.workers.entry(worker).or_default();
+		if let Some((parent, first_own_state_id)) = entry.parent {
+			if amba_state_id < first_own_state_id {
+				return self.global(parent, amba_state_id);
+			}
+		}
+		let entry = self.workers.get_mut(&worker).unwrap();
+		*entry.ids.entry(amba_state_id).or_insert_with(|| {
+			self.next_id += 1;
+			self.next_id
+		})
+	}
+
+	/// Replace the state ids of the nodes of `edges`, as sent by `worker`.
+	pub fn translate_edges(
+		&mut self,
+		worker: WorkerId,
+		edges: &mut [(NodeMetadata, NodeMetadata)],
+	) {
+		for node in edges.iter_mut().flat_map(|(from, to)| [from, to]) {
+			match node {
+				NodeMetadata::State { amba_state_id, .. } => {
+					*amba_state_id = self.global(worker, *amba_state_id);
+				}
+				NodeMetadata::BasicBlock {
+					symbolic_state_id, ..
+				} => {
+					*symbolic_state_id = self.global(worker, *symbolic_state_id);
+				}
+				NodeMetadata::CompressedBasicBlock(block) => {
+					for symbolic_state_id in &mut block.symbolic_state_ids {
+						*symbolic_state_id = self.global(worker, *symbolic_state_id);
+					}
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use ipc::ForkOrigin;
+
+	use crate::workers::*;
+
+	#[test]
+	fn forked_workers() {
+		let mut ids = WorkerStateIds::new();
+		assert_eq!(ids.global(0, 1), 1);
+		assert_eq!(ids.global(0, 2), 2);
+
+		// Worker 1 inherits states 1 and 2 and numbers its own from 3, as does
+		// worker 0 from then on
+		ids.fork(
+			1,
+			0,
+			ForkOrigin {
+				parent_pid: 100,
+				first_own_state_id: 3,
+			},
+		);
+		assert_eq!(ids.global(1, 2), 2);
+		assert_eq!(ids.global(1, 3), 3);
+		assert_eq!(ids.global(0, 3), 4);
+		assert_eq!(ids.global(1, 1), 1);
+
+		// Inherited states not yet seen by the parent
+		ids.fork(
+			2,
+			1,
+			ForkOrigin {
+				parent_pid: 101,
+				first_own_state_id: 5,
+			},
+		);
+		assert_eq!(ids.global(2, 4), 5);
+		assert_eq!(ids.global(1, 4), 5);
+		assert_eq!(ids.global(2, 3), 3);
+		assert_eq!(ids.global(2, 5), 6);
+	}
+}
@@ -41,11 +41,9 @@ impl<S: Copy + Read + Write> QmpClient<S> {
 		Ok(serde_json::from_reader(self.stream_rx.take_until(b'\n')).unwrap())
 	}
 
-	pub fn blocking_request<F: FnMut(QmpEvent)>(
-		&mut self,
-		command: &QmpCommand,
-		mut event_handler: F,
-	) -> Result<QmpResponse, QmpError> {
+	/// Send `command` without awaiting its response, such as when another
+	/// `QmpClient` of the same stream is the one receiving.
+	pub fn blocking_send_command(&mut self, command: &QmpCommand) {
 		self.blocking_send(&QmpRequest {
 			asynchronous: false,
 			command: command.get_command(),
@@ -53,6 +51,14 @@ impl<S: Copy + Read + Write> QmpClient<S> {
 			id: self.id,
 		});
 		self.id += 1;
+	}
+
+	pub fn blocking_request<F: FnMut(QmpEvent)>(
+		&mut self,
+		command: &QmpCommand,
+		mut event_handler: F,
+	) -> Result<QmpResponse, QmpError> {
+		self.blocking_send_command(command);
 		loop {
 			match self.blocking_receive()? {
 				QmpResponse::Event(event) => event_handler(event),
@@ -176,6 +182,7 @@ pub enum QmpCommand {
 	Screendump { filename: String },
 	Stop,
 	Cont,
+	Quit,
 }
 
 impl QmpCommand {
@@ -186,6 +193,7 @@ impl QmpCommand {
 			Self::Screendump { .. } => "screendump",
 			Self::Stop => "stop",
 			Self::Cont => "cont",
+			Self::Quit => "quit",
 		}
 	}
 
@@ -0,0 +1,160 @@
+//! Limits on how long exploration may go on, which amba enforces on the host
+//! by shutting QEMU down once one of them is exceeded.
+
+use std::{fmt, time::Duration};
+
+use schemars::{
+	gen::SchemaGenerator,
+	schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation},
+	JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+
+/// When to stop exploring, see [`crate::Recipe::budget`]. Exploration stops
+/// as soon as any of the given limits is exceeded.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+	/// The longest a run may take, such as `"30m"`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_time: Option<BudgetDuration>,
+	/// The most states that may be explored
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_states: Option<usize>,
+	/// Stop once no new basic block has been covered for this long, such as
+	/// `"5m"`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub stop_when_no_new_coverage_for: Option<BudgetDuration>,
+}
+
+impl Budget {
+	pub fn is_unlimited(&self) -> bool {
+		self == &Self::default()
+	}
+}
+
+/// A duration written as a whole number of seconds, minutes, hours or days,
+/// such as `"90s"`, `"30m"`, `"12h"` or `"7d"`. Without a unit, in seconds.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct BudgetDuration(pub Duration);
+
+/// Parse a duration such as `90`, `45s`, `30m`, `12h` or `7d`, in seconds
+/// unless a unit is given.
+pub fn parse_duration(arg: &str) -> Result<Duration, String> {
+	let (number, unit) = match arg.find(|c: char| !c.is_ascii_digit()) {
+		Some(index) => arg.split_at(index),
+		None => (arg, "s"),
+	};
+	let seconds = match unit {
+		"s" => 1,
+		"m" => 60,
+		"h" => 60 * 60,
+		"d" => 24 * 60 * 60,
+		_ => {
+			return Err(format!(
+				"unknown unit `{unit}`, expected s, m, h or d"
+			))
+		}
+	};
+	let number: u64 = number
+		.parse()
+		.map_err(|err| format!("expected a duration such as `30m`, found `{arg}`: {err}"))?;
+	let seconds = number
+		.checked_mul(seconds)
+		.ok_or_else(|| format!("duration `{arg}` is too long"))?;
+	Ok(Duration::from_secs(seconds))
+}
+
+impl TryFrom<String> for BudgetDuration {
+	type Error = String;
+
+	fn try_from(duration: String) -> Result<Self, String> {
+		parse_duration(&duration).map(Self)
+	}
+}
+
+impl From<BudgetDuration> for String {
+	fn from(duration: BudgetDuration) -> Self {
+		duration.to_string()
+	}
+}
+
+impl JsonSchema for BudgetDuration {
+	fn schema_name() -> String {
+		"BudgetDuration".to_owned()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		SchemaObject {
+			metadata: Some(Box::new(Metadata {
+				description: Some(
+					"A whole number of seconds, minutes, hours or days, such as `\"30m\"`. Without a unit, in seconds."
+						.to_owned(),
+				),
+				..Default::default()
+			})),
+			instance_type: Some(InstanceType::String.into()),
+			string: Some(Box::new(StringValidation {
+				pattern: Some("^[0-9]+[smhd]?$".to_owned()),
+				..Default::default()
+			})),
+			..Default::default()
+		}
+		.into()
+	}
+}
+
+impl fmt::Display for BudgetDuration {
+	/// In the largest unit that the duration is a whole number of.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let seconds = self.0.as_secs();
+		match [("d", 24 * 60 * 60), ("h", 60 * 60), ("m", 60)]
+			.into_iter()
+			.find(|&(_, unit)| seconds != 0 && seconds / unit * unit == seconds)
+		{
+			Some((suffix, unit)) => write!(f, "{}{suffix}", seconds / unit),
+			None => write!(f, "{seconds}s"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::time::Duration;
+
+	use crate::{parse_duration, BudgetDuration};
+
+	#[test]
+	fn durations() {
+		assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+		assert_eq!(
+			parse_duration("30m"),
+			Ok(Duration::from_secs(30 * 60))
+		);
+		assert_eq!(
+			parse_duration("7d"),
+			Ok(Duration::from_secs(7 * 24 * 60 * 60))
+		);
+		assert!(parse_duration("1w").is_err());
+		assert!(parse_duration("m").is_err());
+		assert!(parse_duration("-5s").is_err());
+		assert_eq!(
+			parse_duration("999999999999999999d"),
+			Err("duration `999999999999999999d` is too long".to_owned())
+		);
+		assert_eq!(
+			parse_duration("99999999999999999999"),
+			Err(
+				"expected a duration such as `30m`, found `99999999999999999999`: number too large to fit in target type"
+					.to_owned()
+			)
+		);
+
+		let display = |seconds| BudgetDuration(Duration::from_secs(seconds)).to_string();
+		assert_eq!(display(90), "90s");
+		assert_eq!(display(120), "2m");
+		assert_eq!(display(2 * 24 * 60 * 60), "2d");
+		assert_eq!(display(0), "0s");
+	}
+}
@@ -0,0 +1,1221 @@
+//! Compiler-style diagnostics for invalid recipes, pointing at the line and
+//! column of the recipe file that is wrong.
+//!
+//! Serde only reports that no variant of an untagged enum matched, so for
+//! syntactically invalid recipes we walk the parsed value ourselves and
+//! explain which forms were expected where. The forms are read from the JSON
+//! Schema of recipes.
+
+use std::fmt::Write;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::{
+	Budget, ByteConstraint, FileMode, Hook, InputLayout, RecipeError, RecipeFormat, Seeds,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+	pub message: String,
+	pub location: Option<Location>,
+	pub notes: Vec<String>,
+	pub help: Option<String>,
+}
+
+/// A span within a single line of a recipe file. Lines and columns count from
+/// one, columns in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+	pub line: usize,
+	pub column: usize,
+	pub len: usize,
+}
+
+/// A step into a recipe value, by object key or array index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Segment {
+	Key(String),
+	Index(usize),
+}
+
+impl RecipeError {
+	/// Explain this error in terms of `source`, the recipe file it came from.
+	/// Errors in a recipe that `source` extends should be explained with the
+	/// source of that recipe instead, see [`RecipeError::InExtended`].
+	pub fn diagnostics(&self, source: &str, format: RecipeFormat) -> Vec<Diagnostic> {
+		match self {
+			Self::NotUtf8(err) => {
+				vec![Diagnostic::new(format!("recipe is not valid UTF-8: {err}"))]
+			}
+			Self::NotJson(err) => vec![Diagnostic::new(format!("invalid JSON: {err}"))
+				.at_line_column(source, err.line(), err.column())],
+			Self::NotToml(err) => {
+				let diagnostic = Diagnostic::new(format!("invalid TOML: {err}"));
+				vec![match err.line_col() {
+					Some((line, column)) => diagnostic.at_line_column(source, line + 1, column + 1),
+					None => diagnostic,
+				}]
+			}
+			Self::NotYaml(err) => {
+				let diagnostic = Diagnostic::new(format!("invalid YAML: {err}"));
+				vec![match err.location() {
+					Some(location) => {
+						diagnostic.at_line_column(source, location.line(), location.column())
+					}
+					None => diagnostic,
+				}]
+			}
+			Self::NotSyntacticRecipe(err) => {
+				let mut diagnostics = Vec::new();
+				if let Ok(value) = crate::Recipe::parse_value(source, format) {
+					check_recipe(&value, &mut |path, diagnostic: Diagnostic| {
+						diagnostics.push(diagnostic.at_path(source, &path));
+					});
+				}
+				if diagnostics.is_empty() {
+					diagnostics.push(
+						Diagnostic::new(format!("invalid recipe: {err}")).at_line_column(
+							source,
+							err.line(),
+							err.column(),
+						),
+					);
+				}
+				diagnostics
+			}
+			Self::NotSemanticRecipe(problems) => problems
+				.iter()
+				.map(|problem| {
+					Diagnostic::new(problem.message.clone()).at_path(source, &problem.path)
+				})
+				.collect(),
+			Self::Unreadable(path, err) => vec![Diagnostic::new(format!(
+				"could not read recipe `{}`: {err}",
+				path.display()
+			))],
+			Self::InExtended(path, _) => vec![Diagnostic::new(format!(
+				"the recipe `{}` that this recipe extends is invalid",
+				path.display()
+			))],
+			Self::CyclicExtend(path) => vec![Diagnostic::new(format!(
+				"recipe `{}` extends itself",
+				path.display()
+			))],
+			Self::UndefinedVariables(names) => names
+				.iter()
+				.map(|name| {
+					let placeholder = format!("${{{name}}}");
+					let mut diagnostic = Diagnostic::new(format!("undefined variable `{name}`"))
+						.with_help(format!("set it with `--set {name}=...`"));
+					diagnostic.location = source
+						.find(&placeholder)
+						.map(|offset| Location::of_offset(source, offset, placeholder.len()));
+					diagnostic
+				})
+				.collect(),
+		}
+	}
+}
+
+impl Diagnostic {
+	fn new(message: String) -> Self {
+		Self {
+			message,
+			location: None,
+			notes: Vec::new(),
+			help: None,
+		}
+	}
+
+	fn with_note(mut self, note: impl Into<String>) -> Self {
+		self.notes.push(note.into());
+		self
+	}
+
+	fn with_help(mut self, help: String) -> Self {
+		self.help = Some(help);
+		self
+	}
+
+	/// Serde reports line 0 when it does not know the position.
+	fn at_line_column(mut self, source: &str, line: usize, column: usize) -> Self {
+		if line > 0 && line <= source.lines().count() {
+			self.location = Some(Location {
+				line,
+				column: column.max(1),
+				len: 1,
+			});
+		}
+		self
+	}
+
+	fn at_path(mut self, source: &str, path: &[Segment]) -> Self {
+		self.location = locate(source, path);
+		self
+	}
+
+	/// Render like a compiler error, with the offending line of `source`
+	/// underlined, and with ANSI colours if `colour` is set.
+	pub fn render(&self, file_name: &str, source: &str, colour: bool) -> String {
+		let paint = |code: &str, text: &str| match colour {
+			true => format!("\x1b[{code}m{text}\x1b[0m"),
+			false => text.to_owned(),
+		};
+		let mut ret = String::new();
+		writeln!(
+			ret,
+			"{}{}",
+			paint("1;31", "error"),
+			paint("1", &format!(": {}", self.message))
+		)
+		.unwrap();
+
+		let gutter_width = self
+			.location
+			.map_or(0, |location| location.line.to_string().len());
+		let gutter = " ".repeat(gutter_width);
+		let bar = paint("1;34", "|");
+		match self.location {
+			Some(Location { line, column, len }) => {
+				let text = source.lines().nth(line - 1).unwrap_or("");
+				let prefix: String = text
+					.chars()
+					.take(column - 1)
+					.map(|c| match c {
+						'\t' => "    ",
+						_ => " ",
+					})
+					.collect();
+				writeln!(
+					ret,
+					"{gutter}{} {file_name}:{line}:{column}",
+					paint("1;34", "-->")
+				)
+				.unwrap();
+				writeln!(ret, "{gutter} {bar}").unwrap();
+				writeln!(
+					ret,
+					"{} {bar} {}",
+					paint("1;34", &line.to_string()),
+					text.replace('\t', "    ")
+				)
+				.unwrap();
+				writeln!(
+					ret,
+					"{gutter} {bar} {prefix}{}",
+					paint("1;31", &"^".repeat(len.max(1)))
+				)
+				.unwrap();
+			}
+			None => writeln!(ret, " {} {file_name}", paint("1;34", "-->")).unwrap(),
+		}
+		for note in &self.notes {
+			writeln!(
+				ret,
+				"{gutter} {} note: {note}",
+				paint("1;34", "=")
+			)
+			.unwrap();
+		}
+		if let Some(help) = &self.help {
+			writeln!(
+				ret,
+				"{gutter} {} help: {help}",
+				paint("1;34", "=")
+			)
+			.unwrap();
+		}
+		ret
+	}
+}
+
+impl Location {
+	fn of_offset(source: &str, offset: usize, len: usize) -> Self {
+		let before = &source[..offset];
+		let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+		Self {
+			line: before.matches('\n').count() + 1,
+			column: before[line_start..].chars().count() + 1,
+			len: source[offset..offset + len].chars().count(),
+		}
+	}
+}
+
+/// Find the key or array element that `path` ends in by searching for each of
+/// its segments in turn, as written in any of the recipe formats. Elements of
+/// arrays that cannot be told apart are located by their enclosing key.
+fn locate(source: &str, path: &[Segment]) -> Option<Location> {
+	let mut found: Option<(usize, usize)> = None;
+	let mut offset = 0;
+	for segment in path {
+		match segment {
+			Segment::Key(key) => {
+				let (start, len) = find_key(&source[offset..], key)?;
+				found = Some((offset + start, len));
+				offset += start + len;
+			}
+			Segment::Index(index) => {
+				if let Some((start, len)) =
+					found.and_then(|(start, len)| find_element(source, start, len, *index))
+				{
+					found = Some((start, len));
+					offset = start;
+				}
+			}
+		}
+	}
+	found.map(|(start, len)| Location::of_offset(source, start, len))
+}
+
+/// Element `index` of the array that is the value of the key, or itself the
+/// element, found at `start` with length `len`. The array is written either
+/// inline as `[a, b]`, as a YAML block of `- ` items, or as TOML `[[key]]`
+/// tables.
+fn find_element(source: &str, start: usize, len: usize, index: usize) -> Option<(usize, usize)> {
+	if source[..start].ends_with("[[") && source[start + len..].starts_with("]]") {
+		let header_start = start - 2;
+		let header = &source[header_start..start + len + 2];
+		let (offset, _) = source[header_start..].match_indices(header).nth(index)?;
+		return Some((header_start + offset, header.len()));
+	}
+
+	let rest = &source[start + len..];
+	let value = rest
+		.trim_start_matches([' ', '\t'])
+		.strip_prefix([':', '='])
+		.unwrap_or(rest.trim_start_matches([' ', '\t']))
+		.trim_start();
+	let value_start = source.len() - value.len();
+	if value.starts_with('[') {
+		return find_inline_element(value, index).map(|(offset, len)| (value_start + offset, len));
+	}
+	if value.starts_with('-') {
+		let line_start = source[..value_start]
+			.rfind('\n')
+			.map_or(0, |newline| newline + 1);
+		let indent = value_start - line_start;
+		let mut items = Vec::new();
+		let mut line_offset = line_start;
+		for line in source[line_start..].split_inclusive('\n') {
+			let trimmed = line.trim_start();
+			let line_indent = line.len() - trimmed.len();
+			if !trimmed.trim_end().is_empty() {
+				if line_indent < indent || (line_indent == indent && !trimmed.starts_with('-')) {
+					break;
+				}
+				if line_indent == indent {
+					let item = trimmed[1..].trim_start();
+					let item_start = line_offset + line.len() - item.len();
+					items.push((item_start, item.trim_end().len()));
+				}
+			}
+			line_offset += line.len();
+		}
+		return items.get(index).copied();
+	}
+	None
+}
+
+/// Element `index` of the inline array that `source` starts with, skipping
+/// over strings and nested arrays and objects.
+fn find_inline_element(source: &str, index: usize) -> Option<(usize, usize)> {
+	let mut element = 0;
+	let mut element_start = None;
+	let mut depth = 0;
+	let mut chars = source.char_indices().skip(1);
+	let end_of = |element_start: Option<usize>, end: usize| {
+		element_start.map(|start: usize| (start, source[start..end].trim_end().len()))
+	};
+	while let Some((i, c)) = chars.next() {
+		match c {
+			'"' | '\'' => {
+				element_start.get_or_insert(i);
+				let mut escaped = false;
+				for (_, inner) in chars.by_ref() {
+					match inner {
+						'\\' if c == '"' && !escaped => escaped = true,
+						inner if inner == c && !escaped => break,
+						_ => escaped = false,
+					}
+				}
+			}
+			'[' | '{' => {
+				element_start.get_or_insert(i);
+				depth += 1;
+			}
+			']' | '}' if depth > 0 => depth -= 1,
+			']' | '}' => {
+				return (element == index)
+					.then(|| end_of(element_start, i))
+					.flatten()
+			}
+			',' if depth == 0 => {
+				if element == index {
+					return end_of(element_start, i);
+				}
+				element += 1;
+				element_start = None;
+			}
+			c if c.is_whitespace() => {}
+			_ => {
+				element_start.get_or_insert(i);
+			}
+		}
+	}
+	None
+}
+
+/// The first place `key` occurs as a key, quoted or bare, and followed by
+/// a `:`, `=`, `.` or `]` as in JSON, YAML and TOML.
+fn find_key(source: &str, key: &str) -> Option<(usize, usize)> {
+	let is_bare_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+	let candidates = [format!("\"{key}\""), format!("'{key}'"), key.to_owned()];
+	let mut ret: Option<(usize, usize)> = None;
+	for candidate in &candidates {
+		for (start, _) in source.match_indices(candidate.as_str()) {
+			let end = start + candidate.len();
+			let bare = candidate == key;
+			if bare
+				&& (source[..start].ends_with(is_bare_char)
+					|| source[end..].starts_with(is_bare_char))
+			{
+				continue;
+			}
+			if !source[end..]
+				.trim_start_matches([' ', '\t'])
+				.starts_with([':', '=', '.', ']'])
+			{
+				continue;
+			}
+			if !matches!(ret, Some((best, _)) if best <= start) {
+				ret = Some((start, candidate.len()));
+			}
+			break;
+		}
+	}
+	ret
+}
+
+/// The JSON Schema of recipes, see [`crate::Recipe::json_schema`], that the
+/// forms expected of recipe values are read from, so that they cannot go out
+/// of date with the recipe types.
+struct Schema {
+	root: Value,
+}
+
+impl Schema {
+	fn new() -> Self {
+		Self {
+			root: serde_json::to_value(schemars::schema_for!(crate::Recipe)).unwrap(),
+		}
+	}
+
+	fn definition(&self, name: &str) -> &Value {
+		let definition = &self.root["definitions"][name];
+		assert!(
+			!definition.is_null(),
+			"no definition {name} in the recipe schema"
+		);
+		definition
+	}
+
+	/// The object forms of the definition `name`.
+	fn forms(&self, name: &str) -> Vec<ObjectForm> {
+		self.object_forms(self.definition(name))
+	}
+
+	/// The forms of `schema` that are objects, through references and
+	/// alternatives.
+	fn object_forms(&self, schema: &Value) -> Vec<ObjectForm> {
+		if let Some(name) = schema["$ref"]
+			.as_str()
+			.and_then(|reference| reference.strip_prefix("#/definitions/"))
+		{
+			return self.forms(name);
+		}
+		if let Some(alternatives) = schema["anyOf"].as_array().or(schema["oneOf"].as_array()) {
+			return alternatives
+				.iter()
+				.flat_map(|alternative| self.object_forms(alternative))
+				.collect();
+		}
+		let Some(properties) = schema["properties"].as_object() else {
+			return Vec::new();
+		};
+		let required: Vec<String> = schema["required"]
+			.as_array()
+			.into_iter()
+			.flatten()
+			.filter_map(Value::as_str)
+			.map(str::to_owned)
+			.collect();
+		vec![ObjectForm {
+			kind: properties
+				.get("kind")
+				.and_then(|kind| kind["enum"][0].as_str())
+				.map(str::to_owned),
+			optional: properties
+				.keys()
+				.filter(|key| !required.contains(key))
+				.cloned()
+				.collect(),
+			required,
+		}]
+	}
+}
+
+/// One of the forms an enum can be written in as an object, with required and
+/// optional keys.
+struct ObjectForm {
+	/// The `kind` that chooses this form, if it is tagged as in `tagged.rs`
+	kind: Option<String>,
+	required: Vec<String>,
+	optional: Vec<String>,
+}
+
+impl ObjectForm {
+	fn describe(&self) -> String {
+		let keys: Vec<String> = self
+			.required
+			.iter()
+			.cloned()
+			.chain(self.optional.iter().map(|key| format!("{key}?")))
+			.collect();
+		format!("{{ {} }}", keys.join(", "))
+	}
+
+	fn keys(&self) -> impl Iterator<Item = &str> {
+		self.required
+			.iter()
+			.chain(&self.optional)
+			.map(String::as_str)
+	}
+}
+
+type Report<'a> = dyn FnMut(Vec<Segment>, Diagnostic) + 'a;
+
+fn check_recipe(value: &Value, report: &mut Report<'_>) {
+	let schema = Schema::new();
+	let Value::Object(recipe) = value else {
+		report(
+			Vec::new(),
+			Diagnostic::new(format!(
+				"expected a recipe object, found {}",
+				kind(value)
+			)),
+		);
+		return;
+	};
+	let [form] = &schema.object_forms(&schema.root)[..] else {
+		unreachable!("a recipe has a single form")
+	};
+	// Base recipes are merged in before the recipe is deserialized, see
+	// `resolve.rs`
+	let is_known = |key: &str| key == "extend" || form.keys().any(|known| known == key);
+	for key in &form.required {
+		if !recipe.contains_key(key) {
+			let diagnostic = Diagnostic::new(format!("missing field `{key}`"));
+			match recipe
+				.keys()
+				.filter(|other| !is_known(other))
+				.find(|other| is_similar(other, key))
+			{
+				Some(similar) => report(
+					vec![Segment::Key(similar.clone())],
+					diagnostic.with_help(format!("did you mean `{key}`?")),
+				),
+				None => report(Vec::new(), diagnostic),
+			}
+		}
+	}
+
+	let path = |key: &str| vec![Segment::Key(key.to_owned())];
+	for key in ["executable_path", "stdin_path"] {
+		if let Some(value) = recipe.get(key) {
+			check_type::<String>(path(key), value, report);
+		}
+	}
+	if let Some(value) = recipe.get("arg0") {
+		check_type::<Option<String>>(path("arg0"), value, report);
+	}
+	match recipe.get("files") {
+		Some(Value::Object(files)) => {
+			for (guest_path, file) in files {
+				check_file(
+					&schema,
+					vec![
+						Segment::Key("files".to_owned()),
+						Segment::Key(guest_path.clone()),
+					],
+					file,
+					report,
+				);
+			}
+		}
+		Some(value) => check_type::<Map<String, Value>>(path("files"), value, report),
+		None => {}
+	}
+	match recipe.get("arguments") {
+		Some(Value::Array(arguments)) => {
+			for (i, argument) in arguments.iter().enumerate() {
+				let mut path = path("arguments");
+				path.push(Segment::Index(i));
+				check_argument(&schema, path, argument, report);
+			}
+		}
+		Some(value) => check_type::<Vec<Value>>(path("arguments"), value, report),
+		None => {}
+	}
+	if let Some(environment) = recipe.get("environment") {
+		check_environment(&schema, path("environment"), environment, report);
+	}
+	for key in ["setup", "teardown"] {
+		if let Some(value) = recipe.get(key) {
+			check_type::<Vec<Hook>>(path(key), value, report);
+		}
+	}
+	if let Some(value) = recipe.get("seeds") {
+		check_type::<Option<Seeds>>(path("seeds"), value, report);
+	}
+	if let Some(value) = recipe.get("budget") {
+		check_type::<Budget>(path("budget"), value, report);
+	}
+}
+
+fn check_file(schema: &Schema, path: Vec<Segment>, value: &Value, report: &mut Report<'_>) {
+	let forms = schema.forms("UntaggedFileSource");
+	let tagged_forms = schema.forms("TaggedFileSource");
+	let Value::Object(object) = value else {
+		if !value.is_string() {
+			let forms: Vec<String> = std::iter::once("a host path".to_owned())
+				.chain(forms.iter().map(ObjectForm::describe))
+				.chain([describe_tagged(&tagged_forms)])
+				.collect();
+			report(path, expected_one_of("file", value, &forms));
+		}
+		return;
+	};
+	if !check_tagged(&path, object, &tagged_forms, "file", report) {
+		check_object(&path, object, &forms, "file", report);
+	}
+	check_field::<String>(&path, object, "seed", report);
+	check_field::<String>(&path, object, "host_path", report);
+	check_field::<String>(&path, object, "symlink", report);
+	check_field::<String>(&path, object, "target", report);
+	check_field::<Option<FileMode>>(&path, object, "mode", report);
+	check_field::<Option<u64>>(&path, object, "max_length", report);
+	check_field::<Option<InputLayout>>(&path, object, "layout", report);
+	check_symbolic(schema, &path, object, report);
+}
+
+fn check_argument(schema: &Schema, path: Vec<Segment>, value: &Value, report: &mut Report<'_>) {
+	let forms = schema.forms("UntaggedArgumentSource");
+	let tagged_forms = schema.forms("TaggedArgumentSource");
+	let Value::Object(object) = value else {
+		if !value.is_string() {
+			let forms: Vec<String> = std::iter::once("a string".to_owned())
+				.chain(forms.iter().map(ObjectForm::describe))
+				.chain([describe_tagged(&tagged_forms)])
+				.collect();
+			report(path, expected_one_of("argument", value, &forms));
+		}
+		return;
+	};
+	if !check_tagged(&path, object, &tagged_forms, "argument", report) {
+		check_object(&path, object, &forms, "argument", report);
+	}
+	check_field::<String>(&path, object, "seed", report);
+	check_field::<String>(&path, object, "value", report);
+	check_field::<Option<u64>>(&path, object, "max_length", report);
+	check_symbolic(schema, &path, object, report);
+}
+
+fn check_environment(schema: &Schema, path: Vec<Segment>, value: &Value, report: &mut Report<'_>) {
+	let forms = schema.forms("Environment");
+	let value_forms = schema.forms("UntaggedEnvVarSource");
+	let tagged_value_forms = schema.forms("TaggedEnvVarSource");
+	let Value::Object(environment) = value else {
+		let forms: Vec<String> = forms.iter().map(ObjectForm::describe).collect();
+		report(
+			path,
+			expected_one_of("environment", value, &forms),
+		);
+		return;
+	};
+	check_object(&path, environment, &forms, "environment", report);
+	check_field::<bool>(&path, environment, "inherit", report);
+	check_field::<Vec<String>>(&path, environment, "remove", report);
+	let Some(add) = environment.get("add") else {
+		return;
+	};
+	let mut path = path;
+	path.push(Segment::Key("add".to_owned()));
+	let Value::Object(add) = add else {
+		check_type::<Map<String, Value>>(path, add, report);
+		return;
+	};
+	for (name, value) in add {
+		let mut path = path.clone();
+		path.push(Segment::Key(name.clone()));
+		match value {
+			Value::String(_) => {}
+			Value::Object(object) => {
+				if !check_tagged(
+					&path,
+					object,
+					&tagged_value_forms,
+					"environment variable",
+					report,
+				) {
+					check_object(
+						&path,
+						object,
+						&value_forms,
+						"environment variable",
+						report,
+					);
+				}
+				check_field::<String>(&path, object, "value", report);
+				check_symbolic(schema, &path, object, report);
+			}
+			value => {
+				let forms: Vec<String> = std::iter::once("a string".to_owned())
+					.chain(value_forms.iter().map(ObjectForm::describe))
+					.chain([describe_tagged(&tagged_value_forms)])
+					.collect();
+				report(
+					path,
+					expected_one_of("environment variable", value, &forms),
+				);
+			}
+		}
+	}
+}
+
+fn check_symbolic(
+	schema: &Schema,
+	path: &[Segment],
+	object: &Map<String, Value>,
+	report: &mut Report<'_>,
+) {
+	let Some(value) = object.get("symbolic") else {
+		return;
+	};
+	let mut path = path.to_vec();
+	path.push(Segment::Key("symbolic".to_owned()));
+	let Value::Array(ranges) = value else {
+		check_type::<Vec<Value>>(path, value, report);
+		return;
+	};
+	for (i, range) in ranges.iter().enumerate() {
+		let mut path = path.clone();
+		path.push(Segment::Index(i));
+		check_range(schema, path, range, report);
+	}
+}
+
+fn check_range(schema: &Schema, path: Vec<Segment>, value: &Value, report: &mut Report<'_>) {
+	let forms = schema.forms("SymbolicRange");
+	match value {
+		Value::Number(number) if number.is_u64() => {}
+		Value::Array(pair)
+			if pair.len() == 2 && pair[0].is_u64() && (pair[1].is_u64() || pair[1].is_null()) => {}
+		Value::Object(object) => {
+			check_object(&path, object, &forms, "symbolic range", report);
+			check_field::<u64>(&path, object, "start", report);
+			check_field::<Option<u64>>(&path, object, "end", report);
+			check_field::<ByteConstraint>(&path, object, "constraint", report);
+			if let Some(range) = object.get("range") {
+				let mut path = path;
+				path.push(Segment::Key("range".to_owned()));
+				check_range(schema, path, range, report);
+			}
+		}
+		value => {
+			let forms: Vec<String> = ["an index", "[start, end]", "[start, null]"]
+				.into_iter()
+				.map(str::to_owned)
+				.chain(forms.iter().map(ObjectForm::describe))
+				.collect();
+			report(
+				path,
+				expected_one_of("symbolic range", value, &forms),
+			);
+		}
+	}
+}
+
+/// Report unknown keys and objects that match none of `forms`.
+fn check_object(
+	path: &[Segment],
+	object: &Map<String, Value>,
+	forms: &[ObjectForm],
+	what: &str,
+	report: &mut Report<'_>,
+) {
+	let mut known: Vec<&str> = Vec::new();
+	for key in forms.iter().flat_map(ObjectForm::keys) {
+		if !known.contains(&key) {
+			known.push(key);
+		}
+	}
+	for key in object.keys() {
+		if known.contains(&key.as_str()) {
+			continue;
+		}
+		let mut diagnostic = Diagnostic::new(format!("unknown field `{key}` in {what}"));
+		diagnostic = match known.iter().find(|known| is_similar(key, known)) {
+			Some(similar) => diagnostic.with_help(format!("did you mean `{similar}`?")),
+			None => diagnostic.with_note(format!(
+				"expected one of `{}`",
+				known.join("`, `")
+			)),
+		};
+		let mut path = path.to_vec();
+		path.push(Segment::Key(key.clone()));
+		report(path, diagnostic);
+	}
+
+	let matches = |form: &ObjectForm| form.required.iter().all(|key| object.contains_key(key));
+	if !forms.iter().any(matches) {
+		let mut diagnostic = Diagnostic::new(format!("{what} matches none of its forms"));
+		for form in forms {
+			let missing: Vec<&str> = form
+				.required
+				.iter()
+				.filter(|key| !object.contains_key(*key))
+				.map(String::as_str)
+				.collect();
+			diagnostic = diagnostic.with_note(format!(
+				"{} is missing `{}`",
+				form.describe(),
+				missing.join("`, `")
+			));
+		}
+		report(path.to_vec(), diagnostic);
+	}
+}
+
+/// Check an object that chooses its form by its `kind` key, as in
+/// `tagged.rs`. Returns whether the object has a `kind`, since untagged forms
+/// never do.
+fn check_tagged(
+	path: &[Segment],
+	object: &Map<String, Value>,
+	forms: &[ObjectForm],
+	what: &str,
+	report: &mut Report<'_>,
+) -> bool {
+	let Some(kind) = object.get("kind") else {
+		return false;
+	};
+	if let Some(form) = forms
+		.iter()
+		.find(|form| form.kind.is_some() && kind.as_str() == form.kind.as_deref())
+	{
+		check_object(
+			path,
+			object,
+			std::slice::from_ref(form),
+			what,
+			report,
+		);
+		return true;
+	}
+	let kinds: Vec<&str> = forms
+		.iter()
+		.filter_map(|form| form.kind.as_deref())
+		.collect();
+	let mut diagnostic = Diagnostic::new(format!("unknown {what} kind {kind}"));
+	diagnostic = match kind
+		.as_str()
+		.and_then(|kind| kinds.iter().find(|name| is_similar(kind, name)))
+	{
+		Some(similar) => diagnostic.with_help(format!("did you mean `{similar}`?")),
+		None => diagnostic.with_note(format!(
+			"expected one of `{}`",
+			kinds.join("`, `")
+		)),
+	};
+	let mut path = path.to_vec();
+	path.push(Segment::Key("kind".to_owned()));
+	report(path, diagnostic);
+	true
+}
+
+/// Describe the tagged forms `forms` as one of the ways to write a value.
+fn describe_tagged(forms: &[ObjectForm]) -> String {
+	let kinds: Vec<&str> = forms
+		.iter()
+		.filter_map(|form| form.kind.as_deref())
+		.collect();
+	format!(
+		"{{ kind, .. }} with `kind` one of `{}`",
+		kinds.join("`, `")
+	)
+}
+
+fn check_field<T: DeserializeOwned>(
+	path: &[Segment],
+	object: &Map<String, Value>,
+	key: &str,
+	report: &mut Report<'_>,
+) {
+	if let Some(value) = object.get(key) {
+		let mut path = path.to_vec();
+		path.push(Segment::Key(key.to_owned()));
+		check_type::<T>(path, value, report);
+	}
+}
+
+fn check_type<T: DeserializeOwned>(path: Vec<Segment>, value: &Value, report: &mut Report<'_>) {
+	if let Err(err) = serde_json::from_value::<T>(value.clone()) {
+		let field = match path.last() {
+			Some(Segment::Key(key)) => format!("`{key}`"),
+			Some(Segment::Index(i)) => format!("element {i}"),
+			None => "recipe".to_owned(),
+		};
+		report(path, Diagnostic::new(format!("{field}: {err}")));
+	}
+}
+
+fn expected_one_of(what: &str, value: &Value, forms: &[String]) -> Diagnostic {
+	let mut diagnostic = Diagnostic::new(format!("invalid {what}, found {}", kind(value)));
+	for form in forms {
+		diagnostic = diagnostic.with_note(format!("a {what} can be written as {form}"));
+	}
+	diagnostic
+}
+
+fn kind(value: &Value) -> &'static str {
+	match value {
+		Value::Null => "null",
+		Value::Bool(_) => "a boolean",
+		Value::Number(_) => "a number",
+		Value::String(_) => "a string",
+		Value::Array(_) => "an array",
+		Value::Object(_) => "an object",
+	}
+}
+
+/// Whether `a` is probably a misspelling of `b`.
+fn is_similar(a: &str, b: &str) -> bool {
+	let a: Vec<char> = a.chars().collect();
+	let b: Vec<char> = b.chars().collect();
+	let mut row: Vec<usize> = (0..=b.len()).collect();
+	for (i, &ca) in a.iter().enumerate() {
+		let mut diagonal = row[0];
+		row[0] = i + 1;
+		for (j, &cb) in b.iter().enumerate() {
+			let substitute = diagonal + usize::from(ca != cb);
+			diagonal = row[j + 1];
+			row[j + 1] = substitute.min(row[j] + 1).min(row[j + 1] + 1);
+		}
+	}
+	row[b.len()] <= (b.len() / 3).max(1)
+}
+
+#[cfg(test)]
+mod test {
+	use std::path::Path;
+
+	use serde_json::{json, Value};
+
+	use super::{check_recipe, Schema};
+	use crate::{Location, Recipe, RecipeFormat};
+
+	#[test]
+	fn points_at_mistakes() {
+		let source = r#"{
+	"files": {
+		"input.txt": { "seed": "abc", "symbolc": [0] }
+	},
+	"executable_path": "./prog",
+	"stdin_path": "/tmp/input.txt",
+	"arguments": [{ "seed": "x", "symbolic": ["0"] }]
+}"#;
+		let err = Recipe::deserialize_from(source.as_bytes()).unwrap_err();
+		let diagnostics = err.diagnostics(source, RecipeFormat::Json);
+		let summary: Vec<_> = diagnostics
+			.iter()
+			.map(|diagnostic| {
+				(
+					diagnostic.message.as_str(),
+					diagnostic.location,
+					diagnostic.help.as_deref(),
+				)
+			})
+			.collect();
+		assert_eq!(
+			summary,
+			[
+				(
+					"unknown field `symbolc` in file",
+					Some(Location {
+						line: 3,
+						column: 33,
+						len: 9
+					}),
+					Some("did you mean `symbolic`?")
+				),
+				(
+					"invalid symbolic range, found a string",
+					Some(Location {
+						line: 7,
+						column: 44,
+						len: 3
+					}),
+					None
+				),
+			]
+		);
+		assert_eq!(
+			diagnostics[0].render("prog.json", source, false),
+			"error: unknown field `symbolc` in file
+ --> prog.json:3:33
+  |
+3 |         \"input.txt\": { \"seed\": \"abc\", \"symbolc\": [0] }
+  |                                       ^^^^^^^^^
+  = help: did you mean `symbolic`?
+"
+		);
+	}
+
+	#[test]
+	fn explains_untagged_forms() {
+		let source = r#"
+			executable_path = "./prog"
+			stdin_path = "/tmp/input.txt"
+			[files]
+			prog = 5
+		"#;
+		let err =
+			Recipe::deserialize_from_format(source.as_bytes(), RecipeFormat::Toml).unwrap_err();
+		let diagnostics = err.diagnostics(source, RecipeFormat::Toml);
+		assert_eq!(diagnostics.len(), 1);
+		assert_eq!(
+			diagnostics[0].message,
+			"invalid file, found a number"
+		);
+		assert_eq!(
+			diagnostics[0].location.map(|location| location.line),
+			Some(5)
+		);
+		assert_eq!(diagnostics[0].notes.len(), 6);
+		assert_eq!(
+			diagnostics[0].notes[1],
+			"a file can be written as { host_path, mode }"
+		);
+	}
+
+	#[test]
+	fn locates_array_elements() {
+		let json = r#"{
+	"files": { "input.txt": { "seed": "abc", "symbolic": [0] } },
+	"executable_path": "./prog",
+	"stdin_path": "/tmp/input.txt",
+	"arguments": [
+		{ "seed": "x", "symbolic": [0] },
+		"y, z",
+		{ "seed": "x", "symbolic": [{ "start": "1" }] }
+	]
+}"#;
+		let yaml = "
+files:
+  input.txt: { seed: abc, symbolic: [0] }
+executable_path: ./prog
+stdin_path: /tmp/input.txt
+arguments:
+  - { seed: x, symbolic: [0] }
+  - y
+  - seed: x
+    symbolic: [{ start: '1' }]
+";
+		let toml = r#"
+executable_path = "./prog"
+stdin_path = "/tmp/input.txt"
+files = { "input.txt" = { seed = "abc", symbolic = [0] } }
+[[arguments]]
+seed = "x"
+symbolic = [0]
+[[arguments]]
+seed = "y"
+[[arguments]]
+seed = "x"
+symbolic = [{ start = "1" }]
+"#;
+		for (source, format, line) in [
+			(json, RecipeFormat::Json, 8),
+			(yaml, RecipeFormat::Yaml, 10),
+			(toml, RecipeFormat::Toml, 12),
+		] {
+			let err = Recipe::deserialize_from_format(source.as_bytes(), format).unwrap_err();
+			let diagnostics = err.diagnostics(source, format);
+			assert_eq!(
+				diagnostics
+					.iter()
+					.map(|diagnostic| diagnostic.location.map(|location| location.line))
+					.collect::<Vec<_>>(),
+				[Some(line)],
+				"{format:?}: {diagnostics:?}"
+			);
+		}
+	}
+
+	#[test]
+	fn locates_semantic_problems() {
+		let source = r#"{
+	"files": { "input.txt": { "seed": "abc", "symbolic": [[0, 4]] } },
+	"executable_path": "/bin/prog",
+	"stdin_path": "/tmp/input.txt",
+	"arguments": ["a", { "seed": "b", "symbolic": [[1, 2]] }],
+	"budget": { "max_states": 0 }
+}"#;
+		let recipe = Recipe::deserialize_from(source.as_bytes()).unwrap();
+		let err = recipe.validate(Path::new("/nonexistent")).unwrap_err();
+		let locations: Vec<_> = err
+			.diagnostics(source, RecipeFormat::Json)
+			.into_iter()
+			.map(|diagnostic| diagnostic.location)
+			.collect();
+		assert_eq!(
+			locations,
+			[
+				Some(Location {
+					line: 2,
+					column: 43,
+					len: 10
+				}),
+				Some(Location {
+					line: 5,
+					column: 36,
+					len: 10
+				}),
+				Some(Location {
+					line: 6,
+					column: 14,
+					len: 12
+				}),
+			]
+		);
+	}
+
+	/// A value of `schema` with every optional key of objects given.
+	fn example(schema: &Schema, value: &Value) -> Value {
+		if let Some(name) = value["$ref"]
+			.as_str()
+			.and_then(|reference| reference.strip_prefix("#/definitions/"))
+		{
+			return example(schema, schema.definition(name));
+		}
+		if let Some(first) = value["anyOf"]
+			.get(0)
+			.or(value["oneOf"].get(0))
+			.or(value["allOf"].get(0))
+		{
+			return example(schema, first);
+		}
+		if let Some(first) = value["enum"].get(0) {
+			return first.clone();
+		}
+		let kind = value["type"].as_str().or(value["type"][0].as_str());
+		match kind {
+			Some("object") => Value::Object(
+				value["properties"]
+					.as_object()
+					.into_iter()
+					.flatten()
+					.map(|(key, value)| (key.clone(), example(schema, value)))
+					.collect(),
+			),
+			Some("array") => json!([]),
+			Some("integer") => json!(0),
+			Some("boolean") => json!(true),
+			Some("null") => Value::Null,
+			// Matches the patterns of both `FileMode` and `BudgetDuration`
+			Some("string") if value.get("pattern").is_some() => json!("7"),
+			Some("string") => json!("x"),
+			other => panic!("no example of {other:?}"),
+		}
+	}
+
+	#[test]
+	fn forms_agree_with_recipe_types() {
+		let schema = Schema::new();
+		let base = json!({
+			"files": {},
+			"executable_path": "./prog",
+			"stdin_path": "/tmp/input.txt",
+		});
+		let placements: [(&str, fn(&mut Value, Value)); 4] = [
+			("FileSource", |recipe, form| {
+				recipe["files"]["f"] = form;
+			}),
+			("ArgumentSource", |recipe, form| {
+				recipe["arguments"] = json!([form]);
+			}),
+			("EnvVarSource", |recipe, form| {
+				recipe["environment"] = json!({ "inherit": true, "add": { "V": form } });
+			}),
+			("SymbolicRange", |recipe, form| {
+				recipe["files"]["f"] = json!({ "seed": "x", "symbolic": [form] });
+			}),
+		];
+		let check = |recipe: &Value| {
+			let mut diagnostics = Vec::new();
+			check_recipe(recipe, &mut |_, diagnostic| {
+				diagnostics.push(diagnostic);
+			});
+			(
+				serde_json::from_value::<Recipe>(recipe.clone()).is_ok(),
+				diagnostics.is_empty(),
+			)
+		};
+
+		let [recipe_form] = &schema.object_forms(&schema.root)[..] else {
+			panic!()
+		};
+		let mut recipe = base.clone();
+		for key in recipe_form.keys() {
+			recipe[key] = example(&schema, &schema.root["properties"][key]);
+		}
+		assert_eq!(check(&recipe), (true, true), "{recipe}");
+
+		for (name, place) in placements {
+			let forms: Vec<Value> = schema.definition(name)["anyOf"]
+				.as_array()
+				.unwrap()
+				.iter()
+				.flat_map(|alternative| {
+					let alternative = match alternative["$ref"].as_str() {
+						Some(reference) => {
+							schema.definition(reference.strip_prefix("#/definitions/").unwrap())
+						}
+						None => alternative,
+					};
+					match alternative["anyOf"]
+						.as_array()
+						.or(alternative["oneOf"].as_array())
+					{
+						Some(alternatives) => alternatives.clone(),
+						None => vec![alternative.clone()],
+					}
+				})
+				.filter(|form| form["type"] == "object")
+				.collect();
+			assert_eq!(forms.len(), schema.forms(name).len(), "{name}");
+			for form in forms {
+				let mut form = example(&schema, &form);
+				let mut recipe = base.clone();
+				place(&mut recipe, form.clone());
+				assert_eq!(check(&recipe), (true, true), "{recipe}");
+
+				form["unknown"] = json!(0);
+				let mut recipe = base.clone();
+				place(&mut recipe, form);
+				assert_eq!(check(&recipe), (false, false), "{recipe}");
+			}
+		}
+	}
+}
@@ -0,0 +1,232 @@
+//! How the entries of `Recipe::files` end up in the guest.
+//!
+//! Amba copies every file into the flat `hostfiles` directory of the session,
+//! since `s2ecmd get` fetches files by name only. Bootstrap then moves them to
+//! their possibly nested guest paths, creates the symlinks and sets the
+//! permissions given in the recipe.
+
+use std::fmt;
+
+use schemars::{
+	gen::SchemaGenerator,
+	schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation},
+	JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{validate::remove_dotslash, FileSource, Recipe};
+
+/// The Linux limit on symlinks followed while resolving a path.
+const MAX_SYMLINKS: usize = 40;
+
+/// Unix permissions of a guest file, written as up to four octal digits such
+/// as `"755"`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct FileMode(pub u32);
+
+impl FileMode {
+	pub fn is_executable(self) -> bool {
+		self.0 & 0o111 != 0
+	}
+}
+
+impl TryFrom<String> for FileMode {
+	type Error = String;
+
+	fn try_from(mode: String) -> Result<Self, String> {
+		match u32::from_str_radix(&mode, 8) {
+			Ok(bits) if !mode.starts_with('+') && bits <= 0o7777 => Ok(Self(bits)),
+			_ => Err(format!(
+				"invalid file mode `{mode}`, expected up to four octal digits such as \"755\""
+			)),
+		}
+	}
+}
+
+impl From<FileMode> for String {
+	fn from(mode: FileMode) -> Self {
+		mode.to_string()
+	}
+}
+
+impl JsonSchema for FileMode {
+	fn schema_name() -> String {
+		"FileMode".to_owned()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		SchemaObject {
+			metadata: Some(Box::new(Metadata {
+				description: Some(
+					"Unix permissions of a guest file, written as up to four octal digits such as `\"755\"`."
+						.to_owned(),
+				),
+				..Default::default()
+			})),
+			instance_type: Some(InstanceType::String.into()),
+			string: Some(Box::new(StringValidation {
+				pattern: Some("^[0-7]{1,4}$".to_owned()),
+				..Default::default()
+			})),
+			..Default::default()
+		}
+		.into()
+	}
+}
+
+impl fmt::Display for FileMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:03o}", self.0)
+	}
+}
+
+/// The name within `hostfiles` of the file at the relative `guest_path`.
+/// Files directly in the guest working directory keep their name, while the
+/// `/` of nested paths are escaped as `%2F`.
+pub fn host_file_name(guest_path: &str) -> String {
+	remove_dotslash(guest_path)
+		.replace('%', "%25")
+		.replace('/', "%2F")
+}
+
+impl Recipe {
+	/// The entry of `files` that the relative guest path `guest_path` refers
+	/// to, together with its normalized guest path. Symlinks to other entries
+	/// of `files` are followed, but only as the last component of a path.
+	/// Returns `None` for paths outside of `files`, including those that a
+	/// symlink leads to, and for symlink loops.
+	pub fn guest_file(&self, guest_path: &str) -> Option<(String, &FileSource)> {
+		let mut path = normalize(guest_path)?;
+		for _ in 0..MAX_SYMLINKS {
+			let source = self
+				.files
+				.iter()
+				.find(|(other, _)| normalize(other).as_ref() == Some(&path))
+				.map(|(_, source)| source)?;
+			let FileSource::Symlink { symlink } = source else {
+				return Some((path, source));
+			};
+			if symlink.starts_with('/') {
+				return None;
+			}
+			path = match path.rsplit_once('/') {
+				Some((dir, _)) => normalize(&format!("{dir}/{symlink}"))?,
+				None => normalize(symlink)?,
+			};
+		}
+		None
+	}
+
+	/// The key within `files` of the symbolic file that the relative
+	/// `guest_path` refers to, following symlinks like
+	/// [`Recipe::guest_file`].
+	pub fn symbolic_file(&self, guest_path: &str) -> Option<&str> {
+		let (target, _) = self.guest_file(guest_path)?;
+		self.symbolic_files()
+			.find(|other| normalize(other).as_ref() == Some(&target))
+	}
+
+	/// The key within `files` of the symbolic file that is stdin of the
+	/// analyzed program, or else of the only symbolic file.
+	pub fn default_symbolic_file(&self) -> Option<&str> {
+		let stdin = self.stdin_path.strip_prefix("/tmp/");
+		if let Some(stdin) = self.symbolic_files().find(|&other| Some(other) == stdin) {
+			return Some(stdin);
+		}
+		let mut symbolic = self.symbolic_files();
+		match (symbolic.next(), symbolic.next()) {
+			(Some(only), None) => Some(only),
+			_ => None,
+		}
+	}
+
+	/// The keys within `files` of the symbolic files.
+	pub fn symbolic_files(&self) -> impl Iterator<Item = &str> {
+		self.files
+			.iter()
+			.filter(|(_, source)| {
+				matches!(
+					source,
+					FileSource::SymbolicContent { .. } | FileSource::SymbolicHost { .. }
+				)
+			})
+			.map(|(guest_path, _)| &**guest_path)
+	}
+}
+
+/// Lexically resolve `.`, `..` and repeated `/` in the relative `guest_path`,
+/// or return `None` if it is absolute or leaves the guest working directory.
+fn normalize(guest_path: &str) -> Option<String> {
+	if guest_path.starts_with('/') {
+		return None;
+	}
+	let mut components = Vec::new();
+	for component in guest_path.split('/') {
+		match component {
+			"" | "." => {}
+			".." => {
+				components.pop()?;
+			}
+			component => components.push(component),
+		}
+	}
+	Some(components.join("/"))
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{host_file_name, FileMode, FileSource, Recipe};
+
+	#[test]
+	fn file_modes() {
+		let mode = |mode: &str| FileMode::try_from(mode.to_owned());
+		assert_eq!(mode("755"), Ok(FileMode(0o755)));
+		assert_eq!(mode("0444"), Ok(FileMode(0o444)));
+		assert!(mode("789").is_err());
+		assert!(mode("17777").is_err());
+		assert!(mode("+7").is_err());
+		assert_eq!(FileMode(0o44).to_string(), "044");
+	}
+
+	#[test]
+	fn host_file_names() {
+		assert_eq!(host_file_name("./prog"), "prog");
+		assert_eq!(host_file_name("conf/app.ini"), "conf%2Fapp.ini");
+		assert_eq!(host_file_name("100%/a"), "100%25%2Fa");
+	}
+
+	#[test]
+	fn follows_symlinks() {
+		let recipe = Recipe::deserialize_from(
+			br#"{
+				"files": {
+					"bin/prog": "./prog",
+					"prog": { "symlink": "bin/prog" },
+					"bin/alias": { "symlink": "../prog" },
+					"loop": { "symlink": "./loop" },
+					"outside": { "symlink": "/bin/true" }
+				},
+				"executable_path": "./bin/alias",
+				"stdin_path": "/dev/null"
+			}"#,
+		)
+		.unwrap();
+		let guest_file = |path| {
+			recipe
+				.guest_file(path)
+				.map(|(path, source)| (path, matches!(source, FileSource::Host(_))))
+		};
+		assert_eq!(
+			guest_file("./bin/alias"),
+			Some(("bin/prog".to_owned(), true))
+		);
+		assert_eq!(
+			guest_file("prog"),
+			Some(("bin/prog".to_owned(), true))
+		);
+		assert_eq!(guest_file("loop"), None);
+		assert_eq!(guest_file("outside"), None);
+		assert_eq!(guest_file("missing"), None);
+	}
+}
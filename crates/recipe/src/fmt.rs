@@ -0,0 +1,210 @@
+//! Writing recipes back out in canonical form, for `amba recipe fmt`.
+
+use std::fmt::Write;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Recipe, RecipeFormat, SymbolicRange};
+
+impl Recipe {
+	/// Serialize into the canonical form of `format`, with every field
+	/// present. Symbolic ranges are written as they are, so this should be
+	/// given a recipe from [`Recipe::deserialize_as_written`] to keep the
+	/// forms the user wrote. TOML has no `null`, so `SymbolicRange::Begin` is
+	/// written as the equivalent `SymbolicRange::Span` there.
+	pub fn serialize_to_format(mut self, format: RecipeFormat) -> String {
+		match format {
+			RecipeFormat::Json => {
+				let mut bytes = Vec::new();
+				let formatter = serde_json::ser::PrettyFormatter::with_indent(b"\t");
+				let mut serializer = serde_json::Serializer::with_formatter(&mut bytes, formatter);
+				self.serialize(&mut serializer).unwrap();
+				bytes.push(b'\n');
+				String::from_utf8(bytes).unwrap()
+			}
+			RecipeFormat::Toml => {
+				for ranges in self.symbolic_ranges_mut() {
+					ranges.iter_mut().for_each(SymbolicRange::begin_to_span);
+				}
+				to_toml(&serde_json::to_value(&self).unwrap())
+			}
+			RecipeFormat::Yaml => serde_yaml::to_string(&self).unwrap(),
+		}
+	}
+}
+
+impl RecipeFormat {
+	/// Whether the recipe `source` has comments, which
+	/// [`Recipe::serialize_to_format`] cannot keep. This errs towards finding
+	/// comments, such as a `#` in a YAML block scalar.
+	pub fn has_comments(self, source: &str) -> bool {
+		match self {
+			Self::Json => false,
+			Self::Toml => toml_has_comments(source),
+			Self::Yaml => yaml_has_comments(source),
+		}
+	}
+}
+
+/// Whether `source` has a `#` outside of strings.
+fn toml_has_comments(source: &str) -> bool {
+	let mut rest = source;
+	while let Some(c) = rest.chars().next() {
+		let (quote, escapes) = match c {
+			'#' => return true,
+			'"' => ("\"", true),
+			'\'' => ("'", false),
+			_ => {
+				rest = &rest[c.len_utf8()..];
+				continue;
+			}
+		};
+		let quote = match rest.starts_with(&quote.repeat(3)) {
+			true => quote.repeat(3),
+			false => quote.to_owned(),
+		};
+		rest = &rest[quote.len()..];
+		loop {
+			match rest.chars().next() {
+				None => return false,
+				Some(_) if rest.starts_with(&quote) => {
+					rest = &rest[quote.len()..];
+					break;
+				}
+				Some('\\') if escapes => {
+					let mut chars = rest.chars();
+					chars.nth(1);
+					rest = chars.as_str();
+				}
+				Some(c) => rest = &rest[c.len_utf8()..],
+			}
+		}
+	}
+	false
+}
+
+/// Whether `source` has a `#` that starts a line or follows whitespace,
+/// outside of quoted scalars.
+fn yaml_has_comments(source: &str) -> bool {
+	let mut chars = source.chars().peekable();
+	let mut previous = '\n';
+	while let Some(c) = chars.next() {
+		match c {
+			'#' if previous.is_whitespace() => return true,
+			'"' | '\'' if previous.is_whitespace() || "[{,".contains(previous) => {
+				while let Some(quoted) = chars.next() {
+					match quoted {
+						'\\' if c == '"' => {
+							chars.next();
+						}
+						// `''` escapes a quote in single-quoted scalars
+						'\'' if c == '\'' && chars.peek() == Some(&'\'') => {
+							chars.next();
+						}
+						_ if quoted == c => break,
+						_ => {}
+					}
+				}
+			}
+			_ => {}
+		}
+		previous = c;
+	}
+	false
+}
+
+/// Write a recipe as TOML, with a section per top-level table and everything
+/// below that inline. The `toml` serializer cannot write arrays that mix
+/// tables with other values, such as `arguments`.
+fn to_toml(recipe: &Value) -> String {
+	let Value::Object(recipe) = recipe else {
+		unreachable!("recipes are objects");
+	};
+	let mut ret = String::new();
+	for (key, value) in recipe {
+		if !matches!(value, Value::Object(_) | Value::Null) {
+			writeln!(ret, "{} = {}", toml_key(key), toml_inline(value)).unwrap();
+		}
+	}
+	for (key, value) in recipe {
+		let Value::Object(table) = value else {
+			continue;
+		};
+		write!(ret, "\n[{}]\n", toml_key(key)).unwrap();
+		for (key, value) in table {
+			if !value.is_null() {
+				writeln!(ret, "{} = {}", toml_key(key), toml_inline(value)).unwrap();
+			}
+		}
+	}
+	ret
+}
+
+fn toml_inline(value: &Value) -> String {
+	match value {
+		Value::Null => unreachable!("`null` is skipped in tables and rewritten in arrays"),
+		// JSON string escapes are a subset of TOML basic string escapes
+		Value::Bool(_) | Value::Number(_) | Value::String(_) => value.to_string(),
+		Value::Array(values) => {
+			let values: Vec<String> = values.iter().map(toml_inline).collect();
+			format!("[{}]", values.join(", "))
+		}
+		Value::Object(table) if table.is_empty() => "{}".to_owned(),
+		Value::Object(table) => {
+			let entries: Vec<String> = table
+				.iter()
+				.filter(|(_, value)| !value.is_null())
+				.map(|(key, value)| format!("{} = {}", toml_key(key), toml_inline(value)))
+				.collect();
+			format!("{{ {} }}", entries.join(", "))
+		}
+	}
+}
+
+fn toml_key(key: &str) -> String {
+	let bare = !key.is_empty()
+		&& key
+			.bytes()
+			.all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-');
+	match bare {
+		true => key.to_owned(),
+		false => Value::from(key).to_string(),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::RecipeFormat;
+
+	#[test]
+	fn finds_comments() {
+		for (format, source, has_comments) in [
+			(RecipeFormat::Json, r##"{ "a": "#" }"##, false),
+			(RecipeFormat::Toml, "a = 1 # one", true),
+			(RecipeFormat::Toml, "# a\na = 1", true),
+			(RecipeFormat::Toml, r##"a = "#\"#""##, false),
+			(
+				RecipeFormat::Toml,
+				"a = \"\"\"\n#\n\"\"\"\nb = '''#'''",
+				false,
+			),
+			(RecipeFormat::Yaml, "a: 1 # one", true),
+			(RecipeFormat::Yaml, "# a\na: 1", true),
+			(RecipeFormat::Yaml, "a: b#c", false),
+			(
+				RecipeFormat::Yaml,
+				"a: 'it''s # not'\nb: \"\\\" #\"",
+				false,
+			),
+			(RecipeFormat::Yaml, "a: ['#', \"#\"] # one", true),
+			(RecipeFormat::Yaml, "a: it's # one", true),
+		] {
+			assert_eq!(
+				format.has_comments(source),
+				has_comments,
+				"{format:?}: {source}"
+			);
+		}
+	}
+}
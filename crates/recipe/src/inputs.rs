@@ -0,0 +1,713 @@
+//! Naming of the symbolic inputs that bootstrap creates within the guest.
+//!
+//! Bootstrap makes everything symbolic through `s2ecmd symbfile`, which names
+//! each symbolic variable after the (mangled) path of its file. Arguments and
+//! environment variables are therefore backed by files with recognizable
+//! names, so that the host can tell which recipe input a concrete input
+//! belongs to. The same goes for the symbolic lengths of variable-length
+//! files and arguments, and for constrained symbolic ranges, which the plugin
+//! recognizes in order to constrain their bytes.
+
+use std::collections::BTreeMap;
+
+use crate::{
+	diagnostic::Segment, seeds, ArgumentSource, ByteConstraint, EnvVarSource, FileSource,
+	InputLayout, Recipe, SymbolicRange,
+};
+
+const ARGUMENT_MARKER: &str = "amba_argv_";
+const ENVIRONMENT_MARKER: &str = "amba_envp_";
+const ARGUMENT_LENGTH_MARKER: &str = "amba_arglen_";
+const FILE_LENGTH_MARKER: &str = "amba_filelen_";
+const CONSTRAINED_MARKER: &str = "amba_constrained_";
+
+/// The guest file backing the symbolic argument `argv[index]`.
+pub fn symbolic_argument_guest_path(index: usize) -> String {
+	format!("/tmp/{ARGUMENT_MARKER}{index}")
+}
+
+/// The guest file backing the symbolic environment variable at `index` within
+/// `Environment::add`.
+pub fn symbolic_environment_guest_path(index: usize) -> String {
+	format!("/tmp/{ENVIRONMENT_MARKER}{index}")
+}
+
+/// The guest file backing the symbolic length of the variable-length argument
+/// `argv[index]`.
+pub fn symbolic_argument_length_guest_path(index: usize) -> String {
+	format!("/tmp/{ARGUMENT_LENGTH_MARKER}{index}")
+}
+
+/// The guest file backing the symbolic length of the variable-length file at
+/// `index` within `Recipe::files`.
+pub fn symbolic_file_length_guest_path(index: usize) -> String {
+	format!("/tmp/{FILE_LENGTH_MARKER}{index}")
+}
+
+/// The guest file backing the constrained symbolic range at `index` within
+/// `Recipe::constrained_ranges`. Bootstrap copies the bytes of the range into
+/// it, makes it symbolic and copies the bytes back.
+pub fn symbolic_constrained_guest_path(index: usize) -> String {
+	format!("/tmp/{CONSTRAINED_MARKER}{index}")
+}
+
+/// A symbolic range carrying a [`ByteConstraint`], see
+/// [`Recipe::constrained_ranges`].
+#[derive(Debug)]
+pub struct ConstrainedRange<'a> {
+	/// A human readable name of the input, such as `input.txt` or `argv[1]`
+	pub input: String,
+	target: Input,
+	pub range: &'a SymbolicRange,
+	/// Whether the input is passed to the analyzed program as a NUL-terminated
+	/// string, in which case its bytes must be nonzero as well.
+	pub nul_terminated: bool,
+}
+
+/// An input that bootstrap passes to the analyzed program.
+#[derive(Debug, Clone)]
+enum Input {
+	/// The symbolic copy of the file at a guest path
+	File(String),
+	/// `argv[index]`
+	Argument(usize),
+	/// A variable of `Environment::add`
+	Environment(String),
+}
+
+/// The inputs that bootstrap passed to the analyzed program in some state, see
+/// [`Recipe::concrete_inputs`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConcreteInputs {
+	/// The contents of the symbolic copies of symbolic files, by guest path
+	pub files: BTreeMap<String, Vec<u8>>,
+	/// `argv[1..]`
+	pub arguments: Vec<Vec<u8>>,
+	/// Every variable of `Environment::add`
+	pub environment: BTreeMap<String, Vec<u8>>,
+	/// The symbolic variables that could not be traced back to the recipe
+	pub unknown: Vec<String>,
+}
+
+impl ConcreteInputs {
+	fn get_mut(&mut self, input: &Input) -> Option<&mut Vec<u8>> {
+		match input {
+			Input::File(guest_path) => self.files.get_mut(guest_path),
+			Input::Argument(index) => self.arguments.get_mut(index.checked_sub(1)?),
+			Input::Environment(key) => self.environment.get_mut(key),
+		}
+	}
+}
+
+impl ConstrainedRange<'_> {
+	pub fn constraint(&self) -> &ByteConstraint {
+		self.range.constraint().unwrap()
+	}
+
+	/// Where the ranges of the input are written in the recipe.
+	pub(crate) fn recipe_path(&self) -> Vec<Segment> {
+		let key = |key: &str| Segment::Key(key.to_owned());
+		let mut path = match &self.target {
+			Input::File(guest_path) => vec![key("files"), key(guest_path)],
+			Input::Argument(index) => vec![key("arguments"), Segment::Index(index - 1)],
+			Input::Environment(name) => vec![key("environment"), key("add"), key(name)],
+		};
+		path.push(key("symbolic"));
+		path
+	}
+
+	/// The inclusive byte ranges that the plugin should allow.
+	pub fn allowed(&self) -> Vec<[u8; 2]> {
+		let mut allowed = self.constraint().allowed();
+		if self.nul_terminated {
+			allowed.retain(|&[_, hi]| hi != 0);
+			if let Some([lo, _]) = allowed.first_mut() {
+				*lo = (*lo).max(1);
+			}
+		}
+		allowed
+	}
+
+	/// A human readable name of the range, such as `input.txt[2..4]`.
+	pub fn name(&self) -> String {
+		let [start, end] = self.range.range();
+		match end {
+			u64::MAX => format!("{}[{start}..]", self.input),
+			end => format!("{}[{start}..{end}]", self.input),
+		}
+	}
+}
+
+impl Recipe {
+	/// Every constrained symbolic range of the recipe, in the order in which
+	/// bootstrap makes them symbolic: those of `files`, then those of
+	/// `arguments` and finally those of `environment.add`.
+	pub fn constrained_ranges(&self) -> Vec<ConstrainedRange<'_>> {
+		let files = self
+			.files
+			.iter()
+			.filter_map(|(guest_path, source)| match source {
+				FileSource::Host(_)
+				| FileSource::HostWithMode { .. }
+				| FileSource::Symlink { .. } => None,
+				FileSource::SymbolicContent { symbolic, .. }
+				| FileSource::SymbolicHost { symbolic, .. } => Some((
+					guest_path.clone(),
+					Input::File(guest_path.clone()),
+					symbolic,
+					false,
+				)),
+			});
+		let arguments =
+			self.arguments
+				.iter()
+				.enumerate()
+				.filter_map(|(i, argument)| match argument {
+					ArgumentSource::Concrete(_) => None,
+					ArgumentSource::Symbolic { symbolic, .. } => Some((
+						format!("argv[{}]", i + 1),
+						Input::Argument(i + 1),
+						symbolic,
+						true,
+					)),
+				});
+		let environment = self
+			.environment
+			.add
+			.iter()
+			.filter_map(|(key, value)| match value {
+				EnvVarSource::Concrete(_) => None,
+				EnvVarSource::Symbolic { symbolic, .. } => Some((
+					format!("envp[{key}]"),
+					Input::Environment(key.clone()),
+					symbolic,
+					true,
+				)),
+			});
+		files
+			.chain(arguments)
+			.chain(environment)
+			.flat_map(|(input, target, symbolic, nul_terminated)| {
+				symbolic
+					.iter()
+					.filter(|range| range.constraint().is_some())
+					.map(move |range| ConstrainedRange {
+						input: input.clone(),
+						target: target.clone(),
+						range,
+						nul_terminated,
+					})
+			})
+			.collect()
+	}
+
+	/// Rebuild the inputs that bootstrap passed to the analyzed program in a
+	/// state from the concrete values of its S2E symbolic `variables`, named
+	/// as the plugin reports them rather than as
+	/// [`Recipe::describe_concrete_input`] names them. Every input starts out
+	/// as its seed, with `file_seed` giving the seeds of symbolic files by
+	/// guest path, so bytes that no variable covers keep their seed value.
+	pub fn concrete_inputs(
+		&self,
+		mut file_seed: impl FnMut(&str) -> Vec<u8>,
+		variables: &[(String, Vec<u8>)],
+	) -> ConcreteInputs {
+		// Bootstrap pads variable-length inputs with zeros up to `max_length`
+		let padded = |mut seed: Vec<u8>, max_length: Option<u64>| {
+			if let Some(max_length) = max_length {
+				seed.resize(max_length as usize, 0);
+			}
+			seed
+		};
+		let mut ret = ConcreteInputs {
+			files: self
+				.files
+				.iter()
+				.filter(|(_, source)| {
+					matches!(
+						source,
+						FileSource::SymbolicContent { .. } | FileSource::SymbolicHost { .. }
+					)
+				})
+				.map(|(guest_path, source)| {
+					(
+						guest_path.clone(),
+						padded(file_seed(guest_path), source.max_length()),
+					)
+				})
+				.collect(),
+			arguments: self
+				.arguments
+				.iter()
+				.map(|argument| match argument {
+					ArgumentSource::Concrete(value) => value.clone().into_bytes(),
+					ArgumentSource::Symbolic {
+						seed, max_length, ..
+					} => padded(seed.clone().into_bytes(), *max_length),
+				})
+				.collect(),
+			environment: self
+				.environment
+				.add
+				.iter()
+				.map(|(key, value)| match value {
+					EnvVarSource::Concrete(value) | EnvVarSource::Symbolic { value, .. } => {
+						(key.clone(), value.clone().into_bytes())
+					}
+				})
+				.collect(),
+			unknown: Vec::new(),
+		};
+
+		// Bootstrap copies constrained ranges back over the contents and
+		// truncates to the symbolic length last, so they are applied last too.
+		let mut constrained = Vec::new();
+		let mut lengths = Vec::new();
+		for (variable, value) in variables {
+			let contents = if seeds::seed_marker_index(variable).is_some() {
+				continue;
+			} else if let Some(index) = parse_index_after(variable, CONSTRAINED_MARKER) {
+				constrained.push((index, value));
+				continue;
+			} else if let Some(index) = parse_index_after(variable, ARGUMENT_LENGTH_MARKER) {
+				lengths.push((Input::Argument(index), decode_length(value)));
+				continue;
+			} else if let Some(index) = parse_index_after(variable, FILE_LENGTH_MARKER) {
+				match self.files.keys().nth(index) {
+					Some(guest_path) => {
+						lengths.push((
+							Input::File(guest_path.clone()),
+							decode_length(value),
+						));
+					}
+					None => ret.unknown.push(variable.clone()),
+				}
+				continue;
+			} else if let Some(index) = parse_index_after(variable, ARGUMENT_MARKER) {
+				Some(Input::Argument(index))
+			} else if let Some(index) = parse_index_after(variable, ENVIRONMENT_MARKER) {
+				self.environment
+					.add
+					.keys()
+					.nth(index)
+					.map(|key| Input::Environment(key.clone()))
+			} else {
+				self.symbolic_file_named(variable)
+					.map(|(guest_path, _)| Input::File(guest_path.to_owned()))
+			};
+			match contents
+				.zip(symbfile_offset(variable))
+				.and_then(|(input, offset)| Some((ret.get_mut(&input)?, offset)))
+			{
+				Some((contents, offset)) => write_at(contents, offset, value),
+				None => ret.unknown.push(variable.clone()),
+			}
+		}
+		let constrained_ranges = self.constrained_ranges();
+		for (index, value) in constrained {
+			match constrained_ranges.get(index) {
+				Some(range) => write_at(
+					ret.get_mut(&range.target).unwrap(),
+					range.range.start(),
+					value,
+				),
+				None => ret.unknown.push(format!("{CONSTRAINED_MARKER}{index}")),
+			}
+		}
+		for (input, length) in lengths {
+			if let Some(contents) = ret.get_mut(&input) {
+				contents.truncate(length.try_into().unwrap_or(usize::MAX));
+			}
+		}
+		ret
+	}
+
+	/// A human readable name and value for the concrete input `value` of the
+	/// S2E symbolic variable `variable`, if it can be traced back to the
+	/// recipe. The contents of a symbolic file are named after its guest path.
+	/// Symbolic lengths are decoded into the decimal length that was actually
+	/// used.
+	pub fn describe_concrete_input(
+		&self,
+		variable: &str,
+		value: &[u8],
+	) -> Option<(String, Vec<u8>)> {
+		if let Some((name, length)) = self.concrete_input_length(variable, value) {
+			return Some((name, length.to_string().into_bytes()));
+		}
+		Some((
+			self.concrete_input_value_name(variable)?,
+			value.to_owned(),
+		))
+	}
+
+	/// The layouts of the symbolic files that have one, keyed by the name
+	/// that [`Recipe::describe_concrete_input`] gives their contents.
+	pub fn input_layouts(&self) -> BTreeMap<String, InputLayout> {
+		self.files
+			.iter()
+			.filter_map(|(guest_path, source)| Some((guest_path.clone(), source.layout()?.clone())))
+			.collect()
+	}
+
+	/// The guest path of the symbolic file whose contents `variable` was
+	/// created from. S2E mangles the path into the variable name, so the
+	/// longest guest path whose mangled form occurs in it wins. Only files made
+	/// symbolic as a single symbolic variable are named.
+	fn symbolic_file_of(&self, variable: &str) -> Option<&str> {
+		self.symbolic_file_named(variable)
+			.filter(|(_, symbolic)| {
+				symbolic
+					.iter()
+					.filter(|range| range.constraint().is_none())
+					.count() <= 1
+			})
+			.map(|(guest_path, _)| guest_path)
+	}
+
+	/// The guest path and symbolic ranges of the symbolic file that `variable`
+	/// was created from, by the longest guest path whose mangled form occurs
+	/// in it.
+	fn symbolic_file_named(&self, variable: &str) -> Option<(&str, &[SymbolicRange])> {
+		let mangle = |name: &str| -> String {
+			name.chars()
+				.map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+				.collect()
+		};
+		let variable = mangle(variable);
+		self.files
+			.iter()
+			.filter_map(|(guest_path, source)| match source {
+				FileSource::Host(_)
+				| FileSource::HostWithMode { .. }
+				| FileSource::Symlink { .. } => None,
+				FileSource::SymbolicContent { symbolic, .. }
+				| FileSource::SymbolicHost { symbolic, .. } => Some((&**guest_path, &**symbolic)),
+			})
+			.filter(|(guest_path, _)| variable.contains(&mangle(&format!("/tmp/{guest_path}"))))
+			.max_by_key(|(guest_path, _)| guest_path.len())
+	}
+
+	fn concrete_input_value_name(&self, variable: &str) -> Option<String> {
+		if let Some(index) = parse_index_after(variable, CONSTRAINED_MARKER) {
+			return self
+				.constrained_ranges()
+				.get(index)
+				.map(ConstrainedRange::name);
+		}
+		if let Some(index) = parse_index_after(variable, ARGUMENT_MARKER) {
+			return (1..=self.arguments.len())
+				.contains(&index)
+				.then(|| format!("argv[{index}]"));
+		}
+		if let Some(index) = parse_index_after(variable, ENVIRONMENT_MARKER) {
+			let key = self.environment.add.keys().nth(index)?;
+			return Some(format!("envp[{key}]"));
+		}
+		self.symbolic_file_of(variable).map(str::to_owned)
+	}
+
+	/// The name of a symbolic length together with the length that bootstrap
+	/// used for the little-endian `value` of it.
+	fn concrete_input_length(&self, variable: &str, value: &[u8]) -> Option<(String, u64)> {
+		let (name, max_length) =
+			if let Some(index) = parse_index_after(variable, ARGUMENT_LENGTH_MARKER) {
+				let max_length = self.arguments.get(index.checked_sub(1)?)?.max_length()?;
+				(format!("len(argv[{index}])"), max_length)
+			} else {
+				let index = parse_index_after(variable, FILE_LENGTH_MARKER)?;
+				let (guest_path, source) = self.files.iter().nth(index)?;
+				(format!("len({guest_path})"), source.max_length()?)
+			};
+		Some((name, decode_length(value).min(max_length)))
+	}
+}
+
+/// Decode the little-endian value of a symbolic length.
+fn decode_length(value: &[u8]) -> u64 {
+	let mut bytes = [0; 8];
+	let len = value.len().min(8);
+	bytes[..len].copy_from_slice(&value[..len]);
+	u64::from_le_bytes(bytes)
+}
+
+/// The offset within its file of the range that `s2ecmd symbfile` made the
+/// symbolic variable `variable` from. S2E names such variables
+/// `..._<offset>_<length>_symfile___<n>`.
+fn symbfile_offset(variable: &str) -> Option<u64> {
+	let (name, _) = variable.rsplit_once("_symfile___")?;
+	let mut parts = name.rsplitn(3, '_');
+	let _length = parts.next()?;
+	parts.next()?.parse().ok()
+}
+
+/// Overwrite the bytes of `contents` from `offset` on with `value`, as far as
+/// `contents` reaches.
+fn write_at(contents: &mut [u8], offset: u64, value: &[u8]) {
+	let start = usize::try_from(offset).map_or(contents.len(), |offset| {
+		offset.min(contents.len())
+	});
+	let len = value.len().min(contents.len() - start);
+	contents[start..start + len].copy_from_slice(&value[..len]);
+}
+
+/// Parse the decimal number directly following the first occurrence of
+/// `marker` within `variable`.
+pub(crate) fn parse_index_after(variable: &str, marker: &str) -> Option<usize> {
+	let (_, rest) = variable.split_once(marker)?;
+	let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+	rest[..digits].parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+	use crate::Recipe;
+
+	#[test]
+	fn input_names() {
+		let recipe = Recipe::deserialize_from(
+			br#"{
+				"files": {},
+				"executable_path": "/bin/true",
+				"stdin_path": "/dev/null",
+				"arguments": ["-v", { "seed": "aa", "symbolic": [0] }],
+				"environment": {
+					"inherit": false,
+					"add": {
+						"LANG": "C",
+						"TERM": { "value": "xterm", "symbolic": [[0, null]] }
+					}
+				}
+			}"#,
+		)
+		.unwrap();
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v1___symfile____tmp_amba_argv_2_0_1_symfile___1",
+				b"v"
+			),
+			Some(("argv[2]".to_owned(), b"v".to_vec()))
+		);
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v0___symfile____tmp_amba_argv_12_0_1_symfile___0",
+				b"v"
+			),
+			None
+		);
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v2___symfile____tmp_amba_envp_1_0_5_symfile___2",
+				b"v"
+			),
+			Some(("envp[TERM]".to_owned(), b"v".to_vec()))
+		);
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v0___symfile____tmp_input.txt_0_1_symfile___0",
+				b"v"
+			),
+			None
+		);
+	}
+
+	#[test]
+	fn constrained_ranges() {
+		let recipe = Recipe::deserialize_from(
+			br#"{
+				"files": {
+					"input.txt": {
+						"seed": "aaaa",
+						"symbolic": [0, { "range": [2, null], "constraint": "printable" }]
+					}
+				},
+				"executable_path": "/bin/true",
+				"stdin_path": "/tmp/input.txt",
+				"arguments": [{
+					"seed": "x",
+					"symbolic": [{ "range": 0, "constraint": { "in_range": [0, 9] } }]
+				}]
+			}"#,
+		)
+		.unwrap();
+		let ranges = recipe.constrained_ranges();
+		assert_eq!(ranges.len(), 2);
+		assert_eq!(ranges[0].allowed(), [[0x20, 0x7E]]);
+		assert_eq!(ranges[1].allowed(), [[1, 9]]);
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v1___symfile____tmp_amba_constrained_0_0_2_symfile___1",
+				b"~~"
+			),
+			Some(("input.txt[2..]".to_owned(), b"~~".to_vec()))
+		);
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v2___symfile____tmp_amba_constrained_1_0_1_symfile___2",
+				b"\x05"
+			),
+			Some(("argv[1][0..1]".to_owned(), b"\x05".to_vec()))
+		);
+	}
+
+	#[test]
+	fn file_contents() {
+		let recipe = Recipe::deserialize_from(
+			br#"{
+				"files": {
+					"a.txt": { "seed": "aa" },
+					"a.txt.2": { "seed": "aa", "layout": [{ "name": "x", "type": "u16_le" }] },
+					"split": { "seed": "aaaa", "symbolic": [0, 2] }
+				},
+				"executable_path": "/bin/true",
+				"stdin_path": "/tmp/a.txt"
+			}"#,
+		)
+		.unwrap();
+		let name = |variable| {
+			recipe
+				.describe_concrete_input(variable, b"v")
+				.map(|(name, _)| name)
+		};
+		assert_eq!(
+			name("v0___symfile____tmp_a.txt_0_2_symfile___0"),
+			Some("a.txt".to_owned())
+		);
+		assert_eq!(
+			name("v1___symfile____tmp_a_txt_2_0_2_symfile___1"),
+			Some("a.txt.2".to_owned())
+		);
+		assert_eq!(
+			name("v2___symfile____tmp_split_0_1_symfile___2"),
+			None
+		);
+		assert_eq!(
+			recipe.input_layouts().keys().collect::<Vec<_>>(),
+			["a.txt.2"]
+		);
+	}
+
+	#[test]
+	fn input_lengths() {
+		let recipe = Recipe::deserialize_from(
+			br#"{
+				"files": {
+					"a.txt": { "seed": "aa" },
+					"b.txt": { "seed": "bb", "max_length": 6 }
+				},
+				"executable_path": "/bin/true",
+				"stdin_path": "/tmp/b.txt",
+				"arguments": [{ "seed": "x", "symbolic": [0], "max_length": 3 }]
+			}"#,
+		)
+		.unwrap();
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v0___symfile____tmp_amba_arglen_1_0_8_symfile___0",
+				&[2, 0, 0, 0, 0, 0, 0, 0]
+			),
+			Some(("len(argv[1])".to_owned(), b"2".to_vec()))
+		);
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v1___symfile____tmp_amba_filelen_1_0_8_symfile___1",
+				&[0, 1, 0, 0, 0, 0, 0, 0]
+			),
+			Some(("len(b.txt)".to_owned(), b"6".to_vec()))
+		);
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v2___symfile____tmp_amba_filelen_0_0_8_symfile___2",
+				&[1, 0, 0, 0, 0, 0, 0, 0]
+			),
+			None
+		);
+		assert_eq!(
+			recipe.describe_concrete_input(
+				"v3___symfile____tmp_amba_argv_1_0_3_symfile___3",
+				b"xyz"
+			),
+			Some(("argv[1]".to_owned(), b"xyz".to_vec()))
+		);
+	}
+
+	#[test]
+	fn rebuilds_concrete_inputs() {
+		let recipe = Recipe::deserialize_from(
+			br#"{
+				"files": {
+					"prog": "./prog",
+					"split": {
+						"seed": "abcdef",
+						"symbolic": [0, [4, 6], { "range": 2, "constraint": "digit" }]
+					},
+					"var": { "seed": "xy", "max_length": 4 }
+				},
+				"executable_path": "./prog",
+				"stdin_path": "/tmp/split",
+				"arguments": ["-v", { "seed": "ab", "symbolic": [1] }],
+				"environment": {
+					"inherit": false,
+					"add": { "A": "1", "B": { "value": "bb", "symbolic": [0] } }
+				}
+			}"#,
+		)
+		.unwrap();
+		let variables = [
+			(
+				"v0___symfile____tmp_split_0_1_symfile___0",
+				&b"A"[..],
+			),
+			("v1___symfile____tmp_split_4_2_symfile___1", b"EF"),
+			(
+				"v2___symfile____tmp_amba_constrained_0_0_1_symfile___2",
+				b"7",
+			),
+			("v3___symfile____tmp_var_0_4_symfile___3", b"wxyz"),
+			(
+				"v4___symfile____tmp_amba_filelen_2_0_8_symfile___4",
+				&[3, 0, 0, 0, 0, 0, 0, 0],
+			),
+			(
+				"v5___symfile____tmp_amba_argv_2_1_1_symfile___5",
+				b"Q",
+			),
+			(
+				"v6___symfile____tmp_amba_envp_1_0_1_symfile___6",
+				b"C",
+			),
+			("v7___symfile____tmp_other_0_1_symfile___7", b"?"),
+		];
+		let variables: Vec<_> = variables
+			.into_iter()
+			.map(|(name, value)| (name.to_owned(), value.to_vec()))
+			.collect();
+		let inputs = recipe.concrete_inputs(
+			|guest_path| {
+				recipe.files[guest_path]
+					.max_length()
+					.map_or(b"abcdef".to_vec(), |_| b"xy".to_vec())
+			},
+			&variables,
+		);
+		assert_eq!(
+			inputs.files.into_iter().collect::<Vec<_>>(),
+			[
+				("split".to_owned(), b"Ab7dEF".to_vec()),
+				("var".to_owned(), b"wxy".to_vec())
+			]
+		);
+		assert_eq!(inputs.arguments, [b"-v".to_vec(), b"aQ".to_vec()]);
+		assert_eq!(
+			inputs.environment.into_iter().collect::<Vec<_>>(),
+			[
+				("A".to_owned(), b"1".to_vec()),
+				("B".to_owned(), b"Cb".to_vec())
+			]
+		);
+		assert_eq!(
+			inputs.unknown,
+			["v7___symfile____tmp_other_0_1_symfile___7"]
+		);
+	}
+}
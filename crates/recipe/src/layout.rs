@@ -0,0 +1,171 @@
+//! Structured descriptions of symbolic files, used to decode their concrete
+//! inputs into readable field values.
+
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The layout of a symbolic file as a sequence of named fields, starting at
+/// the first byte of the file.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct InputLayout {
+	pub fields: Vec<LayoutField>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct LayoutField {
+	pub name: String,
+	#[serde(rename = "type")]
+	pub kind: FieldKind,
+	/// Display integers in hexadecimal rather than decimal
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub hex: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+	U8,
+	U16Le,
+	U16Be,
+	U32Le,
+	U32Be,
+	U64Le,
+	U64Be,
+	/// A string, displayed lossily as UTF-8
+	String(FieldLength),
+	/// Raw bytes, displayed with non-ASCII bytes escaped
+	Bytes(FieldLength),
+}
+
+/// The length of a `FieldKind::String` or `FieldKind::Bytes` field.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FieldLength {
+	Fixed(u64),
+	/// The value of an earlier integer field, for length-prefixed data
+	Field(String),
+}
+
+impl FieldKind {
+	/// The size and endianness of integer fields.
+	pub fn integer(&self) -> Option<(usize, Endian)> {
+		match self {
+			Self::U8 => Some((1, Endian::Little)),
+			Self::U16Le => Some((2, Endian::Little)),
+			Self::U16Be => Some((2, Endian::Big)),
+			Self::U32Le => Some((4, Endian::Little)),
+			Self::U32Be => Some((4, Endian::Big)),
+			Self::U64Le => Some((8, Endian::Little)),
+			Self::U64Be => Some((8, Endian::Big)),
+			Self::String(_) | Self::Bytes(_) => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+	Little,
+	Big,
+}
+
+impl InputLayout {
+	/// Decode `bytes` into space-separated `name=value` pairs, such as
+	/// `magic=0x7f454c46 len=3 name="abc"`. Fields that do not fit within
+	/// `bytes` are shown as `name=<eof>`, and trailing bytes beyond the layout
+	/// are counted.
+	pub fn decode(&self, bytes: &[u8]) -> String {
+		let mut integers: Vec<(&str, u64)> = Vec::new();
+		let mut rest = bytes;
+		let mut ret = String::new();
+		for field in &self.fields {
+			if !ret.is_empty() {
+				ret.push(' ');
+			}
+			write!(ret, "{}=", field.name).unwrap();
+
+			let len = match &field.kind {
+				FieldKind::String(FieldLength::Fixed(len))
+				| FieldKind::Bytes(FieldLength::Fixed(len)) => *len,
+				FieldKind::String(FieldLength::Field(name))
+				| FieldKind::Bytes(FieldLength::Field(name)) => {
+					let Some(&(_, len)) = integers.iter().rev().find(|(field, _)| field == name)
+					else {
+						ret.push_str("<unknown length>");
+						return ret;
+					};
+					len
+				}
+				kind => kind.integer().unwrap().0 as u64,
+			};
+			let Some(value) = usize::try_from(len).ok().and_then(|len| rest.get(..len)) else {
+				ret.push_str("<eof>");
+				return ret;
+			};
+			rest = &rest[value.len()..];
+
+			match &field.kind {
+				FieldKind::String(_) => {
+					write!(ret, "{:?}", String::from_utf8_lossy(value)).unwrap();
+				}
+				FieldKind::Bytes(_) => {
+					write!(ret, "b\"{}\"", value.escape_ascii()).unwrap();
+				}
+				kind => {
+					let (_, endian) = kind.integer().unwrap();
+					let mut integer = [0; 8];
+					match endian {
+						Endian::Little => integer[..value.len()].copy_from_slice(value),
+						Endian::Big => integer[8 - value.len()..].copy_from_slice(value),
+					}
+					let integer = match endian {
+						Endian::Little => u64::from_le_bytes(integer),
+						Endian::Big => u64::from_be_bytes(integer),
+					};
+					match field.hex {
+						true => write!(
+							ret,
+							"{integer:#0width$x}",
+							width = 2 + 2 * value.len()
+						),
+						false => write!(ret, "{integer}"),
+					}
+					.unwrap();
+					integers.push((&field.name, integer));
+				}
+			}
+		}
+		if !rest.is_empty() {
+			write!(ret, " +{} bytes", rest.len()).unwrap();
+		}
+		ret
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::layout::InputLayout;
+
+	#[test]
+	fn decode() {
+		let layout: InputLayout = serde_json::from_str(
+			r#"[
+				{ "name": "magic", "type": "u32_be", "hex": true },
+				{ "name": "len", "type": "u16_le" },
+				{ "name": "name", "type": { "string": "len" } },
+				{ "name": "tag", "type": { "bytes": 2 } }
+			]"#,
+		)
+		.unwrap();
+		assert_eq!(
+			layout.decode(b"\x7fELF\x03\x00abc\x00\xffxyz"),
+			r#"magic=0x7f454c46 len=3 name="abc" tag=b"\x00\xff" +3 bytes"#
+		);
+		assert_eq!(
+			layout.decode(b"\x7fELF\x09\x00abc"),
+			r#"magic=0x7f454c46 len=9 name=<eof>"#
+		);
+	}
+}
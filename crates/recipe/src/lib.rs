@@ -1,51 +1,177 @@
-use std::collections::BTreeMap;
+use std::{
+	collections::BTreeMap,
+	io,
+	path::{Path, PathBuf},
+};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::tagged::{ArgumentSourceForm, EnvVarSourceForm, FileSourceForm};
+pub use crate::{
+	budget::{parse_duration, Budget, BudgetDuration},
+	diagnostic::{Diagnostic, Location},
+	files::{host_file_name, FileMode},
+	inputs::{
+		symbolic_argument_guest_path, symbolic_argument_length_guest_path,
+		symbolic_constrained_guest_path, symbolic_environment_guest_path,
+		symbolic_file_length_guest_path, ConcreteInputs, ConstrainedRange,
+	},
+	layout::{Endian, FieldKind, FieldLength, InputLayout, LayoutField},
+	seeds::{
+		seed_file_index, seed_file_name, seed_marker_guest_path, seed_marker_index, seed_of, Seeds,
+	},
+	validate::Problem,
+};
+
+mod budget;
+mod diagnostic;
+mod files;
+mod fmt;
+mod inputs;
+mod layout;
+mod resolve;
+mod seeds;
+mod tagged;
+mod validate;
+
 type GuestPath = String;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Recipe {
+	#[schemars(with = "BTreeMap<GuestPath, FileSourceForm>")]
 	pub files: BTreeMap<GuestPath, FileSource>,
 	pub executable_path: String,
 	pub stdin_path: String,
 	pub arg0: Option<String>,
 	#[serde(default)]
+	#[schemars(with = "Vec<ArgumentSourceForm>")]
 	pub arguments: Vec<ArgumentSource>,
 	#[serde(default)]
 	pub environment: Environment,
+	/// Commands that bootstrap runs one after another in the guest working
+	/// directory before the analyzed program, once every file in `files` is
+	/// in place. They run concretely and are not traced, even if they run the
+	/// analyzed executable.
+	#[serde(default)]
+	pub setup: Vec<Hook>,
+	/// Like `setup`, but run after the analyzed program has exited
+	#[serde(default)]
+	pub teardown: Vec<Hook>,
+	/// Concrete inputs, such as a fuzzing corpus, to start exploration from.
+	/// Each seed replaces the contents of a symbolic file in a state of its
+	/// own, besides the state that keeps the seed of the recipe.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub seeds: Option<Seeds>,
+	/// Limits on exploration, after which amba shuts QEMU down. Exploration is
+	/// otherwise unlimited, going on until every state has terminated
+	#[serde(default, skip_serializing_if = "Budget::is_unlimited")]
+	pub budget: Budget,
+}
+
+/// The formats that recipes can be written in, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecipeFormat {
+	Json,
+	Toml,
+	Yaml,
+}
+
+impl RecipeFormat {
+	/// The format of the recipe at `path`, defaulting to JSON for unknown
+	/// extensions.
+	pub fn from_path(path: &Path) -> Self {
+		match path.extension().and_then(|extension| extension.to_str()) {
+			Some("toml") => Self::Toml,
+			Some("yaml" | "yml") => Self::Yaml,
+			_ => Self::Json,
+		}
+	}
 }
 
 impl Recipe {
 	pub fn deserialize_from(bytes: &[u8]) -> Result<Self, RecipeError> {
+		Self::deserialize_from_format(bytes, RecipeFormat::Json)
+	}
+
+	/// TOML and YAML recipes are converted to JSON values first, so that all
+	/// formats share the same representation and error reporting.
+	pub fn deserialize_from_format(
+		bytes: &[u8],
+		format: RecipeFormat,
+	) -> Result<Self, RecipeError> {
+		let mut ret = Self::deserialize_as_written(bytes, format)?;
+		ret.normalize();
+		Ok(ret)
+	}
+
+	/// Like [`Recipe::deserialize_from_format`], but keeping symbolic ranges
+	/// in the forms they were written in rather than normalizing them, for
+	/// rewriting recipes.
+	pub fn deserialize_as_written(bytes: &[u8], format: RecipeFormat) -> Result<Self, RecipeError> {
 		let string = std::str::from_utf8(bytes)?;
-		let mut ret: Self = serde_json::from_str(string).map_err(|recipe_err| {
-			match serde_json::from_str::<'_, serde_json::Value>(string) {
-				Ok(_) => RecipeError::NotSyntacticRecipe(recipe_err),
-				Err(json_err) => RecipeError::NotJson(json_err),
+		match format {
+			RecipeFormat::Json => serde_json::from_str(string).map_err(|recipe_err| {
+				match serde_json::from_str::<'_, serde_json::Value>(string) {
+					Ok(_) => RecipeError::NotSyntacticRecipe(recipe_err),
+					Err(json_err) => RecipeError::NotJson(json_err),
+				}
+			}),
+			RecipeFormat::Toml | RecipeFormat::Yaml => {
+				serde_json::from_value(Self::parse_value(string, format)?)
+					.map_err(RecipeError::NotSyntacticRecipe)
 			}
-		})?;
+		}
+	}
 
-		for file in ret.files.values_mut() {
-			match file {
-				FileSource::Host(_) => {}
-				FileSource::SymbolicContent { symbolic, .. }
-				| FileSource::SymbolicHost { symbolic, .. } => SymbolicRange::normalize(symbolic),
-			}
+	/// Parse `string` in the given format into a JSON value that is not yet
+	/// known to be a recipe.
+	fn parse_value(string: &str, format: RecipeFormat) -> Result<serde_json::Value, RecipeError> {
+		match format {
+			RecipeFormat::Json => serde_json::from_str(string).map_err(RecipeError::NotJson),
+			RecipeFormat::Toml => toml::from_str(string).map_err(RecipeError::NotToml),
+			RecipeFormat::Yaml => serde_yaml::from_str(string).map_err(RecipeError::NotYaml),
 		}
-		for argument in &mut ret.arguments {
-			match argument {
-				ArgumentSource::Concrete(_) => {}
-				ArgumentSource::Symbolic { symbolic, .. } => SymbolicRange::normalize(symbolic),
-			}
+	}
+
+	fn normalize(&mut self) {
+		for ranges in self.symbolic_ranges_mut() {
+			SymbolicRange::normalize(ranges);
 		}
-		for env_value in &mut ret.environment.add.values_mut() {
-			match env_value {
-				EnvVarSource::Concrete(_) => {}
-				EnvVarSource::Symbolic { symbolic, .. } => SymbolicRange::normalize(symbolic),
+	}
+
+	pub(crate) fn symbolic_ranges_mut(&mut self) -> impl Iterator<Item = &mut Vec<SymbolicRange>> {
+		let files = self.files.values_mut().filter_map(|file| match file {
+			FileSource::Host(_) | FileSource::HostWithMode { .. } | FileSource::Symlink { .. } => {
+				None
 			}
-		}
-		Ok(ret)
+			FileSource::SymbolicContent { symbolic, .. }
+			| FileSource::SymbolicHost { symbolic, .. } => Some(symbolic),
+		});
+		let arguments = self
+			.arguments
+			.iter_mut()
+			.filter_map(|argument| match argument {
+				ArgumentSource::Concrete(_) => None,
+				ArgumentSource::Symbolic { symbolic, .. } => Some(symbolic),
+			});
+		let environment =
+			self.environment
+				.add
+				.values_mut()
+				.filter_map(|env_value| match env_value {
+					EnvVarSource::Concrete(_) => None,
+					EnvVarSource::Symbolic { symbolic, .. } => Some(symbolic),
+				});
+		files.chain(arguments).chain(environment)
+	}
+
+	/// A JSON Schema describing recipes, for editors to validate and
+	/// autocomplete recipes with. It is checked in as `recipe.schema.json`.
+	pub fn json_schema() -> String {
+		let mut schema = serde_json::to_string_pretty(&schemars::schema_for!(Recipe)).unwrap();
+		schema.push('\n');
+		schema
 	}
 }
 
@@ -53,8 +179,15 @@ impl Recipe {
 pub enum RecipeError {
 	NotUtf8(std::str::Utf8Error),
 	NotJson(serde_json::Error),
+	NotToml(toml::de::Error),
+	NotYaml(serde_yaml::Error),
 	NotSyntacticRecipe(serde_json::Error),
-	NotSemanticRecipe(String),
+	NotSemanticRecipe(Vec<Problem>),
+	Unreadable(PathBuf, io::Error),
+	/// An error in a recipe that another recipe extends
+	InExtended(PathBuf, Box<RecipeError>),
+	CyclicExtend(PathBuf),
+	UndefinedVariables(Vec<String>),
 }
 
 impl From<std::str::Utf8Error> for RecipeError {
@@ -63,42 +196,126 @@ impl From<std::str::Utf8Error> for RecipeError {
 	}
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(remote = "Self", untagged, deny_unknown_fields)]
+#[schemars(rename = "UntaggedFileSource")]
 pub enum FileSource {
 	Host(String),
-	/// Symbolic files are fixed-length, containing arbitrary bytes
+	/// Like `Host`, but with the permissions of the guest file given
+	HostWithMode {
+		host_path: String,
+		mode: FileMode,
+	},
+	/// A symbolic link to `symlink`, which is interpreted relative to the
+	/// directory of the link unless it is absolute. It need not be in `files`.
+	Symlink {
+		symlink: String,
+	},
+	/// Symbolic files contain arbitrary bytes. They are as long as their seed,
+	/// unless `max_length` is given, in which case their length is symbolic
+	/// too. See [`FileSource::max_length`].
 	SymbolicContent {
 		seed: String,
 		#[serde(default)]
 		symbolic: Vec<SymbolicRange>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		max_length: Option<u64>,
+		/// Describes the contents for display, see [`InputLayout`]
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		layout: Option<InputLayout>,
+		/// The permissions of both guest copies of the file
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		mode: Option<FileMode>,
 	},
-	/// Symbolic files are fixed-length, containing arbitrary bytes
+	/// Symbolic files contain arbitrary bytes. They are as long as their seed,
+	/// unless `max_length` is given, in which case their length is symbolic
+	/// too. See [`FileSource::max_length`].
 	SymbolicHost {
 		host_path: String,
 		#[serde(default)]
 		symbolic: Vec<SymbolicRange>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		max_length: Option<u64>,
+		/// Describes the contents for display, see [`InputLayout`]
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		layout: Option<InputLayout>,
+		/// The permissions of both guest copies of the file
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		mode: Option<FileMode>,
 	},
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(remote = "Self", untagged, deny_unknown_fields)]
+#[schemars(rename = "UntaggedArgumentSource")]
 pub enum ArgumentSource {
 	Concrete(String),
-	/// Symbolic arguments are fixed-length, containing bytes 1-255
+	/// Symbolic arguments contain bytes 1-255. They are as long as their seed,
+	/// unless `max_length` is given, in which case their length is symbolic
+	/// too. See [`FileSource::max_length`].
 	Symbolic {
 		seed: String,
 		#[serde(default)]
 		symbolic: Vec<SymbolicRange>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		max_length: Option<u64>,
 	},
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl FileSource {
+	/// The maximum length of a variable-length symbolic file. Bootstrap pads
+	/// the seed with symbolic bytes up to `max_length` and forks once for every
+	/// length in `0..=max_length`, truncating the file accordingly. Symbolic
+	/// ranges may therefore extend up to `max_length` rather than just to the
+	/// end of the seed. Validation limits `max_length` to 4096, and the states
+	/// forked for the lengths of all inputs together to 65536.
+	pub fn max_length(&self) -> Option<u64> {
+		match *self {
+			Self::Host(_) | Self::HostWithMode { .. } | Self::Symlink { .. } => None,
+			Self::SymbolicContent { max_length, .. } | Self::SymbolicHost { max_length, .. } => {
+				max_length
+			}
+		}
+	}
+
+	pub fn layout(&self) -> Option<&InputLayout> {
+		match self {
+			Self::Host(_) | Self::HostWithMode { .. } | Self::Symlink { .. } => None,
+			Self::SymbolicContent { layout, .. } | Self::SymbolicHost { layout, .. } => {
+				layout.as_ref()
+			}
+		}
+	}
+
+	/// The permissions that bootstrap gives the file, if the recipe sets them.
+	/// Files without a mode keep the permissions that `s2ecmd get` gives them.
+	pub fn mode(&self) -> Option<FileMode> {
+		match *self {
+			Self::Host(_) | Self::Symlink { .. } => None,
+			Self::HostWithMode { mode, .. } => Some(mode),
+			Self::SymbolicContent { mode, .. } | Self::SymbolicHost { mode, .. } => mode,
+		}
+	}
+}
+
+impl ArgumentSource {
+	/// The maximum length of a variable-length symbolic argument, with the
+	/// same meaning as [`FileSource::max_length`].
+	pub fn max_length(&self) -> Option<u64> {
+		match *self {
+			Self::Concrete(_) => None,
+			Self::Symbolic { max_length, .. } => max_length,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Environment {
 	pub inherit: bool,
 	#[serde(default)]
 	pub remove: Vec<String>,
 	#[serde(default)]
+	#[schemars(with = "BTreeMap<String, EnvVarSourceForm>")]
 	pub add: BTreeMap<String, EnvVarSource>,
 }
 
@@ -112,8 +329,9 @@ impl Default for Environment {
 	}
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(remote = "Self", untagged, deny_unknown_fields)]
+#[schemars(rename = "UntaggedEnvVarSource")]
 pub enum EnvVarSource {
 	Concrete(String),
 	/// Symbolic env vars are fixed-length, containing bytes 1-255
@@ -123,31 +341,88 @@ pub enum EnvVarSource {
 	},
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A command run by bootstrap, see [`Recipe::setup`].
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
 #[serde(untagged)]
+pub enum Hook {
+	/// A shell command, run with `/bin/sh -c`
+	Shell(String),
+	/// A program and its arguments
+	Exec(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged, deny_unknown_fields)]
 pub enum SymbolicRange {
 	Index(u64),
 	Begin(u64, ()),
 	Range(u64, u64),
+	/// The range `[start, end)`, or `[start, ..)` without an `end`. Unlike
+	/// `Begin`, this can be written in formats without `null`, such as TOML.
+	Span {
+		start: u64,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		end: Option<u64>,
+	},
+	/// A range whose bytes may only take the values allowed by `constraint`.
+	/// Bootstrap and the plugin apply the constraint as a path assumption as
+	/// soon as the bytes are made symbolic.
+	Constrained {
+		range: Box<SymbolicRange>,
+		constraint: ByteConstraint,
+	},
 }
 
 #[allow(clippy::len_without_is_empty)]
 impl SymbolicRange {
-	fn normalize(ranges: &mut Vec<SymbolicRange>) {
-		let mut v: Vec<[u64; 2]> = ranges.iter().map(Self::range).collect();
-		v.sort_unstable_by_key(|&[a, b]| (a, u64::MAX - b));
+	/// Sort and merge overlapping ranges with equal constraints, turning them
+	/// all into `SymbolicRange::Range`, possibly wrapped in a single
+	/// `SymbolicRange::Constrained`.
+	pub fn normalize(ranges: &mut Vec<SymbolicRange>) {
+		let mut v: Vec<([u64; 2], Option<ByteConstraint>)> = ranges
+			.iter()
+			.map(|range| (range.range(), range.constraint().cloned()))
+			.collect();
+		v.sort_unstable_by(|([a0, b0], c0), ([a1, b1], c1)| {
+			c0.cmp(c1)
+				.then(a0.cmp(a1))
+				.then((u64::MAX - b0).cmp(&(u64::MAX - b1)))
+		});
 
-		let mut ret: Vec<[u64; 2]> = Vec::new();
+		let mut ret: Vec<([u64; 2], Option<ByteConstraint>)> = Vec::new();
 		v.into_iter()
 			.for_each(|range| match (ret.last_mut(), range) {
-				(Some([a0, b0]), [a, b]) if a <= *b0 => {
+				(Some(([a0, b0], c0)), ([a, b], c)) if a <= *b0 && *c0 == c => {
 					*a0 = (*a0).min(a);
 					*b0 = (*b0).max(b);
 				}
 				(_, range) => ret.push(range),
 			});
+		ret.sort_by_key(|&([a, _], _)| a);
 		ranges.clear();
-		ranges.extend(ret.into_iter().map(|[a, b]| Self::Range(a, b)));
+		ranges.extend(
+			ret.into_iter()
+				.map(|([a, b], constraint)| match constraint {
+					None => Self::Range(a, b),
+					Some(constraint) => Self::Constrained {
+						range: Box::new(Self::Range(a, b)),
+						constraint,
+					},
+				}),
+		);
+	}
+
+	pub(crate) fn begin_to_span(&mut self) {
+		match self {
+			Self::Begin(start, ()) => {
+				*self = Self::Span {
+					start: *start,
+					end: None,
+				}
+			}
+			Self::Constrained { range, .. } => range.begin_to_span(),
+			Self::Index(_) | Self::Range(..) | Self::Span { .. } => {}
+		}
 	}
 
 	fn range(&self) -> [u64; 2] {
@@ -155,6 +430,8 @@ impl SymbolicRange {
 			Self::Index(a) => [a, a + 1],
 			Self::Begin(a, ()) => [a, u64::MAX],
 			Self::Range(a, b) => [a, b],
+			Self::Span { start, end } => [start, end.unwrap_or(u64::MAX)],
+			Self::Constrained { ref range, .. } => range.range(),
 		}
 	}
 
@@ -167,6 +444,242 @@ impl SymbolicRange {
 			Self::Index(_) => 1,
 			Self::Begin(_, ()) => u64::MAX,
 			Self::Range(a, b) => b - a,
+			Self::Span { start, end } => end.map_or(u64::MAX, |end| end.saturating_sub(*start)),
+			Self::Constrained { range, .. } => range.len(),
+		}
+	}
+
+	/// The constraint on the bytes of this range, if any. The outermost
+	/// constraint wins for nested `SymbolicRange::Constrained`.
+	pub fn constraint(&self) -> Option<&ByteConstraint> {
+		match self {
+			Self::Index(_) | Self::Begin(_, ()) | Self::Range(..) | Self::Span { .. } => None,
+			Self::Constrained { constraint, .. } => Some(constraint),
+		}
+	}
+}
+
+/// The values that the bytes of a [`SymbolicRange::Constrained`] may take.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteConstraint {
+	/// Printable ASCII, `0x20..=0x7e`
+	Printable,
+	/// ASCII digits, `b'0'..=b'9'`
+	Digit,
+	/// Any one of the listed bytes
+	OneOf(Vec<u8>),
+	/// Any byte within the inclusive range
+	InRange(u8, u8),
+}
+
+impl ByteConstraint {
+	/// The allowed bytes as sorted, disjoint and non-adjacent inclusive ranges.
+	pub fn allowed(&self) -> Vec<[u8; 2]> {
+		let mut bytes: Vec<u8> = match self {
+			Self::Printable => (0x20..=0x7E).collect(),
+			Self::Digit => (b'0'..=b'9').collect(),
+			Self::OneOf(bytes) => bytes.clone(),
+			Self::InRange(lo, hi) => (*lo..=*hi).collect(),
+		};
+		bytes.sort_unstable();
+		bytes.dedup();
+
+		let mut ret: Vec<[u8; 2]> = Vec::new();
+		for byte in bytes {
+			match ret.last_mut() {
+				Some([_, hi]) if *hi as u16 + 1 == byte as u16 => *hi = byte,
+				_ => ret.push([byte, byte]),
+			}
+		}
+		ret
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{ByteConstraint, FileSource, Recipe, RecipeError, RecipeFormat, SymbolicRange};
+
+	const JSON: &str = r#"{
+		"files": {
+			"prog": "./prog",
+			"input.txt": { "seed": "abcd", "symbolic": [[0, 2], [3, null]] }
+		},
+		"executable_path": "./prog",
+		"stdin_path": "/tmp/input.txt",
+		"arguments": ["-v", { "seed": "x", "symbolic": [0] }],
+		"environment": { "inherit": false, "add": { "LANG": "C" } }
+	}"#;
+
+	#[test]
+	fn formats_agree() {
+		let toml = r#"
+			# Comments are allowed
+			executable_path = "./prog"
+			stdin_path = "/tmp/input.txt"
+			arguments = ["-v", { seed = "x", symbolic = [0] }]
+
+			[files]
+			prog = "./prog"
+			"input.txt" = { seed = "abcd", symbolic = [[0, 2], { start = 3 }] }
+
+			[environment]
+			inherit = false
+			add = { LANG = "C" }
+		"#;
+		let yaml = r#"
+			# Comments are allowed
+			files:
+			  prog: ./prog
+			  input.txt: { seed: abcd, symbolic: [[0, 2], [3, null]] }
+			executable_path: ./prog
+			stdin_path: /tmp/input.txt
+			arguments: [-v, { seed: x, symbolic: [0] }]
+			environment: { inherit: false, add: { LANG: C } }
+		"#
+		.replace('\t', "");
+		let json = Recipe::deserialize_from(JSON.as_bytes()).unwrap();
+		let json = serde_json::to_value(json).unwrap();
+		for (format, text) in [(RecipeFormat::Toml, toml), (RecipeFormat::Yaml, &*yaml)] {
+			let recipe = Recipe::deserialize_from_format(text.as_bytes(), format).unwrap();
+			assert_eq!(
+				serde_json::to_value(recipe).unwrap(),
+				json,
+				"{format:?}"
+			);
+		}
+	}
+
+	#[test]
+	fn tagged_forms_agree() {
+		let tagged = r#"
+			executable_path = "./prog"
+			stdin_path = "/tmp/input.txt"
+			arguments = [
+				{ kind = "concrete", value = "-v" },
+				{ kind = "symbolic", seed = "x", symbolic = [0] },
+			]
+
+			[files]
+			prog = { kind = "host", host_path = "./prog" }
+			"input.txt" = { kind = "symbolic", seed = "abcd", symbolic = [[0, 2], { start = 3 }] }
+
+			[environment]
+			inherit = false
+			add = { LANG = { kind = "concrete", value = "C" } }
+		"#;
+		let recipe =
+			Recipe::deserialize_from_format(tagged.as_bytes(), RecipeFormat::Toml).unwrap();
+		let json = Recipe::deserialize_from(JSON.as_bytes()).unwrap();
+		assert_eq!(
+			serde_json::to_value(recipe).unwrap(),
+			serde_json::to_value(json).unwrap()
+		);
+
+		let unknown_kind = tagged.replace(
+			r#"kind = "symbolic", seed = "abcd""#,
+			r#"kind = "symbolc", seed = "abcd""#,
+		);
+		let err = Recipe::deserialize_from_format(unknown_kind.as_bytes(), RecipeFormat::Toml)
+			.unwrap_err();
+		let diagnostics = err.diagnostics(&unknown_kind, RecipeFormat::Toml);
+		assert_eq!(diagnostics.len(), 1);
+		assert_eq!(
+			diagnostics[0].message,
+			r#"unknown file kind "symbolc""#
+		);
+		assert_eq!(
+			diagnostics[0].help.as_deref(),
+			Some("did you mean `symbolic`?")
+		);
+	}
+
+	#[test]
+	fn serialize_as_written() {
+		let normalized =
+			serde_json::to_value(Recipe::deserialize_from(JSON.as_bytes()).unwrap()).unwrap();
+		for format in [RecipeFormat::Json, RecipeFormat::Toml, RecipeFormat::Yaml] {
+			let recipe =
+				Recipe::deserialize_as_written(JSON.as_bytes(), RecipeFormat::Json).unwrap();
+			let text = recipe.serialize_to_format(format);
+			let recipe = Recipe::deserialize_as_written(text.as_bytes(), format).unwrap();
+			let symbolic = match &recipe.files["input.txt"] {
+				FileSource::SymbolicContent { symbolic, .. } => symbolic.clone(),
+				_ => panic!("{format:?}"),
+			};
+			let begin = match format {
+				RecipeFormat::Toml => SymbolicRange::Span {
+					start: 3,
+					end: None,
+				},
+				RecipeFormat::Json | RecipeFormat::Yaml => SymbolicRange::Begin(3, ()),
+			};
+			assert_eq!(
+				symbolic,
+				[SymbolicRange::Range(0, 2), begin],
+				"{format:?}"
+			);
+
+			let recipe = Recipe::deserialize_from_format(text.as_bytes(), format).unwrap();
+			assert_eq!(
+				serde_json::to_value(recipe).unwrap(),
+				normalized,
+				"{format:?}"
+			);
 		}
 	}
+
+	#[test]
+	fn unknown_fields_are_rejected() {
+		let typo = JSON.replace(r#""seed": "x""#, r#""seed": "x", "symbolc": [0]"#);
+		assert!(matches!(
+			Recipe::deserialize_from(typo.as_bytes()),
+			Err(RecipeError::NotSyntacticRecipe(_))
+		));
+	}
+
+	#[test]
+	fn schema_is_up_to_date() {
+		assert!(
+			Recipe::json_schema() == include_str!("../recipe.schema.json"),
+			"regenerate with `amba recipe schema > crates/recipe/recipe.schema.json`"
+		);
+	}
+
+	#[test]
+	fn normalize_keeps_constraints_apart() {
+		let digit = |range| SymbolicRange::Constrained {
+			range: Box::new(range),
+			constraint: ByteConstraint::Digit,
+		};
+		let mut ranges = vec![
+			SymbolicRange::Range(6, 8),
+			digit(SymbolicRange::Index(4)),
+			SymbolicRange::Range(0, 4),
+			digit(SymbolicRange::Range(2, 4)),
+			SymbolicRange::Index(5),
+		];
+		SymbolicRange::normalize(&mut ranges);
+		assert_eq!(
+			ranges,
+			[
+				SymbolicRange::Range(0, 4),
+				digit(SymbolicRange::Range(2, 5)),
+				SymbolicRange::Range(5, 8),
+			]
+		);
+	}
+
+	#[test]
+	fn allowed_bytes() {
+		assert_eq!(ByteConstraint::Digit.allowed(), [[b'0', b'9']]);
+		assert_eq!(
+			ByteConstraint::OneOf(vec![3, 1, 2, 2, 9, 255]).allowed(),
+			[[1, 3], [9, 9], [255, 255]]
+		);
+		assert_eq!(
+			ByteConstraint::InRange(0, 0x7F).allowed(),
+			[[0, 0x7F]]
+		);
+	}
 }
@@ -0,0 +1,388 @@
+//! Loading recipes from disk, following `extend` to base recipes and
+//! substituting `${VAR}` placeholders.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use serde::de::Error as _;
+use serde_json::Value;
+
+use crate::{Recipe, RecipeError, RecipeFormat};
+
+impl Recipe {
+	/// Load the recipe at `path`.
+	///
+	/// A recipe may name a base recipe with `"extend": "<path>"`, relative to
+	/// its own directory. The fields of the extending recipe override those
+	/// of the base, with objects such as `files` merged key by key. After
+	/// merging, every `${VAR}` in a string or object key is replaced by the
+	/// value of `VAR` in `variables`, and `$${` is a literal `${`. Relative
+	/// host paths in `files` and `seeds.directory` are relative to the
+	/// directory of the recipe they are written in, so those of a base recipe
+	/// are rebased onto the directory of the recipe at `path`. Host paths
+	/// starting with a placeholder are left as they are.
+	pub fn load(path: &Path, variables: &BTreeMap<String, String>) -> Result<Self, RecipeError> {
+		let bytes = fs::read(path).map_err(|err| RecipeError::Unreadable(path.to_owned(), err))?;
+		let format = RecipeFormat::from_path(path);
+		let string = std::str::from_utf8(&bytes)?;
+		let value = Self::parse_value(string, format)?;
+		if !extends(&value) && !contains_placeholder(&value) {
+			// Deserialize directly for better error messages
+			return Self::deserialize_from_format(&bytes, format);
+		}
+
+		let mut value = resolve_extend(path, value, &mut Vec::new())?;
+		let mut undefined = BTreeSet::new();
+		substitute_value(&mut value, variables, &mut undefined);
+		if !undefined.is_empty() {
+			return Err(RecipeError::UndefinedVariables(
+				undefined.into_iter().collect(),
+			));
+		}
+
+		let mut ret: Self =
+			serde_json::from_value(value).map_err(RecipeError::NotSyntacticRecipe)?;
+		ret.normalize();
+		Ok(ret)
+	}
+
+	/// Whether the recipe in `bytes` names a base recipe with `extend`, so
+	/// can only be deserialized by [`Recipe::load`].
+	pub fn extends(bytes: &[u8], format: RecipeFormat) -> bool {
+		std::str::from_utf8(bytes)
+			.ok()
+			.and_then(|string| Self::parse_value(string, format).ok())
+			.is_some_and(|value| extends(&value))
+	}
+}
+
+fn extends(value: &Value) -> bool {
+	matches!(value, Value::Object(object) if object.contains_key("extend"))
+}
+
+/// Replace the `extend` of the recipe `value` loaded from `path` by the
+/// fields of the base recipe it names, recursively.
+fn resolve_extend(
+	path: &Path,
+	mut value: Value,
+	visiting: &mut Vec<PathBuf>,
+) -> Result<Value, RecipeError> {
+	let canonical = path
+		.canonicalize()
+		.map_err(|err| RecipeError::Unreadable(path.to_owned(), err))?;
+	if visiting.contains(&canonical) {
+		return Err(RecipeError::CyclicExtend(path.to_owned()));
+	}
+	let Some(extend) = value
+		.as_object_mut()
+		.and_then(|object| object.remove("extend"))
+	else {
+		return Ok(value);
+	};
+	let Value::String(base_path) = extend else {
+		return Err(RecipeError::NotSyntacticRecipe(
+			serde_json::Error::custom("`extend` must be the path of a recipe"),
+		));
+	};
+	let base_dir = Path::new(&base_path)
+		.parent()
+		.unwrap_or(Path::new(""))
+		.to_owned();
+	let base_path = path.parent().unwrap_or(Path::new("")).join(base_path);
+
+	visiting.push(canonical);
+	let mut base = load_value(&base_path)
+		.and_then(|base| resolve_extend(&base_path, base, visiting))
+		.map_err(|err| match err {
+			err @ RecipeError::CyclicExtend(_) => err,
+			err => RecipeError::InExtended(base_path.clone(), Box::new(err)),
+		})?;
+	visiting.pop();
+	rebase_host_paths(&mut base, &base_dir);
+
+	let mut merged = base;
+	merge(&mut merged, value);
+	Ok(merged)
+}
+
+/// Prefix the relative host paths of the recipe `value` with `dir`.
+fn rebase_host_paths(value: &mut Value, dir: &Path) {
+	if dir.as_os_str().is_empty() {
+		return;
+	}
+	let rebase = |path: &mut Value| {
+		if let Value::String(string) = path {
+			if Path::new(string.as_str()).is_relative() && !string.starts_with("${") {
+				*string = dir.join(&*string).to_string_lossy().into_owned();
+			}
+		}
+	};
+	let Some(object) = value.as_object_mut() else {
+		return;
+	};
+	if let Some(Value::Object(files)) = object.get_mut("files") {
+		for source in files.values_mut() {
+			match source {
+				Value::String(_) => rebase(source),
+				Value::Object(source) => {
+					if let Some(host_path) = source.get_mut("host_path") {
+						rebase(host_path);
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+	if let Some(directory) = object
+		.get_mut("seeds")
+		.and_then(Value::as_object_mut)
+		.and_then(|seeds| seeds.get_mut("directory"))
+	{
+		rebase(directory);
+	}
+}
+
+fn load_value(path: &Path) -> Result<Value, RecipeError> {
+	let bytes = fs::read(path).map_err(|err| RecipeError::Unreadable(path.to_owned(), err))?;
+	Recipe::parse_value(
+		std::str::from_utf8(&bytes)?,
+		RecipeFormat::from_path(path),
+	)
+}
+
+/// Merge `overrides` into `base`, recursing into objects and replacing
+/// everything else.
+fn merge(base: &mut Value, overrides: Value) {
+	match (base, overrides) {
+		(Value::Object(base), Value::Object(overrides)) => {
+			for (key, value) in overrides {
+				match base.get_mut(&key) {
+					Some(base_value) => merge(base_value, value),
+					None => {
+						base.insert(key, value);
+					}
+				}
+			}
+		}
+		(base, overrides) => *base = overrides,
+	}
+}
+
+fn contains_placeholder(value: &Value) -> bool {
+	match value {
+		Value::String(string) => string.contains("${"),
+		Value::Array(values) => values.iter().any(contains_placeholder),
+		Value::Object(object) => object
+			.iter()
+			.any(|(key, value)| key.contains("${") || contains_placeholder(value)),
+		Value::Null | Value::Bool(_) | Value::Number(_) => false,
+	}
+}
+
+fn substitute_value(
+	value: &mut Value,
+	variables: &BTreeMap<String, String>,
+	undefined: &mut BTreeSet<String>,
+) {
+	match value {
+		Value::String(string) => *string = substitute(string, variables, undefined),
+		Value::Array(values) => {
+			for value in values {
+				substitute_value(value, variables, undefined);
+			}
+		}
+		Value::Object(object) => {
+			*object = std::mem::take(object)
+				.into_iter()
+				.map(|(key, mut value)| {
+					substitute_value(&mut value, variables, undefined);
+					(substitute(&key, variables, undefined), value)
+				})
+				.collect();
+		}
+		Value::Null | Value::Bool(_) | Value::Number(_) => {}
+	}
+}
+
+fn substitute(
+	string: &str,
+	variables: &BTreeMap<String, String>,
+	undefined: &mut BTreeSet<String>,
+) -> String {
+	let mut ret = String::new();
+	let mut rest = string;
+	while let Some(start) = rest.find('$') {
+		ret.push_str(&rest[..start]);
+		rest = &rest[start..];
+		if let Some(after) = rest.strip_prefix("$${") {
+			ret.push_str("${");
+			rest = after;
+		} else if let Some((name, after)) = rest
+			.strip_prefix("${")
+			.and_then(|after| after.split_once('}'))
+		{
+			match variables.get(name) {
+				Some(value) => ret.push_str(value),
+				None => {
+					undefined.insert(name.to_owned());
+				}
+			}
+			rest = after;
+		} else {
+			ret.push('$');
+			rest = &rest[1..];
+		}
+	}
+	ret.push_str(rest);
+	ret
+}
+
+#[cfg(test)]
+mod test {
+	use std::{collections::BTreeMap, fs};
+
+	use crate::{ArgumentSource, FileSource, Recipe, RecipeError, RecipeFormat};
+
+	fn write_recipes(name: &str, recipes: &[(&str, &str)]) -> std::path::PathBuf {
+		let dir = std::env::temp_dir().join(format!(
+			"amba-recipe-{name}-{}",
+			std::process::id()
+		));
+		fs::create_dir_all(&dir).unwrap();
+		for (file, contents) in recipes {
+			fs::write(dir.join(file), contents).unwrap();
+		}
+		dir
+	}
+
+	#[test]
+	fn extend_and_substitute() {
+		let dir = write_recipes(
+			"extend",
+			&[
+				(
+					"base.json",
+					r#"{
+						"files": { "/bin/prog": "prog" },
+						"executable_path": "/bin/prog",
+						"stdin_path": "/dev/null",
+						"arguments": ["--base"]
+					}"#,
+				),
+				(
+					"run.toml",
+					r#"
+						extend = "base.json"
+						stdin_path = "${INPUT}"
+						arguments = ["--mode=${MODE}", "$${HOME}"]
+						[files."${INPUT}"]
+						seed = "abc"
+						symbolic = [0]
+					"#,
+				),
+			],
+		);
+		let variables = [("INPUT", "/tmp/input"), ("MODE", "fast")]
+			.into_iter()
+			.map(|(name, value)| (name.to_owned(), value.to_owned()))
+			.collect();
+		let read = |file: &str| fs::read(dir.join(file)).unwrap();
+		assert!(Recipe::extends(
+			&read("run.toml"),
+			RecipeFormat::Toml
+		));
+		assert!(!Recipe::extends(
+			&read("base.json"),
+			RecipeFormat::Json
+		));
+		let recipe = Recipe::load(&dir.join("run.toml"), &variables).unwrap();
+		assert_eq!(recipe.executable_path, "/bin/prog");
+		assert_eq!(recipe.stdin_path, "/tmp/input");
+		assert!(matches!(
+			recipe.files.get("/bin/prog"),
+			Some(FileSource::Host(_))
+		));
+		assert!(matches!(
+			recipe.files.get("/tmp/input"),
+			Some(FileSource::SymbolicContent { .. })
+		));
+		let arguments: Vec<_> = recipe
+			.arguments
+			.iter()
+			.map(|argument| match argument {
+				ArgumentSource::Concrete(argument) => argument.as_str(),
+				ArgumentSource::Symbolic { .. } => panic!(),
+			})
+			.collect();
+		assert_eq!(arguments, ["--mode=fast", "${HOME}"]);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn extend_rebases_host_paths() {
+		let dir = write_recipes("rebase", &[]);
+		fs::create_dir_all(dir.join("base")).unwrap();
+		fs::write(
+			dir.join("base/base.json"),
+			r#"{
+				"files": {
+					"/bin/prog": "prog",
+					"/etc/conf": { "kind": "symbolic-host", "host_path": "conf" },
+					"/lib/libc.so": "/usr/lib/libc.so",
+					"/in": "${INPUT}"
+				},
+				"executable_path": "/bin/prog",
+				"stdin_path": "/dev/null",
+				"seeds": { "directory": "corpus" }
+			}"#,
+		)
+		.unwrap();
+		fs::write(
+			dir.join("run.json"),
+			r#"{ "extend": "base/base.json", "files": { "/bin/tool": "tool" } }"#,
+		)
+		.unwrap();
+		let variables = [("INPUT".to_owned(), "input".to_owned())].into();
+		let recipe = Recipe::load(&dir.join("run.json"), &variables).unwrap();
+		let host_path = |guest_path: &str| match recipe.files.get(guest_path) {
+			Some(FileSource::Host(host_path) | FileSource::SymbolicHost { host_path, .. }) => {
+				host_path.as_str()
+			}
+			other => panic!("{other:?}"),
+		};
+		assert_eq!(host_path("/bin/prog"), "base/prog");
+		assert_eq!(host_path("/etc/conf"), "base/conf");
+		assert_eq!(host_path("/lib/libc.so"), "/usr/lib/libc.so");
+		assert_eq!(host_path("/in"), "input");
+		assert_eq!(host_path("/bin/tool"), "tool");
+		assert_eq!(recipe.seeds.unwrap().directory, "base/corpus");
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn resolution_errors() {
+		let dir = write_recipes(
+			"errors",
+			&[
+				("a.json", r#"{ "extend": "b.json" }"#),
+				("b.json", r#"{ "extend": "a.json" }"#),
+				(
+					"vars.json",
+					r#"{ "files": {}, "executable_path": "${A}", "stdin_path": "${B}${A}" }"#,
+				),
+			],
+		);
+		assert!(matches!(
+			Recipe::load(&dir.join("a.json"), &BTreeMap::new()),
+			Err(RecipeError::CyclicExtend(_))
+		));
+		match Recipe::load(&dir.join("vars.json"), &BTreeMap::new()) {
+			Err(RecipeError::UndefinedVariables(names)) => assert_eq!(names, ["A", "B"]),
+			other => panic!("{other:?}"),
+		}
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
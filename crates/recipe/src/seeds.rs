@@ -0,0 +1,128 @@
+//! Starting exploration from seeds, concrete inputs such as a fuzzing corpus.
+//!
+//! Amba copies the seed files into the `seeds` directory of the session, named
+//! as the S2E `SeedSearcher` plugin expects. Within the guest, bootstrap asks
+//! the plugin for a seed, which forks a state for every seed it finds. Each of
+//! these runs the analyzed program with the seed in place of the seed of
+//! [`Recipe::seeded_file`], and makes a marker file symbolic whose name, like
+//! those in `inputs.rs`, lets the host tell which seed the state and its
+//! descendants originated from.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{inputs::parse_index_after, Recipe};
+
+const SEED_MARKER: &str = "amba_seed_";
+/// `SeedScheduler` schedules seeds of a priority above 6 as soon as they are
+/// found, rather than only once exploration stops making progress.
+const SEED_PRIORITY: u32 = 7;
+
+/// Where to start exploration from, see [`Recipe::seeds`].
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Seeds {
+	/// A host directory of seed files, relative to the recipe
+	pub directory: String,
+	/// The guest path of the symbolic file whose contents the seeds replace.
+	/// Defaults to the symbolic file that is stdin, or the only symbolic file
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub file: Option<String>,
+}
+
+/// The name of the seed at `index` within the `seeds` directory of a session,
+/// in the `<index>-<priority>.<extension>` form of `SeedSearcher`.
+pub fn seed_file_name(index: usize) -> String {
+	format!("{index}-{SEED_PRIORITY}.seed")
+}
+
+/// The index of a seed named by [`seed_file_name`].
+pub fn seed_file_index(seed_file_name: &str) -> Option<usize> {
+	let (index, _) = seed_file_name.split_once('-')?;
+	index.parse().ok()
+}
+
+/// The guest file that bootstrap makes symbolic in states that start from the
+/// seed at `index`. The analyzed program never reads it.
+pub fn seed_marker_guest_path(index: usize) -> String {
+	format!("/tmp/{SEED_MARKER}{index}")
+}
+
+/// The index of the seed that the S2E symbolic variable `variable` marks, if
+/// it is a seed marker.
+pub fn seed_marker_index(variable: &str) -> Option<usize> {
+	parse_index_after(variable, SEED_MARKER)
+}
+
+/// The index of the seed that a state with the symbolic `variables` originated
+/// from, if any.
+pub fn seed_of(variables: &[(String, Vec<u8>)]) -> Option<usize> {
+	variables
+		.iter()
+		.find_map(|(variable, _)| seed_marker_index(variable))
+}
+
+impl Recipe {
+	/// The key within `files` of the symbolic file whose contents seeds
+	/// replace, if the recipe has seeds.
+	pub fn seeded_file(&self) -> Option<&str> {
+		match &self.seeds.as_ref()?.file {
+			Some(guest_path) => self.symbolic_file(guest_path),
+			None => self.default_symbolic_file(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{seed_file_index, seed_file_name, seed_of, Recipe};
+
+	#[test]
+	fn seeds() {
+		assert_eq!(seed_file_index(&seed_file_name(12)), Some(12));
+		assert_eq!(
+			seed_of(&[
+				(
+					"v0___symfile____tmp_input_0_1_symfile___0".to_owned(),
+					vec![0]
+				),
+				(
+					"v1___symfile____tmp_amba_seed_3_0_1_symfile___1".to_owned(),
+					vec![0]
+				),
+			]),
+			Some(3)
+		);
+
+		let recipe = |seeds: &str| {
+			Recipe::deserialize_from(
+				format!(
+					r#"{{
+						"files": {{
+							"prog": "./prog",
+							"a": {{ "seed": "a", "symbolic": [0] }},
+							"b": {{ "seed": "b", "symbolic": [0] }}
+						}},
+						"executable_path": "./prog",
+						"stdin_path": "/tmp/b",
+						"seeds": {seeds}
+					}}"#
+				)
+				.as_bytes(),
+			)
+			.unwrap()
+		};
+		assert_eq!(
+			recipe(r#"{ "directory": "corpus" }"#).seeded_file(),
+			Some("b")
+		);
+		assert_eq!(
+			recipe(r#"{ "directory": "corpus", "file": "./a" }"#).seeded_file(),
+			Some("a")
+		);
+		assert_eq!(
+			recipe(r#"{ "directory": "corpus", "file": "prog" }"#).seeded_file(),
+			None
+		);
+	}
+}
@@ -0,0 +1,222 @@
+//! Explicitly tagged forms of file, argument and environment variable sources,
+//! such as `{ kind = "host", host_path = "a.out" }`.
+//!
+//! The untagged forms can only be told apart by their keys, so a misspelled
+//! key makes serde give up on every form at once. Recipes may write either
+//! form; both deserialize to the same value, which serializes in the untagged
+//! form.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{ArgumentSource, EnvVarSource, FileMode, FileSource, InputLayout, SymbolicRange};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) enum TaggedFileSource {
+	/// A copy of a host file
+	Host {
+		host_path: String,
+		#[serde(default)]
+		mode: Option<FileMode>,
+	},
+	/// A symbolic link to `target`, see [`FileSource::Symlink`]
+	Symlink { target: String },
+	/// A symbolic file starting out as `seed`
+	Symbolic {
+		seed: String,
+		#[serde(default)]
+		symbolic: Vec<SymbolicRange>,
+		#[serde(default)]
+		max_length: Option<u64>,
+		#[serde(default)]
+		layout: Option<InputLayout>,
+		#[serde(default)]
+		mode: Option<FileMode>,
+	},
+	/// A symbolic file starting out as a copy of a host file
+	SymbolicHost {
+		host_path: String,
+		#[serde(default)]
+		symbolic: Vec<SymbolicRange>,
+		#[serde(default)]
+		max_length: Option<u64>,
+		#[serde(default)]
+		layout: Option<InputLayout>,
+		#[serde(default)]
+		mode: Option<FileMode>,
+	},
+}
+
+impl From<TaggedFileSource> for FileSource {
+	fn from(tagged: TaggedFileSource) -> Self {
+		match tagged {
+			TaggedFileSource::Host {
+				host_path,
+				mode: None,
+			} => Self::Host(host_path),
+			TaggedFileSource::Host {
+				host_path,
+				mode: Some(mode),
+			} => Self::HostWithMode { host_path, mode },
+			TaggedFileSource::Symlink { target } => Self::Symlink { symlink: target },
+			TaggedFileSource::Symbolic {
+				seed,
+				symbolic,
+				max_length,
+				layout,
+				mode,
+			} => Self::SymbolicContent {
+				seed,
+				symbolic,
+				max_length,
+				layout,
+				mode,
+			},
+			TaggedFileSource::SymbolicHost {
+				host_path,
+				symbolic,
+				max_length,
+				layout,
+				mode,
+			} => Self::SymbolicHost {
+				host_path,
+				symbolic,
+				max_length,
+				layout,
+				mode,
+			},
+		}
+	}
+}
+
+/// A [`FileSource`] in either form.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[schemars(rename = "FileSource")]
+pub(crate) enum FileSourceForm {
+	Tagged(TaggedFileSource),
+	Untagged(#[serde(with = "FileSource")] FileSource),
+}
+
+impl<'de> Deserialize<'de> for FileSource {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Ok(match FileSourceForm::deserialize(deserializer)? {
+			FileSourceForm::Tagged(tagged) => tagged.into(),
+			FileSourceForm::Untagged(untagged) => untagged,
+		})
+	}
+}
+
+impl Serialize for FileSource {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		FileSource::serialize(self, serializer)
+	}
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) enum TaggedArgumentSource {
+	Concrete {
+		value: String,
+	},
+	/// See [`ArgumentSource::Symbolic`]
+	Symbolic {
+		seed: String,
+		#[serde(default)]
+		symbolic: Vec<SymbolicRange>,
+		#[serde(default)]
+		max_length: Option<u64>,
+	},
+}
+
+impl From<TaggedArgumentSource> for ArgumentSource {
+	fn from(tagged: TaggedArgumentSource) -> Self {
+		match tagged {
+			TaggedArgumentSource::Concrete { value } => Self::Concrete(value),
+			TaggedArgumentSource::Symbolic {
+				seed,
+				symbolic,
+				max_length,
+			} => Self::Symbolic {
+				seed,
+				symbolic,
+				max_length,
+			},
+		}
+	}
+}
+
+/// An [`ArgumentSource`] in either form.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[schemars(rename = "ArgumentSource")]
+pub(crate) enum ArgumentSourceForm {
+	Tagged(TaggedArgumentSource),
+	Untagged(#[serde(with = "ArgumentSource")] ArgumentSource),
+}
+
+impl<'de> Deserialize<'de> for ArgumentSource {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Ok(
+			match ArgumentSourceForm::deserialize(deserializer)? {
+				ArgumentSourceForm::Tagged(tagged) => tagged.into(),
+				ArgumentSourceForm::Untagged(untagged) => untagged,
+			},
+		)
+	}
+}
+
+impl Serialize for ArgumentSource {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		ArgumentSource::serialize(self, serializer)
+	}
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) enum TaggedEnvVarSource {
+	Concrete {
+		value: String,
+	},
+	/// See [`EnvVarSource::Symbolic`]
+	Symbolic {
+		value: String,
+		symbolic: Vec<SymbolicRange>,
+	},
+}
+
+impl From<TaggedEnvVarSource> for EnvVarSource {
+	fn from(tagged: TaggedEnvVarSource) -> Self {
+		match tagged {
+			TaggedEnvVarSource::Concrete { value } => Self::Concrete(value),
+			TaggedEnvVarSource::Symbolic { value, symbolic } => Self::Symbolic { value, symbolic },
+		}
+	}
+}
+
+/// An [`EnvVarSource`] in either form.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[schemars(rename = "EnvVarSource")]
+pub(crate) enum EnvVarSourceForm {
+	Tagged(TaggedEnvVarSource),
+	Untagged(#[serde(with = "EnvVarSource")] EnvVarSource),
+}
+
+impl<'de> Deserialize<'de> for EnvVarSource {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Ok(
+			match EnvVarSourceForm::deserialize(deserializer)? {
+				EnvVarSourceForm::Tagged(tagged) => tagged.into(),
+				EnvVarSourceForm::Untagged(untagged) => untagged,
+			},
+		)
+	}
+}
+
+impl Serialize for EnvVarSource {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		EnvVarSource::serialize(self, serializer)
+	}
+}
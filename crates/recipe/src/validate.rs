@@ -0,0 +1,796 @@
+//! Semantic validation of a syntactically valid [`Recipe`]
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs,
+	path::Path,
+};
+
+use crate::{
+	diagnostic::Segment, ArgumentSource, EnvVarSource, FieldKind, FieldLength, FileSource, Hook,
+	InputLayout, Recipe, RecipeError, SymbolicRange,
+};
+
+/// Files written into the `hostfiles` directory by amba itself.
+const RESERVED_GUEST_PATHS: &[&str] = &["recipe.json", "bootstrap.elf", "bootstrap.sh"];
+
+/// Bootstrap copies symbolic files here before making them symbolic.
+const SYMBOLIC_FILE_DIR: &str = "/tmp/";
+
+/// The largest `max_length` of a variable-length input. Bootstrap forks once
+/// for every length up to `max_length`, so larger ones exhaust S2E.
+const MAX_LENGTH_LIMIT: u64 = 4096;
+
+/// The most states that bootstrap may fork into for the lengths of all
+/// variable-length inputs together, which multiply.
+const LENGTH_STATES_LIMIT: u64 = 1 << 16;
+
+/// Something wrong with a recipe, found by [`Recipe::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+	pub message: String,
+	/// The field of the recipe that the problem is about, if it is known
+	pub(crate) path: Vec<Segment>,
+}
+
+impl Problem {
+	fn new(path: Vec<Segment>, message: String) -> Self {
+		Self { message, path }
+	}
+}
+
+fn key(key: &str) -> Segment {
+	Segment::Key(key.to_owned())
+}
+
+impl Recipe {
+	/// Check everything that `Recipe::deserialize_from` cannot: that the paths
+	/// refer to files that exist and that symbolic ranges lie within their
+	/// seeds. Host paths are interpreted relative to `recipe_dir`. Every
+	/// problem found is reported, not just the first.
+	pub fn validate(&self, recipe_dir: &Path) -> Result<(), RecipeError> {
+		let mut problems = Vec::new();
+
+		let mut normalized_guest_paths: BTreeMap<&str, &str> = BTreeMap::new();
+		for (guest_path, source) in &self.files {
+			let path = vec![key("files"), key(guest_path)];
+			let normalized = remove_dotslash(guest_path);
+			if normalized.is_empty() {
+				problems.push(Problem::new(
+					path,
+					format!("guest path `{guest_path}` is empty"),
+				));
+				continue;
+			}
+			if guest_path.starts_with('/') {
+				problems.push(Problem::new(
+					path.clone(),
+					format!("guest path `{guest_path}` must be relative"),
+				));
+			} else if normalized.split('/').any(|component| component == "..") {
+				problems.push(Problem::new(
+					path.clone(),
+					format!("guest path `{guest_path}` must not contain `..`"),
+				));
+			} else if normalized
+				.split('/')
+				.any(|component| matches!(component, "" | "."))
+			{
+				problems.push(Problem::new(
+					path.clone(),
+					format!("guest path `{guest_path}` contains an empty or `.` component"),
+				));
+			}
+			if RESERVED_GUEST_PATHS.contains(&normalized) {
+				problems.push(Problem::new(
+					path.clone(),
+					format!("guest path `{guest_path}` is reserved by amba"),
+				));
+			}
+			if let Some(other) = normalized_guest_paths.insert(normalized, guest_path) {
+				problems.push(Problem::new(
+					path.clone(),
+					format!("guest paths `{other}` and `{guest_path}` refer to the same file"),
+				));
+			}
+
+			match source {
+				FileSource::Host(host_path) | FileSource::HostWithMode { host_path, .. } => {
+					check_host_file_exists(
+						&path,
+						recipe_dir,
+						guest_path,
+						host_path,
+						&mut problems,
+					);
+				}
+				FileSource::Symlink { symlink } => {
+					if symlink.is_empty() {
+						let mut path = path.clone();
+						path.push(key("symlink"));
+						problems.push(Problem::new(
+							path,
+							format!("symlink `{guest_path}` has an empty target"),
+						));
+					}
+				}
+				FileSource::SymbolicContent {
+					seed,
+					symbolic,
+					max_length,
+					..
+				} => {
+					let what = format!("file `{guest_path}`");
+					check_ranges(
+						&path,
+						&what,
+						seed.len() as u64,
+						*max_length,
+						symbolic,
+						&mut problems,
+					);
+				}
+				FileSource::SymbolicHost {
+					host_path,
+					symbolic,
+					max_length,
+					..
+				} => {
+					if let Some(len) = check_host_file_exists(
+						&path,
+						recipe_dir,
+						guest_path,
+						host_path,
+						&mut problems,
+					) {
+						let what = format!("file `{guest_path}`");
+						check_ranges(
+							&path,
+							&what,
+							len,
+							*max_length,
+							symbolic,
+							&mut problems,
+						);
+					}
+				}
+			}
+			if let (
+				Some(layout),
+				FileSource::SymbolicContent { symbolic, .. }
+				| FileSource::SymbolicHost { symbolic, .. },
+			) = (source.layout(), source)
+			{
+				check_layout(&path, guest_path, layout, symbolic, &mut problems);
+			}
+		}
+
+		// Every guest directory is created by bootstrap, so no file may be
+		// where a directory is needed.
+		for guest_path in self.files.keys() {
+			let normalized = remove_dotslash(guest_path);
+			let mut dirs = normalized.match_indices('/').map(|(i, _)| &normalized[..i]);
+			if let Some(dir) = dirs.find(|dir| normalized_guest_paths.contains_key(dir)) {
+				problems.push(Problem::new(
+					vec![key("files"), key(guest_path)],
+					format!(
+						"guest path `{guest_path}` is within `{}`, which is not a directory",
+						normalized_guest_paths[dir]
+					),
+				));
+			}
+		}
+
+		// Absolute guest paths are interpreted as already existing within the
+		// guest, as are the targets of symlinks that lead out of `files`.
+		if !self.executable_path.starts_with('/') {
+			let executable = remove_dotslash(&self.executable_path);
+			match (
+				self.files.get(executable),
+				self.guest_file(executable),
+			) {
+				(None, _) => problems.push(Problem::new(
+					vec![key("executable_path")],
+					format!(
+						"executable_path `{}` matches no guest file in `files` and is not an absolute guest path",
+						self.executable_path
+					),
+				)),
+				(Some(_), Some((_, FileSource::HostWithMode { mode, .. })))
+					if !mode.is_executable() =>
+				{
+					problems.push(Problem::new(
+						vec![key("executable_path")],
+						format!(
+							"executable_path `{}` refers to a file with mode {mode}, which is not executable",
+							self.executable_path
+						),
+					));
+				}
+				(
+					Some(_),
+					Some((_, FileSource::SymbolicContent { .. } | FileSource::SymbolicHost { .. })),
+				) => {
+					problems.push(Problem::new(
+						vec![key("executable_path")],
+						format!(
+							"executable_path `{}` refers to a symbolic file",
+							self.executable_path
+						),
+					));
+				}
+				(Some(_), _) => {}
+			}
+		}
+
+		let stdin_path = remove_dotslash(&self.stdin_path);
+		match stdin_path.strip_prefix(SYMBOLIC_FILE_DIR) {
+			Some(symbolic_path) => match self.files.get(symbolic_path) {
+				Some(FileSource::SymbolicContent { .. } | FileSource::SymbolicHost { .. }) => {}
+				Some(
+					FileSource::Host(_)
+					| FileSource::HostWithMode { .. }
+					| FileSource::Symlink { .. },
+				)
+				| None => problems.push(Problem::new(
+					vec![key("stdin_path")],
+					format!(
+						"stdin_path `{}` refers to no symbolic file in `files`",
+						self.stdin_path
+					),
+				)),
+			},
+			None => match self
+				.files
+				.get(stdin_path)
+				.map(|_| self.guest_file(stdin_path))
+			{
+				None => problems.push(Problem::new(
+					vec![key("stdin_path")],
+					format!(
+						"stdin_path `{}` refers to no file in `files`",
+						self.stdin_path
+					),
+				)),
+				Some(Some((
+					target,
+					FileSource::SymbolicContent { .. } | FileSource::SymbolicHost { .. },
+				))) => {
+					problems.push(Problem::new(
+						vec![key("stdin_path")],
+						format!(
+							"stdin_path `{}` refers to the concrete copy of a symbolic file, use `{SYMBOLIC_FILE_DIR}{target}` instead",
+							self.stdin_path
+						),
+					));
+				}
+				Some(_) => {}
+			},
+		}
+
+		for (i, argument) in self.arguments.iter().enumerate() {
+			match argument {
+				ArgumentSource::Concrete(_) => {}
+				ArgumentSource::Symbolic {
+					seed,
+					symbolic,
+					max_length,
+				} => {
+					let path = vec![key("arguments"), Segment::Index(i)];
+					let what = format!("argument {}", i + 1);
+					if seed.contains('\0') {
+						let mut path = path.clone();
+						path.push(key("seed"));
+						problems.push(Problem::new(
+							path,
+							format!("seed of {what} contains a NUL byte"),
+						));
+					}
+					check_ranges(
+						&path,
+						&what,
+						seed.len() as u64,
+						*max_length,
+						symbolic,
+						&mut problems,
+					);
+				}
+			}
+		}
+		for (name, value) in &self.environment.add {
+			match value {
+				EnvVarSource::Concrete(_) => {}
+				EnvVarSource::Symbolic { value, symbolic } => {
+					let path = vec![key("environment"), key("add"), key(name)];
+					let what = format!("environment variable `{name}`");
+					if value.contains('\0') {
+						let mut path = path.clone();
+						path.push(key("value"));
+						problems.push(Problem::new(
+							path,
+							format!("value of {what} contains a NUL byte"),
+						));
+					}
+					check_ranges(
+						&path,
+						&what,
+						value.len() as u64,
+						None,
+						symbolic,
+						&mut problems,
+					);
+				}
+			}
+		}
+
+		// Inputs over `MAX_LENGTH_LIMIT` have been reported on their own
+		let length_states = self
+			.files
+			.values()
+			.filter_map(FileSource::max_length)
+			.chain(self.arguments.iter().filter_map(ArgumentSource::max_length))
+			.filter(|&max_length| max_length <= MAX_LENGTH_LIMIT)
+			.fold(1, |states: u64, max_length| {
+				states.saturating_mul(max_length + 1)
+			});
+		if length_states > LENGTH_STATES_LIMIT {
+			problems.push(Problem::new(
+				Vec::new(),
+				format!(
+					"bootstrap forks into {length_states} states for the lengths of the variable-length inputs, more than the limit of {LENGTH_STATES_LIMIT}"
+				),
+			));
+		}
+
+		for (what, hooks) in [("setup", &self.setup), ("teardown", &self.teardown)] {
+			for (i, hook) in hooks.iter().enumerate() {
+				let empty = match hook {
+					Hook::Shell(command) => command.trim().is_empty(),
+					Hook::Exec(argv) => argv.is_empty(),
+				};
+				if empty {
+					problems.push(Problem::new(
+						vec![key(what), Segment::Index(i)],
+						format!("{what} command {} is empty", i + 1),
+					));
+				}
+			}
+		}
+
+		for range in self.constrained_ranges() {
+			if range.allowed().is_empty() {
+				problems.push(Problem::new(
+					range.recipe_path(),
+					format!(
+						"constraint {:?} of symbolic range {} allows no bytes{}",
+						range.constraint(),
+						range.name(),
+						match range.nul_terminated {
+							true => " other than NUL",
+							false => "",
+						}
+					),
+				));
+			}
+		}
+
+		if let Some(seeds) = &self.seeds {
+			if !recipe_dir.join(&seeds.directory).is_dir() {
+				problems.push(Problem::new(
+					vec![key("seeds"), key("directory")],
+					format!(
+						"seeds directory `{}` is not a directory",
+						seeds.directory
+					),
+				));
+			}
+			match (&seeds.file, self.seeded_file()) {
+				(_, Some(_)) => {}
+				(Some(file), None) => problems.push(Problem::new(
+					vec![key("seeds"), key("file")],
+					format!("seeds file `{file}` refers to no symbolic file in `files`"),
+				)),
+				(None, None) => problems.push(Problem::new(
+					vec![key("seeds")],
+					"seeds need a symbolic file to replace, but stdin is not symbolic and there is not exactly one symbolic file, choose one with `file`"
+						.to_owned(),
+				)),
+			}
+		}
+
+		if self.budget.max_states == Some(0) {
+			problems.push(Problem::new(
+				vec![key("budget"), key("max_states")],
+				"budget max_states must allow at least one state".to_owned(),
+			));
+		}
+
+		match problems.is_empty() {
+			true => Ok(()),
+			false => Err(RecipeError::NotSemanticRecipe(problems)),
+		}
+	}
+}
+
+/// Strip any number of leading `./` from a guest path.
+pub(crate) fn remove_dotslash(mut guest_path: &str) -> &str {
+	while let Some(stripped) = guest_path.strip_prefix("./") {
+		guest_path = stripped;
+	}
+	guest_path
+}
+
+/// Returns the length of the host file, if it exists.
+fn check_host_file_exists(
+	path: &[Segment],
+	recipe_dir: &Path,
+	guest_path: &str,
+	host_path: &str,
+	problems: &mut Vec<Problem>,
+) -> Option<u64> {
+	match fs::metadata(recipe_dir.join(host_path)) {
+		Ok(metadata) if metadata.is_file() => Some(metadata.len()),
+		Ok(_) => {
+			problems.push(Problem::new(
+				path.to_vec(),
+				format!("host path `{host_path}` for guest path `{guest_path}` is not a file"),
+			));
+			None
+		}
+		Err(err) => {
+			problems.push(Problem::new(
+				path.to_vec(),
+				format!(
+					"host path `{host_path}` for guest path `{guest_path}` cannot be read: {err}"
+				),
+			));
+			None
+		}
+	}
+}
+
+/// Check that the `ranges` lie within a seed of length `seed_len`, or within
+/// `max_length` for variable-length inputs. `path` is the input within the
+/// recipe.
+fn check_ranges(
+	path: &[Segment],
+	what: &str,
+	seed_len: u64,
+	max_length: Option<u64>,
+	ranges: &[SymbolicRange],
+	problems: &mut Vec<Problem>,
+) {
+	let field = |name: &str| {
+		let mut path = path.to_vec();
+		path.push(key(name));
+		path
+	};
+	let (len, bound) = match max_length {
+		Some(max_length) if max_length < seed_len => {
+			problems.push(Problem::new(
+				field("max_length"),
+				format!(
+					"max_length {max_length} of {what} is shorter than its seed of length {seed_len}"
+				),
+			));
+			(seed_len, "seed")
+		}
+		Some(max_length) if max_length > MAX_LENGTH_LIMIT => {
+			problems.push(Problem::new(
+				field("max_length"),
+				format!(
+					"max_length {max_length} of {what} is longer than the limit of {MAX_LENGTH_LIMIT}, as bootstrap forks once for every length"
+				),
+			));
+			(max_length, "max_length")
+		}
+		Some(max_length) => (max_length, "max_length"),
+		None => (seed_len, "seed"),
+	};
+	let mut previous_end = 0;
+	for range in ranges {
+		let [start, end] = range.range();
+		// Normalized ranges are sorted, and only overlap when their
+		// constraints differ.
+		if start < previous_end {
+			problems.push(Problem::new(
+				field("symbolic"),
+				format!(
+					"symbolic range {} of {what} overlaps a range with a different constraint",
+					format_range(start, end),
+				),
+			));
+		}
+		previous_end = previous_end.max(end);
+		if end == u64::MAX {
+			if start >= len {
+				problems.push(Problem::new(
+					field("symbolic"),
+					format!(
+						"symbolic range [{start}, ..) of {what} starts outside its {bound} of length {len}"
+					),
+				));
+			}
+		} else if start >= end {
+			problems.push(Problem::new(
+				field("symbolic"),
+				format!("symbolic range [{start}, {end}) of {what} is empty"),
+			));
+		} else if end > len {
+			problems.push(Problem::new(
+				field("symbolic"),
+				format!(
+					"symbolic range [{start}, {end}) of {what} extends past its {bound} of length {len}"
+				),
+			));
+		}
+	}
+}
+
+/// A layout is decoded from a single symbolic variable, so it must cover the
+/// file from its start with a single unconstrained range.
+fn check_layout(
+	path: &[Segment],
+	guest_path: &str,
+	layout: &InputLayout,
+	symbolic: &[SymbolicRange],
+	problems: &mut Vec<Problem>,
+) {
+	let mut path = path.to_vec();
+	path.push(key("layout"));
+	match symbolic {
+		[] => {}
+		[range] if range.start() == 0 && range.constraint().is_none() => {}
+		_ => problems.push(Problem::new(
+			path.clone(),
+			format!(
+				"file `{guest_path}` has a layout, so it must be symbolic as a single unconstrained range starting at 0"
+			),
+		)),
+	}
+	let field_path = |i: usize| {
+		let mut path = path.clone();
+		path.push(Segment::Index(i));
+		path
+	};
+	let mut integer_fields = BTreeSet::new();
+	for (i, field) in layout.fields.iter().enumerate() {
+		if field.name.is_empty() {
+			problems.push(Problem::new(
+				field_path(i),
+				format!("field {i} of the layout of `{guest_path}` has an empty name"),
+			));
+		} else if layout.fields[..i]
+			.iter()
+			.any(|earlier| earlier.name == field.name)
+		{
+			problems.push(Problem::new(
+				field_path(i),
+				format!(
+					"field `{}` of the layout of `{guest_path}` is defined more than once",
+					field.name
+				),
+			));
+		}
+		match &field.kind {
+			FieldKind::String(FieldLength::Field(length))
+			| FieldKind::Bytes(FieldLength::Field(length)) => {
+				if !integer_fields.contains(&**length) {
+					problems.push(Problem::new(
+						field_path(i),
+						format!(
+							"field `{}` of the layout of `{guest_path}` takes its length from `{length}`, which is no earlier integer field",
+							field.name
+						),
+					));
+				}
+			}
+			kind => {
+				if kind.integer().is_some() {
+					integer_fields.insert(&*field.name);
+				}
+			}
+		}
+	}
+}
+
+fn format_range(start: u64, end: u64) -> String {
+	match end {
+		u64::MAX => format!("[{start}, ..)"),
+		end => format!("[{start}, {end})"),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::path::Path;
+
+	use crate::{diagnostic::Segment, Recipe, RecipeError};
+
+	/// The problems with the recipe `json`, with their paths written like JSON
+	/// pointers.
+	fn problems(json: &str) -> Vec<(String, String)> {
+		let recipe = Recipe::deserialize_from(json.as_bytes()).unwrap();
+		match recipe.validate(Path::new("/nonexistent")) {
+			Ok(()) => Vec::new(),
+			Err(RecipeError::NotSemanticRecipe(problems)) => problems
+				.into_iter()
+				.map(|problem| {
+					let path = problem
+						.path
+						.iter()
+						.map(|segment| match segment {
+							Segment::Key(key) => format!("/{key}"),
+							Segment::Index(index) => format!("/{index}"),
+						})
+						.collect();
+					(path, problem.message)
+				})
+				.collect(),
+			Err(err) => panic!("unexpected error {err:?}"),
+		}
+	}
+
+	#[test]
+	fn valid_recipe() {
+		let problems = problems(
+			r#"{
+				"files": {
+					"input.txt": { "seed": "aaaa", "symbolic": [[0, 4]] },
+					"conf/app.ini": { "seed": "[app]", "mode": "444" },
+					"bin/grep": { "symlink": "/usr/bin/grep" }
+				},
+				"executable_path": "./bin/grep",
+				"stdin_path": "/tmp/input.txt",
+				"arguments": [
+					"x",
+					{ "seed": "yy", "symbolic": [1] },
+					{ "seed": "z", "symbolic": [[0, 4]], "max_length": 4 }
+				],
+				"setup": ["mkdir -p data", ["touch", "data/x"]],
+				"teardown": ["rm -r data"]
+			}"#,
+		);
+		assert_eq!(problems, []);
+	}
+
+	#[test]
+	fn reports_every_problem() {
+		let problems = problems(
+			r#"{
+				"files": {
+					"prog": "./prog",
+					"short": { "seed": "abc", "max_length": 2 },
+					"long": { "seed": "abc", "max_length": 1000000 },
+					"layout": {
+						"seed": "abcd",
+						"symbolic": [1],
+						"layout": [{ "name": "s", "type": { "string": "n" } }]
+					},
+					"overlap": {
+						"seed": "abc",
+						"symbolic": [[0, 2], { "range": 1, "constraint": { "one_of": [] } }]
+					},
+					"./input.txt": { "seed": "aaaa", "symbolic": [[2, 6]] },
+					"input.txt": { "seed": "a", "symbolic": [[3, null]] },
+					"/abs": { "seed": "a" },
+					"dir/": { "seed": "a" },
+					"prog/nested": { "seed": "a" },
+					"link": { "symlink": "" },
+					"script": { "host_path": "./script", "mode": "644" },
+					"run": { "symlink": "script" }
+				},
+				"executable_path": "./run",
+				"stdin_path": "input.txt",
+				"setup": ["true", " "],
+				"teardown": [[]],
+				"seeds": { "directory": "missing", "file": "prog" },
+				"budget": { "max_states": 0 }
+			}"#,
+		);
+		let expected = [
+			(
+				"/files/./input.txt/symbolic",
+				"symbolic range [2, 6) of file `./input.txt` extends past its seed of length 4",
+			),
+			("/files//abs", "guest path `/abs` must be relative"),
+			(
+				"/files/dir/",
+				"guest path `dir/` contains an empty or `.` component",
+			),
+			(
+				"/files/input.txt",
+				"guest paths `./input.txt` and `input.txt` refer to the same file",
+			),
+			(
+				"/files/input.txt/symbolic",
+				"symbolic range [3, ..) of file `input.txt` starts outside its seed of length 1",
+			),
+			(
+				"/files/layout/layout",
+				"file `layout` has a layout, so it must be symbolic as a single unconstrained range starting at 0",
+			),
+			(
+				"/files/layout/layout/0",
+				"field `s` of the layout of `layout` takes its length from `n`, which is no earlier integer field",
+			),
+			("/files/link/symlink", "symlink `link` has an empty target"),
+			(
+				"/files/long/max_length",
+				"max_length 1000000 of file `long` is longer than the limit of 4096, as bootstrap forks once for every length",
+			),
+			(
+				"/files/overlap/symbolic",
+				"symbolic range [1, 2) of file `overlap` overlaps a range with a different constraint",
+			),
+			(
+				"/files/prog",
+				"host path `./prog` for guest path `prog` cannot be read: No such file or directory (os error 2)",
+			),
+			(
+				"/files/script",
+				"host path `./script` for guest path `script` cannot be read: No such file or directory (os error 2)",
+			),
+			(
+				"/files/short/max_length",
+				"max_length 2 of file `short` is shorter than its seed of length 3",
+			),
+			(
+				"/files/prog/nested",
+				"guest path `prog/nested` is within `prog`, which is not a directory",
+			),
+			(
+				"/executable_path",
+				"executable_path `./run` refers to a file with mode 644, which is not executable",
+			),
+			(
+				"/stdin_path",
+				"stdin_path `input.txt` refers to the concrete copy of a symbolic file, use `/tmp/input.txt` instead",
+			),
+			("/setup/1", "setup command 2 is empty"),
+			("/teardown/0", "teardown command 1 is empty"),
+			(
+				"/files/overlap/symbolic",
+				"constraint OneOf([]) of symbolic range overlap[1..2] allows no bytes",
+			),
+			(
+				"/seeds/directory",
+				"seeds directory `missing` is not a directory",
+			),
+			(
+				"/seeds/file",
+				"seeds file `prog` refers to no symbolic file in `files`",
+			),
+			(
+				"/budget/max_states",
+				"budget max_states must allow at least one state",
+			),
+		];
+		assert_eq!(
+			problems,
+			expected.map(|(path, message)| (path.to_owned(), message.to_owned()))
+		);
+	}
+
+	#[test]
+	fn limits_length_states() {
+		let recipe = |max_length: u64| {
+			format!(
+				r#"{{
+					"files": {{ "input.txt": {{ "seed": "", "max_length": {max_length} }} }},
+					"executable_path": "/bin/cat",
+					"stdin_path": "/tmp/input.txt",
+					"arguments": [{{ "seed": "", "max_length": {max_length} }}]
+				}}"#
+			)
+		};
+		assert_eq!(problems(&recipe(255)), []);
+		assert_eq!(
+			problems(&recipe(256)),
+			[(
+				String::new(),
+				"bootstrap forks into 66049 states for the lengths of the variable-length inputs, more than the limit of 65536".to_owned()
+			)]
+		);
+	}
+}